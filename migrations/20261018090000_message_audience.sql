@@ -0,0 +1,21 @@
+-- Add migration script here
+alter table notification_subscriber
+    add column tags text[] not null default '{}';
+
+alter table notification_message
+    add column group_id        bigint references notification_group (id) on delete set null,
+    add column recipient_count bigint not null default 0;
+
+create table if not exists notification_recipient
+(
+    id         bigint generated by default as identity,
+    message_id bigint      not null references notification_message (id) on delete cascade,
+    user_id    bigint      not null default 0,
+    created_at timestamptz not null default current_timestamp
+);
+
+alter table notification_recipient
+    add constraint notification_recipient_id_pk primary key (id);
+
+alter table notification_recipient
+    add constraint notification_recipient_message_user_uq unique (message_id, user_id);
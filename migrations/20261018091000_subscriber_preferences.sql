@@ -0,0 +1,19 @@
+-- Add migration script here
+alter table notification_subscriber
+    add column muted_until        timestamptz,
+    add column opted_out_channels text[] not null default '{}',
+    add column delivery_channels  text[] not null default '{in_app}';
+
+create table if not exists notification_delivery
+(
+    id               bigint generated by default as identity,
+    message_id       bigint      not null references notification_message (id) on delete cascade,
+    user_id          bigint      not null default 0,
+    delivery_channel varchar     not null default '',
+    delivered_at     timestamptz,
+    created_at       timestamptz not null default current_timestamp,
+    updated_at       timestamptz not null default current_timestamp
+);
+
+alter table notification_delivery
+    add constraint notification_delivery_id_pk primary key (id);
@@ -0,0 +1,52 @@
+-- Add migration script here
+alter table notification_message
+    add column priority varchar not null default 'normal';
+
+alter table notification_message
+    add constraint notification_message_priority_ck
+        check (priority in ('low', 'normal', 'high', 'urgent'));
+
+alter table notification_delivery
+    add column deliver_after timestamptz not null default current_timestamp;
+
+create table if not exists notification_quiet_hours
+(
+    id         bigint generated by default as identity,
+    user_id    bigint      not null default 0 unique,
+    start_time time        not null,
+    end_time   time        not null,
+    timezone   varchar     not null default 'UTC',
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp
+);
+
+alter table notification_quiet_hours
+    add constraint notification_quiet_hours_id_pk primary key (id);
+
+-- Returns the end of the quiet hours window that `at` falls into, or `at`
+-- itself when it is outside of the window. Windows may wrap past midnight.
+create or replace function notification_quiet_hours_end(
+    start_time time,
+    end_time   time,
+    timezone   varchar,
+    at         timestamptz
+) returns timestamptz as
+$$
+declare
+    local_at   timestamp := at at time zone timezone;
+    local_time time      := local_at::time;
+begin
+    if start_time < end_time then
+        if local_time >= start_time and local_time < end_time then
+            return (local_at::date + end_time) at time zone timezone;
+        end if;
+    elsif start_time > end_time then
+        if local_time >= start_time then
+            return (local_at::date + 1 + end_time) at time zone timezone;
+        elsif local_time < end_time then
+            return (local_at::date + end_time) at time zone timezone;
+        end if;
+    end if;
+    return at;
+end;
+$$ language plpgsql stable;
@@ -0,0 +1,34 @@
+-- Add migration script here
+alter table notification_subscriber
+    add column digest_mode    varchar not null default 'off',
+    add column last_digest_at timestamptz;
+
+alter table notification_subscriber
+    add constraint notification_subscriber_digest_mode_ck
+        check (digest_mode in ('off', 'daily', 'weekly'));
+
+alter table notification_recipient
+    add column digested_at timestamptz;
+
+create table if not exists notification_digest
+(
+    id            bigint generated by default as identity,
+    user_id       bigint      not null default 0,
+    group_id      bigint      not null references notification_group (id) on delete cascade,
+    subject       varchar     not null default '',
+    body          varchar     not null default '',
+    message_count bigint      not null default 0,
+    created_at    timestamptz not null default current_timestamp,
+    updated_at    timestamptz not null default current_timestamp
+);
+
+alter table notification_digest
+    add constraint notification_digest_id_pk primary key (id);
+
+alter table notification_delivery
+    alter column message_id drop not null,
+    add column digest_id bigint references notification_digest (id) on delete cascade;
+
+alter table notification_delivery
+    add constraint notification_delivery_source_ck
+        check ((message_id is null) <> (digest_id is null));
@@ -0,0 +1,9 @@
+-- Add migration script here
+delete from notification_subscriber as ns
+using notification_subscriber as duplicate
+where ns.user_id = duplicate.user_id
+and ns.group_id = duplicate.group_id
+and ns.id > duplicate.id;
+
+alter table notification_subscriber
+    add constraint notification_subscriber_user_group_uq unique (user_id, group_id);
@@ -0,0 +1,6 @@
+-- Add migration script here
+create index notification_subscriber_group_created_at_idx
+on notification_subscriber (group_id, created_at, id);
+
+create index notification_subscriber_group_user_id_idx
+on notification_subscriber (group_id, user_id);
@@ -0,0 +1,3 @@
+-- Add migration script here
+alter table notification_group
+add column description varchar not null default '';
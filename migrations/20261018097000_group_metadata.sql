@@ -0,0 +1,5 @@
+-- Add migration script here
+alter table notification_group
+add column icon_url varchar not null default '',
+add column visibility varchar not null default 'public'
+    check (visibility in ('public', 'private'));
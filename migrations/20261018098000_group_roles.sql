@@ -0,0 +1,26 @@
+-- Add migration script here
+create table if not exists notification_group_role
+(
+    id         bigint generated by default as identity,
+    group_id   bigint      not null references notification_group (id) on delete cascade,
+    user_id    bigint,
+    email      varchar,
+    role       varchar     not null default 'viewer'
+        check (role in ('owner', 'admin', 'publisher', 'viewer')),
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp,
+    check ((user_id is null) <> (email is null)),
+    unique (group_id, user_id),
+    unique (group_id, email)
+);
+
+alter table notification_group_role
+    add constraint notification_group_role_id_pk primary key (id);
+
+create unique index notification_group_role_owner_idx
+on notification_group_role (group_id)
+where role = 'owner';
+
+insert into notification_group_role (group_id, email, role)
+select id, admin_email, 'owner'
+from notification_group;
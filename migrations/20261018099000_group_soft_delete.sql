@@ -0,0 +1,11 @@
+-- Add migration script here
+alter table notification_group
+add column deleted_at timestamptz;
+
+-- the name of a deleted group is free to reuse during its grace period
+alter table notification_group
+drop constraint notification_group_name_key;
+
+create unique index notification_group_name_idx
+on notification_group (name)
+where deleted_at is null;
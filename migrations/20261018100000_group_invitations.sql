@@ -0,0 +1,45 @@
+-- Add migration script here
+alter table notification_group
+drop constraint notification_group_visibility_check;
+
+update notification_group
+set visibility = 'invite_only'
+where visibility = 'private';
+
+alter table notification_group
+add constraint notification_group_visibility_check
+    check (visibility in ('public', 'invite_only', 'closed'));
+
+create table if not exists notification_invitation
+(
+    id         bigint generated by default as identity,
+    group_id   bigint      not null references notification_group (id) on delete cascade,
+    code       varchar     not null default replace(gen_random_uuid()::text, '-', '') unique,
+    expires_at timestamptz,
+    max_uses   integer,
+    use_count  integer     not null default 0,
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp
+);
+
+alter table notification_invitation
+    add constraint notification_invitation_id_pk primary key (id);
+
+create table if not exists notification_join_request
+(
+    id         bigint generated by default as identity,
+    group_id   bigint      not null references notification_group (id) on delete cascade,
+    user_id    bigint      not null default 0,
+    status     varchar     not null default 'pending'
+        check (status in ('pending', 'approved', 'rejected')),
+    decided_at timestamptz,
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp
+);
+
+alter table notification_join_request
+    add constraint notification_join_request_id_pk primary key (id);
+
+create unique index notification_join_request_pending_idx
+on notification_join_request (group_id, user_id)
+where status = 'pending';
@@ -0,0 +1,7 @@
+-- Add migration script here
+alter table notification_group
+add column parent_id bigint references notification_group (id) on delete set null,
+add constraint notification_group_parent_check check (parent_id <> id);
+
+create index notification_group_parent_id_idx
+on notification_group (parent_id);
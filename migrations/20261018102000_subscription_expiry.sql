@@ -0,0 +1,7 @@
+-- Add migration script here
+alter table notification_subscriber
+add column expires_at timestamptz;
+
+create index notification_subscriber_expires_at_idx
+on notification_subscriber (expires_at)
+where expires_at is not null;
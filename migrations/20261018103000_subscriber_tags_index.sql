@@ -0,0 +1,3 @@
+-- Add migration script here
+create index notification_subscriber_tags_idx
+on notification_subscriber using gin (tags);
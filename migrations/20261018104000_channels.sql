@@ -0,0 +1,30 @@
+-- Add migration script here
+create table if not exists notification_channel
+(
+    id               bigint generated by default as identity,
+    group_id         bigint      not null references notification_group (id) on delete cascade,
+    name             varchar     not null unique,
+    description      varchar     not null default '',
+    default_priority varchar     not null default 'normal',
+    retention_days   integer,
+    created_at       timestamptz not null default current_timestamp,
+    updated_at       timestamptz not null default current_timestamp
+);
+
+alter table notification_channel
+    add constraint notification_channel_id_pk primary key (id),
+    add constraint notification_channel_default_priority_ck
+        check (default_priority in ('low', 'normal', 'high', 'urgent')),
+    add constraint notification_channel_retention_days_ck
+        check (retention_days > 0);
+
+create index notification_channel_group_id_idx
+on notification_channel (group_id);
+
+-- channels already in use are registered to the group that last wrote to them
+insert into notification_channel (group_id, name)
+select distinct on (channel) group_id, channel
+from notification_message
+where group_id is not null
+order by channel, created_at desc
+on conflict (name) do nothing;
@@ -0,0 +1,13 @@
+-- Add migration script here
+create table if not exists notification_channel_subscription
+(
+    id         bigint generated by default as identity,
+    user_id    bigint      not null,
+    pattern    varchar     not null,
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp,
+    unique (user_id, pattern)
+);
+
+alter table notification_channel_subscription
+    add constraint notification_channel_subscription_id_pk primary key (id);
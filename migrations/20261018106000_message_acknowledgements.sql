@@ -0,0 +1,13 @@
+-- Add migration script here
+alter table notification_message
+    add column if not exists acknowledged_by      bigint,
+    add column if not exists acknowledged_at      timestamptz,
+    add column if not exists acknowledgement_note varchar;
+
+alter table notification_message
+    add constraint notification_message_acknowledgement_ck
+        check ((acknowledged_by is null) = (acknowledged_at is null));
+
+create index notification_message_unacknowledged_idx
+on notification_message (channel)
+where acknowledged_at is null;
@@ -0,0 +1,27 @@
+-- Add migration script here
+create table if not exists notification_escalation_step
+(
+    id               bigint generated by default as identity,
+    group_id         bigint      not null references notification_group (id) on delete cascade,
+    step             integer     not null,
+    delay_minutes    integer     not null,
+    delivery_channel varchar,
+    target_group_id  bigint references notification_group (id) on delete cascade,
+    created_at       timestamptz not null default current_timestamp,
+    updated_at       timestamptz not null default current_timestamp,
+    unique (group_id, step)
+);
+
+alter table notification_escalation_step
+    add constraint notification_escalation_step_id_pk primary key (id),
+    add constraint notification_escalation_step_delay_minutes_ck
+        check (delay_minutes > 0),
+    add constraint notification_escalation_step_target_ck
+        check (delivery_channel is not null or target_group_id is not null);
+
+alter table notification_message
+    add column if not exists escalation_step integer not null default 0;
+
+create index notification_message_escalation_idx
+on notification_message (group_id)
+where acknowledged_at is null;
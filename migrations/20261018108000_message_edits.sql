@@ -0,0 +1,18 @@
+-- Add migration script here
+alter table notification_message
+    add column if not exists retracted_at timestamptz;
+
+create table if not exists notification_message_edit
+(
+    id         bigint generated by default as identity,
+    message_id bigint      not null references notification_message (id) on delete cascade,
+    subject    varchar     not null,
+    message    varchar     not null,
+    created_at timestamptz not null default current_timestamp
+);
+
+alter table notification_message_edit
+    add constraint notification_message_edit_id_pk primary key (id);
+
+create index notification_message_edit_message_id_idx
+on notification_message_edit (message_id);
@@ -0,0 +1,12 @@
+-- Add migration script here
+create table if not exists notification_message_dismissal
+(
+    id         bigint generated by default as identity,
+    message_id bigint      not null references notification_message (id) on delete cascade,
+    user_id    bigint      not null,
+    created_at timestamptz not null default current_timestamp,
+    unique (message_id, user_id)
+);
+
+alter table notification_message_dismissal
+    add constraint notification_message_dismissal_id_pk primary key (id);
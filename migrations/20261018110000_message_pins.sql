@@ -0,0 +1,9 @@
+-- Add migration script here
+alter table notification_message
+    add column if not exists pinned         boolean not null default false,
+    add column if not exists pinned_at      timestamptz,
+    add column if not exists pin_expires_at timestamptz;
+
+create index if not exists notification_message_pinned_idx
+    on notification_message (channel)
+    where pinned;
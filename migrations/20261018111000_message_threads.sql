@@ -0,0 +1,9 @@
+-- Add migration script here
+alter table notification_message
+    add column if not exists parent_id  bigint references notification_message (id) on delete set null,
+    add column if not exists thread_key varchar,
+    add column if not exists thread     varchar not null
+        generated always as (coalesce(thread_key, 'message-' || id)) stored;
+
+create index if not exists notification_message_thread_idx
+    on notification_message (channel, thread, created_at);
@@ -0,0 +1,28 @@
+-- Add migration script here
+-- channels only ever written to without a group are registered to a closed
+-- legacy group, its roles are granted or its channels moved by hand
+insert into notification_group (name, description, visibility)
+select 'legacy_channels', 'channels in use before channels belonged to groups', 'closed'
+where exists (
+        select 1
+        from notification_message message
+        where not exists (
+                select 1
+                from notification_channel channel
+                where channel.name = message.channel
+            )
+    )
+on conflict (name) where deleted_at is null do nothing;
+
+insert into notification_channel (group_id, name)
+select distinct legacy_group.id, message.channel
+from notification_message message
+cross join notification_group legacy_group
+where legacy_group.name = 'legacy_channels'
+and legacy_group.deleted_at is null
+and not exists (
+        select 1
+        from notification_channel channel
+        where channel.name = message.channel
+    )
+on conflict (name) do nothing;
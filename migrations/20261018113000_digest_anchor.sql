@@ -0,0 +1,10 @@
+-- Add migration script here
+-- the digest window of a subscription starts when its digest mode is enabled
+update notification_subscriber
+set last_digest_at = updated_at
+where digest_mode <> 'off'
+and last_digest_at is null;
+
+alter table notification_subscriber
+    add constraint notification_subscriber_digest_anchor_ck
+        check (digest_mode = 'off' or last_digest_at is not null);
@@ -14,4 +14,18 @@ pub struct AppConfig {
     pub run_migrations: bool,
     #[arg(long, env)]
     pub seed: bool,
+    #[arg(long, env, default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    pub digest_job_interval: u64,
+    #[arg(long, env, default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    pub group_purge_job_interval: u64,
+    #[arg(long, env, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..=36500))]
+    pub group_deletion_grace_days: u64,
+    #[arg(long, env, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub subscription_expiry_job_interval: u64,
+    #[arg(long, env, default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    pub message_retention_job_interval: u64,
+    #[arg(long, env, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub escalation_job_interval: u64,
+    #[arg(long, env, default_value_t = 5)]
+    pub max_escalation_steps: usize,
 }
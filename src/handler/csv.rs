@@ -0,0 +1,88 @@
+use crate::repository::subscriber::SubscriberEntity;
+
+pub const SUBSCRIBER_CSV_HEADER: &str = "user_id,subscribed_at,tags";
+/// Largest subscriber CSV accepted by an import.
+pub const MAX_IMPORT_CSV_BYTES: usize = 8 * 1024 * 1024;
+pub const SUBSCRIBER_EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Splits a CSV into its records and their fields, fields may be quoted to
+/// hold commas, quotes and line breaks.
+fn csv_records(csv: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!(
+            "unterminated quote on record {}",
+            records.len() + 1
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Reads the user ids from the first column of a subscriber CSV, the header
+/// row and any additional columns are optional and ignored.
+pub fn parse_user_ids(csv: &str) -> Result<Vec<i64>, String> {
+    let mut user_ids = Vec::new();
+
+    for (index, record) in csv_records(csv)?.iter().enumerate() {
+        let user_id = record.first().map(|field| field.trim()).unwrap_or_default();
+        if user_id.is_empty() || (index == 0 && user_id == "user_id") {
+            continue;
+        }
+
+        match user_id.parse::<i64>() {
+            Ok(user_id) => user_ids.push(user_id),
+            Err(_) => {
+                return Err(format!(
+                    "invalid user id {:?} on record {}",
+                    user_id,
+                    index + 1
+                ))
+            }
+        }
+    }
+
+    Ok(user_ids)
+}
+
+pub fn subscriber_row(subscriber: &SubscriberEntity) -> String {
+    format!(
+        "{},{},{}",
+        subscriber.user_id,
+        subscriber.created_at.unix_timestamp(),
+        csv_field(&subscriber.tags.join(";"))
+    )
+}
@@ -1,3 +1,4 @@
+pub mod csv;
 pub mod notification;
 
 #[cfg(test)]
@@ -5,20 +6,44 @@ pub mod test {
     use std::{sync::Arc, thread, time};
 
     use madtofan_microservice_common::notification::{
-        notification_server::Notification, AddGroupRequest, AddMessageRequest,
-        AddSubscriberRequest, ClearMessagesRequest, GetGroupsRequest, GetMessagesRequest,
-        GetSubscribersRequest, RemoveGroupRequest, RemoveSubscriberRequest, VerifyTokenRequest,
+        notification_server::Notification, AcknowledgeMessageRequest, AddGroupRequest,
+        AddMessageRequest, AddSubscriberRequest, AddSubscribersRequest, ChannelPatternRequest,
+        ClearMessagesRequest, CountSubscribersRequest, CreateChannelRequest, DeleteChannelRequest,
+        DeleteMessageRequest, DismissMessageRequest, EscalationStep, ExportSubscribersRequest,
+        GetEscalationPolicyRequest, GetGroupsRequest, GetMessageHistoryRequest, GetMessagesRequest,
+        GetPreferencesRequest, GetQuietHoursRequest, GetSubscribersRequest, GetThreadRequest,
+        GrantRoleRequest, GroupMember, ListChannelPatternsRequest, ListChannelsRequest,
+        ListGroupsRequest, PinMessageRequest, RemoveGroupRequest, RemoveSubscriberRequest,
+        RemoveSubscribersRequest, RetractMessageRequest, SetEscalationPolicyRequest,
+        SetQuietHoursRequest, StreamMessagesRequest, UnpinMessageRequest, UpdateChannelRequest,
+        UpdateGroupRequest, UpdateMessageRequest, UpdatePreferencesRequest, VerifyTokenRequest,
     };
     use sqlx::PgPool;
-    use tonic::Request;
+    use tokio_stream::StreamExt;
+    use tonic::{Request, Status};
 
     use crate::{
+        handler::csv::{parse_user_ids, SUBSCRIBER_CSV_HEADER},
         repository::{
+            channel::{ChannelRepository, DynChannelRepositoryTrait},
+            channel_subscription::{
+                ChannelSubscriptionRepository, DynChannelSubscriptionRepositoryTrait,
+            },
+            escalation::{DynEscalationRepositoryTrait, EscalationRepository},
             group::{DynGroupRepositoryTrait, GroupRepository},
-            message::{DynMessageRepositoryTrait, MessageRepository},
+            invitation::{DynInvitationRepositoryTrait, InvitationRepository},
+            join_request::{DynJoinRequestRepositoryTrait, JoinRequestRepository},
+            message::{
+                DynMessageRepositoryTrait, MessageFanout, MessageFilter, MessageRepository,
+                NewMessage,
+            },
+            quiet_hours::{DynQuietHoursRepositoryTrait, QuietHoursRepository},
+            role::{DynRoleRepositoryTrait, RoleRepository},
             subscriber::{DynSubscriberRepositoryTrait, SubscriberRepository},
         },
         service::{
+            channel::{ChannelService, DynChannelServiceTrait},
+            escalation::{DynEscalationServiceTrait, EscalationService},
             group::{DynGroupServiceTrait, GroupService},
             message::{DynMessageServiceTrait, MessageService},
             subscriber::{DynSubscriberServiceTrait, SubscriberService},
@@ -31,6 +56,7 @@ pub mod test {
         subscriber_repository: DynSubscriberRepositoryTrait,
         group_repository: DynGroupRepositoryTrait,
         message_repository: DynMessageRepositoryTrait,
+        channel_repository: DynChannelRepositoryTrait,
         handler: RequestHandler,
     }
 
@@ -41,24 +67,64 @@ pub mod test {
             Arc::new(GroupRepository::new(pool.clone())) as DynGroupRepositoryTrait;
         let message_repository =
             Arc::new(MessageRepository::new(pool.clone())) as DynMessageRepositoryTrait;
+        let quiet_hours_repository =
+            Arc::new(QuietHoursRepository::new(pool.clone())) as DynQuietHoursRepositoryTrait;
+        let role_repository = Arc::new(RoleRepository::new(pool.clone())) as DynRoleRepositoryTrait;
+        let invitation_repository =
+            Arc::new(InvitationRepository::new(pool.clone())) as DynInvitationRepositoryTrait;
+        let join_request_repository =
+            Arc::new(JoinRequestRepository::new(pool.clone())) as DynJoinRequestRepositoryTrait;
+        let channel_repository =
+            Arc::new(ChannelRepository::new(pool.clone())) as DynChannelRepositoryTrait;
+        let channel_subscription_repository =
+            Arc::new(ChannelSubscriptionRepository::new(pool.clone()))
+                as DynChannelSubscriptionRepositoryTrait;
+        let escalation_repository =
+            Arc::new(EscalationRepository::new(pool.clone())) as DynEscalationRepositoryTrait;
         let subscriber_service = Arc::new(SubscriberService::new(
             subscriber_repository.clone(),
             group_repository.clone(),
+            quiet_hours_repository.clone(),
+            role_repository.clone(),
+            invitation_repository,
+            join_request_repository,
         )) as DynSubscriberServiceTrait;
-        let group_service =
-            Arc::new(GroupService::new(group_repository.clone())) as DynGroupServiceTrait;
-        let message_service =
-            Arc::new(MessageService::new(message_repository.clone())) as DynMessageServiceTrait;
+        let group_service = Arc::new(GroupService::new(
+            group_repository.clone(),
+            role_repository.clone(),
+        )) as DynGroupServiceTrait;
+        let message_service = Arc::new(MessageService::new(
+            message_repository.clone(),
+            subscriber_repository.clone(),
+            group_repository.clone(),
+            channel_repository.clone(),
+            channel_subscription_repository,
+            role_repository.clone(),
+        )) as DynMessageServiceTrait;
+        let channel_service = Arc::new(ChannelService::new(
+            channel_repository.clone(),
+            group_repository.clone(),
+            role_repository.clone(),
+        )) as DynChannelServiceTrait;
+        let escalation_service = Arc::new(EscalationService::new(
+            escalation_repository,
+            group_repository.clone(),
+            role_repository,
+            5,
+        )) as DynEscalationServiceTrait;
         let handler = RequestHandler::new(
             subscriber_service.clone(),
             group_service.clone(),
             message_service.clone(),
+            channel_service,
+            escalation_service,
         );
 
         AllTraits {
             subscriber_repository,
             group_repository,
             message_repository,
+            channel_repository,
             handler,
         }
     }
@@ -77,6 +143,7 @@ pub mod test {
         let request = Request::new(AddSubscriberRequest {
             user_id: sub_id,
             group: group_name.to_string(),
+            invitation_code: None,
         });
 
         all_traits.handler.add_subscriber(request).await?;
@@ -89,6 +156,147 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn add_subscriber_twice_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let sub_id = 0;
+        let request = Request::new(AddSubscriberRequest {
+            user_id: sub_id,
+            group: group_name.to_string(),
+            invitation_code: None,
+        });
+        all_traits.handler.add_subscriber(request).await?;
+
+        let request = Request::new(AddSubscriberRequest {
+            user_id: sub_id,
+            group: group_name.to_string(),
+            invitation_code: None,
+        });
+        let response = all_traits
+            .handler
+            .add_subscriber(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(response.message, "User is already subscribed!");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_subscribers_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let request = Request::new(AddSubscribersRequest {
+            actor: Some(admin.clone()),
+            user_ids: vec![0, 1, 2],
+            groups: vec![group_name.to_string()],
+        });
+        let results = all_traits
+            .handler
+            .add_subscribers(request)
+            .await?
+            .into_inner()
+            .results;
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.outcome == "subscribed"));
+
+        let request = Request::new(RemoveSubscribersRequest {
+            actor: Some(admin),
+            user_ids: vec![0, 1],
+            groups: vec![group_name.to_string()],
+        });
+        let results = all_traits
+            .handler
+            .remove_subscribers(request)
+            .await?
+            .into_inner()
+            .results;
+
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|result| result.outcome == "unsubscribed"));
+
+        let subs_list = all_traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 1);
+        assert_eq!(subs_list.first().unwrap().user_id, 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn export_subscribers_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscribers(&[0, 1], std::slice::from_ref(&group))
+            .await?;
+        all_traits
+            .subscriber_repository
+            .set_tags(0, &group, &["on,call".to_string(), "\"vip\"".to_string()])
+            .await?;
+
+        let request = Request::new(ExportSubscribersRequest {
+            group: group_name.to_string(),
+        });
+        let rows = all_traits
+            .handler
+            .export_subscribers(request)
+            .await?
+            .into_inner()
+            .map(|response| response.map(|response| response.row))
+            .collect::<Result<Vec<String>, Status>>()
+            .await?;
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], SUBSCRIBER_CSV_HEADER);
+        assert!(rows[1].starts_with("0,"));
+        assert!(rows[1].ends_with(",\"on,call;\"\"vip\"\"\""));
+        assert!(rows[2].starts_with("1,"));
+        assert_eq!(parse_user_ids(&rows.join("\n")).unwrap(), vec![0, 1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_user_ids_test() {
+        let csv = "user_id,subscribed_at,tags\n0,0,\"on,call\"\n\"1\",0,\"multi\nline\"\n";
+
+        assert_eq!(parse_user_ids(csv).unwrap(), vec![0, 1]);
+        assert!(parse_user_ids("0,0,tag\nabc").is_err());
+        assert!(parse_user_ids("0,0,tag\n1,0,\"open").is_err());
+    }
+
     #[sqlx::test]
     async fn remove_subscriber_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
@@ -161,7 +369,10 @@ pub mod test {
 
         let request = Request::new(RemoveGroupRequest {
             name: group_to_remove_name.to_string(),
-            admin_email: group_to_remove_admin_email.to_string(),
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some(group_to_remove_admin_email.to_string()),
+            }),
         });
 
         all_traits.handler.remove_group(request).await?;
@@ -176,6 +387,104 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn update_group_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let request = Request::new(UpdateGroupRequest {
+            name: group_name.to_string(),
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some("admin_email".to_string()),
+            }),
+            new_name: None,
+            new_admin_email: Some("new_admin_email".to_string()),
+            description: None,
+            icon_url: None,
+            visibility: None,
+            parent: None,
+        });
+        let admin_email_result = all_traits.handler.update_group(request).await;
+
+        assert!(admin_email_result.is_err());
+
+        let request = Request::new(UpdateGroupRequest {
+            name: group_name.to_string(),
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some("admin_email".to_string()),
+            }),
+            new_name: None,
+            new_admin_email: None,
+            description: None,
+            icon_url: Some("https://example.com/icon.png".to_string()),
+            visibility: None,
+            parent: None,
+        });
+        all_traits.handler.update_group(request).await?;
+
+        let group = all_traits
+            .group_repository
+            .get_group(group_name)
+            .await?
+            .unwrap();
+
+        assert_eq!(group.admin_email, "admin_email");
+        assert_eq!(group.icon_url, "https://example.com/icon.png");
+        assert_eq!(group.visibility, "public");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn grant_role_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let request = Request::new(GrantRoleRequest {
+            group: group_name.to_string(),
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some("admin_email".to_string()),
+            }),
+            member: Some(GroupMember {
+                user_id: Some(1),
+                email: None,
+            }),
+            role: "viewer".to_string(),
+        });
+        all_traits.handler.grant_role(request).await?;
+
+        // a viewer is not allowed to remove the group
+        let request = Request::new(RemoveGroupRequest {
+            name: group_name.to_string(),
+            actor: Some(GroupMember {
+                user_id: Some(1),
+                email: None,
+            }),
+        });
+        let remove_result = all_traits.handler.remove_group(request).await;
+
+        assert!(remove_result.is_err());
+
+        let group = all_traits.group_repository.get_group(group_name).await?;
+
+        assert!(group.is_some());
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn get_subscribers_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
@@ -203,6 +512,12 @@ pub mod test {
 
         let request = Request::new(GetSubscribersRequest {
             group: group1_name.to_string(),
+            cursor: String::new(),
+            limit: 0,
+            sort_by: String::new(),
+            descending: false,
+            tags: vec![],
+            match_all_tags: false,
         });
 
         let subs_list = all_traits
@@ -218,6 +533,121 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn get_subscribers_paginated_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscribers(&[0, 1, 2], &[group])
+            .await?;
+
+        let request = Request::new(GetSubscribersRequest {
+            group: group_name.to_string(),
+            cursor: String::new(),
+            limit: 2,
+            sort_by: "user_id".to_string(),
+            descending: true,
+            tags: vec![],
+            match_all_tags: false,
+        });
+        let response = all_traits
+            .handler
+            .get_subscribers(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(response.total_count, 3);
+        assert_eq!(
+            response
+                .subscribers
+                .iter()
+                .map(|sub| sub.user_id)
+                .collect::<Vec<i64>>(),
+            vec![2, 1]
+        );
+        assert!(!response.next_cursor.is_empty());
+
+        let request = Request::new(GetSubscribersRequest {
+            group: group_name.to_string(),
+            cursor: response.next_cursor,
+            limit: 2,
+            sort_by: "user_id".to_string(),
+            descending: true,
+            tags: vec![],
+            match_all_tags: false,
+        });
+        let response = all_traits
+            .handler
+            .get_subscribers(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(response.subscribers.len(), 1);
+        assert_eq!(response.subscribers.first().unwrap().user_id, 0);
+        assert!(response.next_cursor.is_empty());
+
+        let request = Request::new(CountSubscribersRequest {
+            group: group_name.to_string(),
+        });
+        let count = all_traits
+            .handler
+            .count_subscribers(request)
+            .await?
+            .into_inner()
+            .count;
+
+        assert_eq!(count, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_subscribers_tagged_count_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscribers(&[0, 1, 2], std::slice::from_ref(&group))
+            .await?;
+        for user_id in [0, 1] {
+            all_traits
+                .subscriber_repository
+                .set_tags(user_id, &group, &["on_call".to_string()])
+                .await?;
+        }
+
+        let request = Request::new(GetSubscribersRequest {
+            group: group_name.to_string(),
+            cursor: String::new(),
+            limit: 1,
+            sort_by: String::new(),
+            descending: false,
+            tags: vec!["on_call".to_string()],
+            match_all_tags: false,
+        });
+        let response = all_traits
+            .handler
+            .get_subscribers(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(response.subscribers.len(), 1);
+        assert_eq!(response.total_count, 2);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn get_groups_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
@@ -243,7 +673,10 @@ pub mod test {
             .add_subscriber(sub2_id, &group2)
             .await?;
 
-        let request = Request::new(GetGroupsRequest { user_id: sub1_id });
+        let request = Request::new(GetGroupsRequest {
+            user_id: sub1_id,
+            effective: false,
+        });
 
         let groups_list = all_traits
             .handler
@@ -259,50 +692,332 @@ pub mod test {
     }
 
     #[sqlx::test]
-    async fn verify_token_test(pool: PgPool) -> anyhow::Result<()> {
+    async fn subscriber_response_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
 
         let group_name = "group_name";
-        let token = "token";
-        all_traits
+        let group = all_traits
             .group_repository
-            .add_group(group_name, "admin_email", token)
+            .add_group(group_name, "admin_email", "token")
             .await?;
 
-        let request = Request::new(VerifyTokenRequest {
-            name: group_name.to_string(),
-            token: token.to_string(),
-        });
+        let sub_id = 0;
+        let subscriber = all_traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?
+            .unwrap();
 
-        let verify_valid_token = all_traits.handler.verify_token(request).await?;
-        assert!(verify_valid_token.into_inner().valid);
+        let request = Request::new(UpdatePreferencesRequest {
+            user_id: sub_id,
+            group: group_name.to_string(),
+            muted_until: None,
+            opted_out_channels: vec![],
+            delivery_channels: vec!["email".to_string()],
+            digest_mode: "daily".to_string(),
+        });
+        all_traits.handler.update_preferences(request).await?;
 
-        let request = Request::new(VerifyTokenRequest {
-            name: group_name.to_string(),
-            token: "invalid_token".to_string(),
+        let request = Request::new(GetSubscribersRequest {
+            group: group_name.to_string(),
+            cursor: String::new(),
+            limit: 0,
+            sort_by: String::new(),
+            descending: false,
+            tags: vec![],
+            match_all_tags: false,
         });
+        let subs_list = all_traits
+            .handler
+            .get_subscribers(request)
+            .await?
+            .into_inner()
+            .subscribers;
 
-        let verify_invalid_token = all_traits.handler.verify_token(request).await?;
-        assert!(!verify_invalid_token.into_inner().valid);
+        assert_eq!(subs_list.len(), 1);
+        let response = subs_list.first().unwrap();
+        assert_eq!(response.created_at, subscriber.created_at.unix_timestamp());
+        let preferences = response.preferences.as_ref().unwrap();
+        assert_eq!(preferences.delivery_channels, vec!["email".to_string()]);
+        assert_eq!(preferences.digest_mode, "daily");
 
         Ok(())
     }
 
     #[sqlx::test]
-    async fn add_message_test(pool: PgPool) -> anyhow::Result<()> {
+    async fn group_response_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
 
-        let message = "test_message";
-        let add_message_request = Request::new(AddMessageRequest {
-            channel: "channel1".to_string(),
-            subject: "subject".to_string(),
-            message: message.to_string(),
-        });
-        let request = all_traits.handler.add_message(add_message_request).await?;
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscribers(&[0, 1], std::slice::from_ref(&group))
+            .await?;
 
-        assert_eq!(request.into_inner().message, message);
+        let request = Request::new(UpdateGroupRequest {
+            name: group_name.to_string(),
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some("admin_email".to_string()),
+            }),
+            new_name: None,
+            new_admin_email: None,
+            description: Some("group description".to_string()),
+            icon_url: None,
+            visibility: None,
+            parent: None,
+        });
+        all_traits.handler.update_group(request).await?;
 
-        Ok(())
+        let request = Request::new(GetGroupsRequest {
+            user_id: 0,
+            effective: false,
+        });
+        let groups_list = all_traits
+            .handler
+            .get_groups(request)
+            .await?
+            .into_inner()
+            .groups;
+
+        assert_eq!(groups_list.len(), 1);
+        let response = groups_list.first().unwrap();
+        assert_eq!(response.description, "group description");
+        assert_eq!(response.created_at, group.created_at.unix_timestamp());
+        assert_eq!(response.subscriber_count, 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_groups_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group1 = all_traits
+            .group_repository
+            .add_group("alerts_group", "admin_email", "token")
+            .await?;
+        all_traits
+            .group_repository
+            .add_group("news_group", "admin_email", "token")
+            .await?;
+        all_traits
+            .group_repository
+            .add_group("other", "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscribers(&[0, 1], &[group1])
+            .await?;
+
+        let request = Request::new(ListGroupsRequest {
+            search: "_group".to_string(),
+            visibility: "public".to_string(),
+            offset: 0,
+            limit: 1,
+        });
+        let response = all_traits.handler.list_groups(request).await?.into_inner();
+
+        assert_eq!(response.count, 2);
+        assert_eq!(response.groups.len(), 1);
+        assert_eq!(response.groups.first().unwrap().name, "alerts_group");
+        assert_eq!(response.groups.first().unwrap().subscriber_count, 2);
+
+        let request = Request::new(ListGroupsRequest {
+            search: String::new(),
+            visibility: "closed".to_string(),
+            offset: 0,
+            limit: 10,
+        });
+        let response = all_traits.handler.list_groups(request).await?.into_inner();
+
+        assert_eq!(response.count, 0);
+        assert!(response.groups.is_empty());
+
+        let request = Request::new(ListGroupsRequest {
+            search: "_group".to_string(),
+            visibility: String::new(),
+            offset: -1,
+            limit: 0,
+        });
+        let response = all_traits.handler.list_groups(request).await?.into_inner();
+
+        assert_eq!(response.groups.len(), 2);
+
+        let request = Request::new(ListGroupsRequest {
+            search: "_group".to_string(),
+            visibility: String::new(),
+            offset: i64::from(i32::MAX) + 1,
+            limit: 10,
+        });
+        let response = all_traits.handler.list_groups(request).await?.into_inner();
+
+        assert!(response.groups.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn verify_token_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let token = "token";
+        all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", token)
+            .await?;
+
+        let request = Request::new(VerifyTokenRequest {
+            name: group_name.to_string(),
+            token: token.to_string(),
+        });
+
+        let verify_valid_token = all_traits.handler.verify_token(request).await?;
+        assert!(verify_valid_token.into_inner().valid);
+
+        let request = Request::new(VerifyTokenRequest {
+            name: group_name.to_string(),
+            token: "invalid_token".to_string(),
+        });
+
+        let verify_invalid_token = all_traits.handler.verify_token(request).await?;
+        assert!(!verify_invalid_token.into_inner().valid);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let message = "test_message";
+        let add_message_request = Request::new(AddMessageRequest {
+            actor: Some(admin.clone()),
+            channel: "channel1".to_string(),
+            subject: "subject".to_string(),
+            message: message.to_string(),
+            priority: "normal".to_string(),
+            audience: None,
+            parent_id: None,
+            thread_key: None,
+        });
+        let unknown_channel_result = all_traits.handler.add_message(add_message_request).await;
+
+        assert!(unknown_channel_result.is_err());
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "channel1", "", "normal", None)
+            .await?;
+
+        let add_message_request = Request::new(AddMessageRequest {
+            actor: Some(admin.clone()),
+            channel: "channel1".to_string(),
+            subject: "subject".to_string(),
+            message: message.to_string(),
+            priority: "normal".to_string(),
+            audience: None,
+            parent_id: None,
+            thread_key: None,
+        });
+        let outsider_result = all_traits
+            .handler
+            .add_message(Request::new(AddMessageRequest {
+                actor: None,
+                channel: "channel1".to_string(),
+                subject: "subject".to_string(),
+                message: message.to_string(),
+                priority: "normal".to_string(),
+                audience: None,
+                parent_id: None,
+                thread_key: None,
+            }))
+            .await;
+
+        assert!(outsider_result.is_err());
+
+        let request = all_traits.handler.add_message(add_message_request).await?;
+
+        assert_eq!(request.into_inner().message, message);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn channels_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let request = Request::new(CreateChannelRequest {
+            group: group_name.to_string(),
+            actor: Some(admin.clone()),
+            name: "alerts".to_string(),
+            description: "alerts".to_string(),
+            default_priority: "high".to_string(),
+            retention_days: Some(7),
+        });
+        all_traits.handler.create_channel(request).await?;
+
+        let request = Request::new(UpdateChannelRequest {
+            name: "alerts".to_string(),
+            actor: Some(admin.clone()),
+            description: None,
+            default_priority: None,
+            retention_days: None,
+            clear_retention: true,
+        });
+        all_traits.handler.update_channel(request).await?;
+
+        let request = Request::new(ListChannelsRequest {
+            group: group_name.to_string(),
+        });
+        let channels = all_traits
+            .handler
+            .list_channels(request)
+            .await?
+            .into_inner()
+            .channels;
+
+        assert_eq!(channels.len(), 1);
+        let channel = channels.first().unwrap();
+        assert_eq!(channel.default_priority, "high");
+        assert!(channel.retention_days.is_none());
+
+        let request = Request::new(DeleteChannelRequest {
+            name: "alerts".to_string(),
+            actor: Some(admin),
+        });
+        all_traits.handler.delete_channel(request).await?;
+
+        assert!(all_traits
+            .channel_repository
+            .get_channel("alerts")
+            .await?
+            .is_none());
+
+        Ok(())
     }
 
     #[sqlx::test]
@@ -315,15 +1030,37 @@ pub mod test {
 
         all_traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
         all_traits
             .message_repository
-            .add_message(channel, "subject", message)
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let get_message_request = Request::new(GetMessagesRequest {
             channels,
+            patterns: vec![],
+            unacknowledged: false,
+            user_id: None,
+            collapse_threads: false,
             offset: 0,
             limit: 10,
         });
@@ -337,6 +1074,555 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn acknowledge_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "urgent", None)
+            .await?;
+        let message = all_traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "alerts",
+                    subject: "subject",
+                    message: "message",
+                    priority: "urgent",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+
+        let acknowledge_request = Request::new(AcknowledgeMessageRequest {
+            id: message.id,
+            user_id: 1,
+            note: Some("on it".to_string()),
+        });
+        let acknowledged_message = all_traits
+            .handler
+            .acknowledge_message(acknowledge_request)
+            .await?
+            .into_inner();
+
+        let acknowledgement = acknowledged_message.acknowledgement.unwrap();
+        assert_eq!(acknowledgement.user_id, 1);
+        assert_eq!(acknowledgement.note, Some("on it".to_string()));
+
+        let get_message_request = Request::new(GetMessagesRequest {
+            channels: vec!["alerts".to_string()],
+            patterns: vec![],
+            unacknowledged: true,
+            user_id: None,
+            collapse_threads: false,
+            offset: 0,
+            limit: 10,
+        });
+        let messages = all_traits
+            .handler
+            .get_messages(get_message_request)
+            .await?
+            .into_inner();
+        assert_eq!(messages.count, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn escalation_policy_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .group_repository
+            .add_group("on_call", "admin_email", "token")
+            .await?;
+
+        let steps = vec![
+            EscalationStep {
+                delay_minutes: 15,
+                delivery_channel: Some("sms".to_string()),
+                target_group: None,
+            },
+            EscalationStep {
+                delay_minutes: 30,
+                delivery_channel: None,
+                target_group: Some("on_call".to_string()),
+            },
+        ];
+        let set_request = Request::new(SetEscalationPolicyRequest {
+            group: "group_name".to_string(),
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some("admin_email".to_string()),
+            }),
+            steps: steps.clone(),
+        });
+        all_traits
+            .handler
+            .set_escalation_policy(set_request)
+            .await?;
+
+        let get_request = Request::new(GetEscalationPolicyRequest {
+            group: "group_name".to_string(),
+        });
+        let policy = all_traits
+            .handler
+            .get_escalation_policy(get_request)
+            .await?
+            .into_inner();
+        assert_eq!(policy.steps, steps);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn edit_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "normal", None)
+            .await?;
+        let message = all_traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "alerts",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let stream_request = Request::new(StreamMessagesRequest {
+            user_id: None,
+            patterns: vec!["alerts".to_string()],
+        });
+        let mut stream = all_traits
+            .handler
+            .stream_messages(stream_request)
+            .await?
+            .into_inner();
+
+        let update_request = Request::new(UpdateMessageRequest {
+            id: message.id,
+            actor: Some(admin.clone()),
+            subject: Some("corrected subject".to_string()),
+            message: None,
+        });
+        let updated_message = all_traits
+            .handler
+            .update_message(update_request)
+            .await?
+            .into_inner();
+        assert_eq!(updated_message.subject, "corrected subject");
+        assert_eq!(updated_message.message, "message");
+
+        let retract_request = Request::new(RetractMessageRequest {
+            id: message.id,
+            actor: Some(admin),
+        });
+        let retracted_message = all_traits
+            .handler
+            .retract_message(retract_request)
+            .await?
+            .into_inner();
+        assert!(retracted_message.retracted_at.is_some());
+
+        let updated_event = stream.next().await.unwrap()?;
+        assert_eq!(updated_event.event, "updated");
+        let retracted_event = stream.next().await.unwrap()?;
+        assert_eq!(retracted_event.event, "retracted");
+
+        let history_request = Request::new(GetMessageHistoryRequest { id: message.id });
+        let edits = all_traits
+            .handler
+            .get_message_history(history_request)
+            .await?
+            .into_inner()
+            .edits;
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits.first().unwrap().subject, "subject");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn thread_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "incidents", "", "normal", None)
+            .await?;
+
+        let mut message_ids = vec![];
+        for (message, thread_key) in [
+            ("incident opened", "incident-1"),
+            ("incident resolved", "incident-1"),
+            ("maintenance scheduled", "maintenance-1"),
+        ] {
+            let add_message_request = Request::new(AddMessageRequest {
+                actor: Some(admin.clone()),
+                channel: "incidents".to_string(),
+                subject: "subject".to_string(),
+                message: message.to_string(),
+                priority: "normal".to_string(),
+                audience: None,
+                parent_id: None,
+                thread_key: Some(thread_key.to_string()),
+            });
+            let added_message = all_traits
+                .handler
+                .add_message(add_message_request)
+                .await?
+                .into_inner();
+            message_ids.push(added_message.id);
+        }
+
+        let get_message_request = Request::new(GetMessagesRequest {
+            channels: vec!["incidents".to_string()],
+            patterns: vec![],
+            unacknowledged: false,
+            user_id: None,
+            collapse_threads: true,
+            offset: 0,
+            limit: 10,
+        });
+        let messages = all_traits
+            .handler
+            .get_messages(get_message_request)
+            .await?
+            .into_inner();
+        assert_eq!(messages.count, 2);
+        assert_eq!(
+            messages
+                .messages
+                .iter()
+                .map(|message| (message.id, message.reply_count))
+                .collect::<Vec<(i64, i64)>>(),
+            vec![(message_ids[2], 0), (message_ids[1], 1)]
+        );
+
+        let thread_request = Request::new(GetThreadRequest { id: message_ids[0] });
+        let thread = all_traits
+            .handler
+            .get_thread(thread_request)
+            .await?
+            .into_inner();
+        assert_eq!(thread.count, 2);
+        assert_eq!(thread.messages.last().unwrap().message, "incident resolved");
+        assert_eq!(thread.messages.last().unwrap().thread, "incident-1");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn pin_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "normal", None)
+            .await?;
+        let mut message_ids = vec![];
+        for body in ["announcement", "update"] {
+            let message = all_traits
+                .message_repository
+                .add_message(
+                    &NewMessage {
+                        channel: "alerts",
+                        subject: "subject",
+                        message: body,
+                        priority: "normal",
+                        ..Default::default()
+                    },
+                    &MessageFanout::default(),
+                )
+                .await?;
+            message_ids.push(message.id);
+        }
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let pin_request = Request::new(PinMessageRequest {
+            id: message_ids[0],
+            actor: Some(admin.clone()),
+            expires_at: None,
+        });
+        let pinned_message = all_traits
+            .handler
+            .pin_message(pin_request)
+            .await?
+            .into_inner();
+        assert!(pinned_message.pinned);
+        assert!(pinned_message.pin_expires_at.is_none());
+
+        let get_message_request = Request::new(GetMessagesRequest {
+            channels: vec!["alerts".to_string()],
+            patterns: vec![],
+            unacknowledged: false,
+            user_id: None,
+            collapse_threads: false,
+            offset: 0,
+            limit: 10,
+        });
+        let messages = all_traits
+            .handler
+            .get_messages(get_message_request)
+            .await?
+            .into_inner()
+            .messages;
+        assert_eq!(messages.first().unwrap().id, message_ids[0]);
+        assert!(!messages.last().unwrap().pinned);
+
+        let unpin_request = Request::new(UnpinMessageRequest {
+            id: message_ids[0],
+            actor: Some(admin),
+        });
+        let unpinned_message = all_traits
+            .handler
+            .unpin_message(unpin_request)
+            .await?
+            .into_inner();
+        assert!(!unpinned_message.pinned);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_and_dismiss_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "normal", None)
+            .await?;
+        let message = all_traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "alerts",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+
+        let dismiss_request = Request::new(DismissMessageRequest {
+            id: message.id,
+            user_id: 1,
+        });
+        all_traits.handler.dismiss_message(dismiss_request).await?;
+
+        let get_message_request = Request::new(GetMessagesRequest {
+            channels: vec!["alerts".to_string()],
+            patterns: vec![],
+            unacknowledged: false,
+            user_id: Some(1),
+            collapse_threads: false,
+            offset: 0,
+            limit: 10,
+        });
+        let messages = all_traits
+            .handler
+            .get_messages(get_message_request)
+            .await?
+            .into_inner();
+        assert!(messages.messages.is_empty());
+        assert_eq!(messages.count, 0);
+
+        let delete_request = Request::new(DeleteMessageRequest {
+            id: message.id,
+            actor: Some(GroupMember {
+                user_id: None,
+                email: Some("admin_email".to_string()),
+            }),
+        });
+        let deleted_message = all_traits
+            .handler
+            .delete_message(delete_request)
+            .await?
+            .into_inner();
+        assert_eq!(deleted_message.id, message.id);
+
+        let history_request = Request::new(GetMessageHistoryRequest { id: message.id });
+        assert!(all_traits
+            .handler
+            .get_message_history(history_request)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn channel_patterns_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let invalid_pattern_request = Request::new(ChannelPatternRequest {
+            user_id: 1,
+            pattern: "billing.in*".to_string(),
+        });
+        let invalid_pattern_result = all_traits
+            .handler
+            .subscribe_channel_pattern(invalid_pattern_request)
+            .await;
+        assert!(invalid_pattern_result.is_err());
+
+        for pattern in ["billing.*", "alerts.#"] {
+            let subscribe_request = Request::new(ChannelPatternRequest {
+                user_id: 1,
+                pattern: pattern.to_string(),
+            });
+            all_traits
+                .handler
+                .subscribe_channel_pattern(subscribe_request)
+                .await?;
+        }
+
+        let unsubscribe_request = Request::new(ChannelPatternRequest {
+            user_id: 1,
+            pattern: "billing.*".to_string(),
+        });
+        all_traits
+            .handler
+            .unsubscribe_channel_pattern(unsubscribe_request)
+            .await?;
+
+        let list_request = Request::new(ListChannelPatternsRequest { user_id: 1 });
+        let patterns = all_traits
+            .handler
+            .list_channel_patterns(list_request)
+            .await?
+            .into_inner()
+            .patterns;
+        assert_eq!(patterns, vec!["alerts.#"]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stream_messages_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = GroupMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        for channel in ["billing.invoice", "alerts.db.down"] {
+            all_traits
+                .channel_repository
+                .add_channel(group.id, channel, "", "normal", None)
+                .await?;
+        }
+
+        let subscribe_request = Request::new(ChannelPatternRequest {
+            user_id: 1,
+            pattern: "alerts.#".to_string(),
+        });
+        all_traits
+            .handler
+            .subscribe_channel_pattern(subscribe_request)
+            .await?;
+
+        let stream_request = Request::new(StreamMessagesRequest {
+            user_id: Some(1),
+            patterns: vec![],
+        });
+        let mut stream = all_traits
+            .handler
+            .stream_messages(stream_request)
+            .await?
+            .into_inner();
+
+        for channel in ["billing.invoice", "alerts.db.down"] {
+            let add_message_request = Request::new(AddMessageRequest {
+                actor: Some(admin.clone()),
+                channel: channel.to_string(),
+                subject: "subject".to_string(),
+                message: "message".to_string(),
+                priority: "normal".to_string(),
+                audience: None,
+                parent_id: None,
+                thread_key: None,
+            });
+            all_traits.handler.add_message(add_message_request).await?;
+        }
+
+        let streamed_event = stream.next().await.unwrap()?;
+        assert_eq!(streamed_event.event, "posted");
+        assert_eq!(streamed_event.message.unwrap().channel, "alerts.db.down");
+
+        let get_message_request = Request::new(GetMessagesRequest {
+            channels: vec![],
+            patterns: vec!["billing.*".to_string()],
+            unacknowledged: false,
+            user_id: None,
+            collapse_threads: false,
+            offset: 0,
+            limit: 10,
+        });
+        let messages = all_traits
+            .handler
+            .get_messages(get_message_request)
+            .await?
+            .into_inner();
+        assert_eq!(messages.count, 1);
+        assert_eq!(
+            messages.messages.first().unwrap().channel,
+            "billing.invoice"
+        );
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn clear_messages_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
@@ -346,7 +1632,16 @@ pub mod test {
         let message = "test_message";
         let first_message = all_traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let first_message_time = first_message.created_at;
@@ -355,7 +1650,16 @@ pub mod test {
 
         all_traits
             .message_repository
-            .add_message(channel, "subject", message)
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let clear_message_request = Request::new(ClearMessagesRequest {
@@ -368,7 +1672,14 @@ pub mod test {
 
         let left_messages = all_traits
             .message_repository
-            .get_messages(channels, 0, 50)
+            .get_messages(
+                &MessageFilter {
+                    channels,
+                    ..Default::default()
+                },
+                0,
+                50,
+            )
             .await?;
 
         assert_eq!(left_messages.len(), 1);
@@ -376,4 +1687,76 @@ pub mod test {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn update_preferences_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let sub_id = 0;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+
+        let muted_until = 4102444800;
+        let request = Request::new(UpdatePreferencesRequest {
+            user_id: sub_id,
+            group: group_name.to_string(),
+            muted_until: Some(muted_until),
+            opted_out_channels: vec!["channel".to_string()],
+            delivery_channels: vec!["email".to_string()],
+            digest_mode: "daily".to_string(),
+        });
+        all_traits.handler.update_preferences(request).await?;
+
+        let request = Request::new(GetPreferencesRequest {
+            user_id: sub_id,
+            group: group_name.to_string(),
+        });
+        let preferences = all_traits
+            .handler
+            .get_preferences(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(preferences.muted_until, Some(muted_until));
+        assert_eq!(preferences.opted_out_channels, vec!["channel".to_string()]);
+        assert_eq!(preferences.delivery_channels, vec!["email".to_string()]);
+        assert_eq!(preferences.digest_mode, "daily");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_quiet_hours_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let sub_id = 0;
+        let request = Request::new(SetQuietHoursRequest {
+            user_id: sub_id,
+            start_minute: 22 * 60,
+            end_minute: 7 * 60,
+            timezone: "Asia/Kuala_Lumpur".to_string(),
+        });
+        all_traits.handler.set_quiet_hours(request).await?;
+
+        let request = Request::new(GetQuietHoursRequest { user_id: sub_id });
+        let quiet_hours = all_traits
+            .handler
+            .get_quiet_hours(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(quiet_hours.start_minute, 22 * 60);
+        assert_eq!(quiet_hours.end_minute, 7 * 60);
+        assert_eq!(quiet_hours.timezone, "Asia/Kuala_Lumpur");
+
+        Ok(())
+    }
 }
@@ -1,22 +1,65 @@
-use tonic::{Request, Response, Status};
+use std::pin::Pin;
+
+use sqlx::types::time::OffsetDateTime;
+use tokio::sync::mpsc;
+use tokio_stream::{
+    wrappers::{BroadcastStream, ReceiverStream},
+    Stream, StreamExt,
+};
+use tonic::{Request, Response, Status, Streaming};
 
 use madtofan_microservice_common::notification::{
-    groups_response::Group, notification_server::Notification, subscribers_response::Subscriber,
-    AddGroupRequest, AddMessageRequest, AddSubscriberRequest, ClearMessagesRequest,
-    GetGroupsRequest, GetMessagesRequest, GetSubscribersRequest, GroupsResponse, MessageResponse,
-    MessagesResponse, NotificationResponse, RemoveGroupRequest, RemoveSubscriberRequest,
-    SubscribersResponse, VerifyTokenRequest, VerifyTokenResponse,
+    channels_response::Channel, groups_response::Group, join_requests_response::JoinRequest,
+    notification_server::Notification, subscribers_response::Subscriber, AcknowledgeMessageRequest,
+    AddGroupRequest, AddMessageRequest, AddSubscriberRequest, AddSubscribersRequest,
+    ChannelPatternRequest, ChannelPatternsResponse, ChannelsResponse, ClearMessagesRequest,
+    ClearQuietHoursRequest, ClearSubscriberTagsRequest, CountSubscribersRequest,
+    CountSubscribersResponse, CreateChannelRequest, CreateInvitationRequest,
+    DecideJoinRequestRequest, DeleteChannelRequest, DeleteMessageRequest, DismissMessageRequest,
+    EscalationPolicyResponse, ExportSubscribersRequest, ExportSubscribersResponse,
+    GetEscalationPolicyRequest, GetGroupsRequest, GetMessageHistoryRequest, GetMessagesRequest,
+    GetPreferencesRequest, GetQuietHoursRequest, GetSubscribersRequest, GetThreadRequest,
+    GrantRoleRequest, GroupsResponse, ImportSubscribersRequest, ImportSubscribersResponse,
+    InvitationResponse, JoinRequestsResponse, ListChannelPatternsRequest, ListChannelsRequest,
+    ListGroupsRequest, ListGroupsResponse, ListJoinRequestsRequest, MessageEventResponse,
+    MessageHistoryResponse, MessageResponse, MessagesResponse, NotificationResponse,
+    PinMessageRequest, PreferencesResponse, QuietHoursResponse, RemoveGroupRequest,
+    RemoveSubscriberRequest, RemoveSubscribersRequest, RestoreGroupRequest, RetractMessageRequest,
+    RevokeRoleRequest, SetEscalationPolicyRequest, SetQuietHoursRequest, SetSubscriberTagsRequest,
+    SetSubscriptionExpiryRequest, StreamMessagesRequest, SubmitJoinRequestRequest,
+    SubscriberTagsResponse, SubscribersResponse, SubscriptionResultResponse,
+    SubscriptionResultsResponse, TransferOwnershipRequest, UnpinMessageRequest,
+    UpdateChannelRequest, UpdateGroupRequest, UpdateMessageRequest, UpdatePreferencesRequest,
+    VerifyTokenRequest, VerifyTokenResponse,
 };
 
-use crate::service::{
-    group::DynGroupServiceTrait, message::DynMessageServiceTrait,
-    subscriber::DynSubscriberServiceTrait,
+use crate::{
+    handler::csv::{
+        parse_user_ids, subscriber_row, MAX_IMPORT_CSV_BYTES, SUBSCRIBER_CSV_HEADER,
+        SUBSCRIBER_EXPORT_PAGE_SIZE,
+    },
+    repository::{
+        channel::ChannelUpdate,
+        group::GroupUpdate,
+        message::MessageEntity,
+        role::RoleMember,
+        subscriber::{SubscriberPreferences, TagFilter, SUBSCRIBER_SORT_CREATED_AT},
+    },
+    service::{
+        channel::DynChannelServiceTrait,
+        escalation::DynEscalationServiceTrait,
+        group::DynGroupServiceTrait,
+        message::{Audience, DynMessageServiceTrait, MessagePost, MessageQuery, MessageThread},
+        subscriber::{DynSubscriberServiceTrait, SubscriptionOutcome},
+    },
 };
 
 pub struct RequestHandler {
     subscriber_service: DynSubscriberServiceTrait,
     group_service: DynGroupServiceTrait,
     message_service: DynMessageServiceTrait,
+    channel_service: DynChannelServiceTrait,
+    escalation_service: DynEscalationServiceTrait,
 }
 
 impl RequestHandler {
@@ -24,29 +67,213 @@ impl RequestHandler {
         subscriber_service: DynSubscriberServiceTrait,
         group_service: DynGroupServiceTrait,
         message_service: DynMessageServiceTrait,
+        channel_service: DynChannelServiceTrait,
+        escalation_service: DynEscalationServiceTrait,
     ) -> Self {
         Self {
             subscriber_service,
             group_service,
             message_service,
+            channel_service,
+            escalation_service,
         }
     }
+
+    /// Responses of the messages with the number of replies to them, or in
+    /// their whole thread once collapsed.
+    async fn threaded_messages(
+        &self,
+        messages: Vec<MessageEntity>,
+        collapsed: bool,
+    ) -> Result<Vec<MessageResponse>, Status> {
+        let ids = messages.iter().map(|msg| msg.id).collect::<Vec<i64>>();
+        let reply_counts = self.message_service.count_replies(&ids, collapsed).await?;
+
+        Ok(messages
+            .into_iter()
+            .map(|msg| {
+                let reply_count = reply_counts.get(&msg.id).copied().unwrap_or_default();
+                MessageResponse {
+                    reply_count,
+                    ..msg.into_message_response()
+                }
+            })
+            .collect())
+    }
 }
 
+type ExportSubscribersStream =
+    Pin<Box<dyn Stream<Item = Result<ExportSubscribersResponse, Status>> + Send>>;
+type StreamMessagesStream =
+    Pin<Box<dyn Stream<Item = Result<MessageEventResponse, Status>> + Send>>;
+
 #[tonic::async_trait]
 impl Notification for RequestHandler {
+    type ExportSubscribersStream = ExportSubscribersStream;
+    type StreamMessagesStream = StreamMessagesStream;
+
     async fn add_subscriber(
         &self,
         request: Request<AddSubscriberRequest>,
     ) -> Result<Response<NotificationResponse>, Status> {
         let req = request.into_inner();
 
+        let outcome = self
+            .subscriber_service
+            .add_subscriber(req.user_id, req.group, req.invitation_code)
+            .await?;
+
+        let message = match outcome {
+            SubscriptionOutcome::AlreadySubscribed => String::from("User is already subscribed!"),
+            _ => String::from("Successfully add subscriber!"),
+        };
+        Ok(Response::new(NotificationResponse { message }))
+    }
+
+    async fn create_invitation(
+        &self,
+        request: Request<CreateInvitationRequest>,
+    ) -> Result<Response<InvitationResponse>, Status> {
+        let req = request.into_inner();
+
+        let expires_at = req
+            .expires_at
+            .map(OffsetDateTime::from_unix_timestamp)
+            .transpose()
+            .map_err(|_| Status::invalid_argument("expires_at is not a valid timestamp"))?;
+
+        let invitation = self
+            .subscriber_service
+            .create_invitation(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                expires_at,
+                req.max_uses,
+            )
+            .await?;
+
+        Ok(Response::new(invitation.into_invitation_response()))
+    }
+
+    async fn set_subscription_expiry(
+        &self,
+        request: Request<SetSubscriptionExpiryRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        let expires_at = req
+            .expires_at
+            .map(OffsetDateTime::from_unix_timestamp)
+            .transpose()
+            .map_err(|_| Status::invalid_argument("expires_at is not a valid timestamp"))?;
+
+        self.subscriber_service
+            .set_subscription_expiry(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.user_id,
+                expires_at,
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully set subscription expiry!"),
+        }))
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        request: Request<SetSubscriberTagsRequest>,
+    ) -> Result<Response<SubscriberTagsResponse>, Status> {
+        let req = request.into_inner();
+
+        let subscriber = self
+            .subscriber_service
+            .set_subscriber_tags(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.user_id,
+                req.tags,
+            )
+            .await?;
+
+        Ok(Response::new(SubscriberTagsResponse {
+            tags: subscriber.tags,
+        }))
+    }
+
+    async fn clear_subscriber_tags(
+        &self,
+        request: Request<ClearSubscriberTagsRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.subscriber_service
+            .clear_subscriber_tags(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.user_id,
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully cleared subscriber tags!"),
+        }))
+    }
+
+    async fn submit_join_request(
+        &self,
+        request: Request<SubmitJoinRequestRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
         self.subscriber_service
-            .add_subscriber(req.user_id, req.group)
+            .submit_join_request(req.user_id, req.group)
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully submitted join request!"),
+        }))
+    }
+
+    async fn list_join_requests(
+        &self,
+        request: Request<ListJoinRequestsRequest>,
+    ) -> Result<Response<JoinRequestsResponse>, Status> {
+        let req = request.into_inner();
+
+        let join_requests = self
+            .subscriber_service
+            .list_join_requests(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+            )
+            .await?
+            .into_iter()
+            .map(|join_request| join_request.into_join_request_response())
+            .collect::<Vec<JoinRequest>>();
+
+        Ok(Response::new(JoinRequestsResponse { join_requests }))
+    }
+
+    async fn decide_join_request(
+        &self,
+        request: Request<DecideJoinRequestRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        let join_request = self
+            .subscriber_service
+            .decide_join_request(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.join_request_id,
+                req.approve,
+            )
             .await?;
 
         Ok(Response::new(NotificationResponse {
-            message: String::from("Successfully add subscriber!"),
+            message: format!("Join request {}!", join_request.status),
         }))
     }
 
@@ -65,6 +292,159 @@ impl Notification for RequestHandler {
         }))
     }
 
+    async fn add_subscribers(
+        &self,
+        request: Request<AddSubscribersRequest>,
+    ) -> Result<Response<SubscriptionResultsResponse>, Status> {
+        let req = request.into_inner();
+
+        let results = self
+            .subscriber_service
+            .add_subscribers(
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.user_ids,
+                req.groups,
+            )
+            .await?
+            .into_iter()
+            .map(|result| result.into_subscription_result_response())
+            .collect::<Vec<SubscriptionResultResponse>>();
+
+        Ok(Response::new(SubscriptionResultsResponse { results }))
+    }
+
+    async fn remove_subscribers(
+        &self,
+        request: Request<RemoveSubscribersRequest>,
+    ) -> Result<Response<SubscriptionResultsResponse>, Status> {
+        let req = request.into_inner();
+
+        let results = self
+            .subscriber_service
+            .remove_subscribers(
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.user_ids,
+                req.groups,
+            )
+            .await?
+            .into_iter()
+            .map(|result| result.into_subscription_result_response())
+            .collect::<Vec<SubscriptionResultResponse>>();
+
+        Ok(Response::new(SubscriptionResultsResponse { results }))
+    }
+
+    async fn import_subscribers(
+        &self,
+        request: Request<Streaming<ImportSubscribersRequest>>,
+    ) -> Result<Response<ImportSubscribersResponse>, Status> {
+        let mut stream = request.into_inner();
+
+        // the import options are taken from the first chunk of the stream
+        let mut options: Option<ImportSubscribersRequest> = None;
+        let mut csv = Vec::new();
+        while let Some(chunk) = stream.message().await? {
+            if csv.len() + chunk.chunk.len() > MAX_IMPORT_CSV_BYTES {
+                return Err(Status::invalid_argument(format!(
+                    "import is larger than {} bytes",
+                    MAX_IMPORT_CSV_BYTES
+                )));
+            }
+            csv.extend_from_slice(&chunk.chunk);
+            if options.is_none() {
+                options = Some(chunk);
+            }
+        }
+
+        let options = options.ok_or_else(|| Status::invalid_argument("import stream is empty"))?;
+        let csv = String::from_utf8(csv)
+            .map_err(|_| Status::invalid_argument("import is not valid utf-8"))?;
+        let user_ids = parse_user_ids(&csv).map_err(Status::invalid_argument)?;
+
+        let diff = self
+            .subscriber_service
+            .import_subscribers(
+                options.group,
+                options.actor.map(RoleMember::from).unwrap_or_default(),
+                user_ids,
+                options.replace,
+                options.dry_run,
+            )
+            .await?;
+
+        Ok(Response::new(ImportSubscribersResponse {
+            dry_run: options.dry_run,
+            added: diff.added,
+            removed: diff.removed,
+            unchanged: diff.unchanged,
+        }))
+    }
+
+    async fn export_subscribers(
+        &self,
+        request: Request<ExportSubscribersRequest>,
+    ) -> Result<Response<Self::ExportSubscribersStream>, Status> {
+        let req = request.into_inner();
+
+        let subscriber_service = self.subscriber_service.clone();
+        let export_page = move |group: String, cursor: String| {
+            let subscriber_service = subscriber_service.clone();
+            async move {
+                subscriber_service
+                    .get_subscribers(
+                        group,
+                        cursor,
+                        SUBSCRIBER_EXPORT_PAGE_SIZE,
+                        SUBSCRIBER_SORT_CREATED_AT.to_string(),
+                        false,
+                        TagFilter::default(),
+                    )
+                    .await
+            }
+        };
+
+        // the first page is read right away so that an unknown group fails the
+        // call, the following ones are read as the rows are sent
+        let mut page = export_page(req.group.clone(), String::new()).await?;
+
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_EXPORT_PAGE_SIZE as usize);
+        tokio::spawn(async move {
+            let header = ExportSubscribersResponse {
+                row: String::from(SUBSCRIBER_CSV_HEADER),
+            };
+            if sender.send(Ok(header)).await.is_err() {
+                return;
+            }
+
+            loop {
+                for subscriber in page.subscribers.iter() {
+                    let row = ExportSubscribersResponse {
+                        row: subscriber_row(subscriber),
+                    };
+                    if sender.send(Ok(row)).await.is_err() {
+                        return;
+                    }
+                }
+
+                let cursor = match page.next_cursor {
+                    Some(cursor) => cursor,
+                    None => return,
+                };
+                page = match export_page(req.group.clone(), cursor).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        let _ = sender.send(Err(e.into())).await;
+                        return;
+                    }
+                };
+            }
+        });
+
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(receiver)) as Self::ExportSubscribersStream
+        ))
+    }
+
     async fn add_group(
         &self,
         request: Request<AddGroupRequest>,
@@ -87,7 +467,10 @@ impl Notification for RequestHandler {
         let req = request.into_inner();
 
         self.group_service
-            .remove_group(req.name, req.admin_email)
+            .remove_group(
+                req.name,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+            )
             .await?;
 
         Ok(Response::new(NotificationResponse {
@@ -100,18 +483,164 @@ impl Notification for RequestHandler {
         request: Request<GetSubscribersRequest>,
     ) -> Result<Response<SubscribersResponse>, Status> {
         let req = request.into_inner();
+        let tags = TagFilter {
+            tags: req.tags,
+            match_all: req.match_all_tags,
+        };
 
-        let subscriber_entity = self
+        let page = self
             .subscriber_service
-            .list_subs_by_group(req.group)
+            .get_subscribers(
+                req.group.clone(),
+                req.cursor,
+                req.limit,
+                req.sort_by,
+                req.descending,
+                tags.clone(),
+            )
             .await?;
 
-        let subscribers = subscriber_entity
+        let subscribers = page
+            .subscribers
             .into_iter()
             .map(|sub| sub.into_subscriber_response())
             .collect::<Vec<Subscriber>>();
 
-        Ok(Response::new(SubscribersResponse { subscribers }))
+        let total_count = self
+            .subscriber_service
+            .count_subscribers(req.group, tags)
+            .await?;
+
+        Ok(Response::new(SubscribersResponse {
+            subscribers,
+            next_cursor: page.next_cursor.unwrap_or_default(),
+            total_count,
+        }))
+    }
+
+    async fn count_subscribers(
+        &self,
+        request: Request<CountSubscribersRequest>,
+    ) -> Result<Response<CountSubscribersResponse>, Status> {
+        let req = request.into_inner();
+
+        let count = self
+            .subscriber_service
+            .count_subscribers(req.group, TagFilter::default())
+            .await?;
+
+        Ok(Response::new(CountSubscribersResponse { count }))
+    }
+
+    async fn restore_group(
+        &self,
+        request: Request<RestoreGroupRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.group_service
+            .restore_group(
+                req.name,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully restored group!"),
+        }))
+    }
+
+    async fn update_group(
+        &self,
+        request: Request<UpdateGroupRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        // the admin email follows the owner role and moves with transfer_ownership
+        if req.new_admin_email.is_some() {
+            return Err(Status::invalid_argument(
+                "group admin is changed by transferring ownership",
+            ));
+        }
+
+        let update = GroupUpdate {
+            name: req.new_name,
+            description: req.description,
+            icon_url: req.icon_url,
+            visibility: req.visibility,
+            ..Default::default()
+        };
+
+        self.group_service
+            .update_group(
+                req.name,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                update,
+                req.parent,
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully updated group!"),
+        }))
+    }
+
+    async fn grant_role(
+        &self,
+        request: Request<GrantRoleRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.group_service
+            .grant_role(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.member.map(RoleMember::from).unwrap_or_default(),
+                req.role,
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully granted role!"),
+        }))
+    }
+
+    async fn revoke_role(
+        &self,
+        request: Request<RevokeRoleRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.group_service
+            .revoke_role(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.member.map(RoleMember::from).unwrap_or_default(),
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully revoked role!"),
+        }))
+    }
+
+    async fn transfer_ownership(
+        &self,
+        request: Request<TransferOwnershipRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.group_service
+            .transfer_ownership(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.new_owner.map(RoleMember::from).unwrap_or_default(),
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully transferred ownership!"),
+        }))
     }
 
     async fn get_groups(
@@ -120,16 +649,56 @@ impl Notification for RequestHandler {
     ) -> Result<Response<GroupsResponse>, Status> {
         let req = request.into_inner();
 
-        let subscriber_entity = self.group_service.list_groups_by_sub(req.user_id).await?;
+        let group_entity = self
+            .group_service
+            .list_groups_by_sub(req.user_id, req.effective)
+            .await?;
+        let subscriber_counts = self.group_service.count_subscribers(&group_entity).await?;
 
-        let groups = subscriber_entity
+        let groups = group_entity
             .into_iter()
-            .map(|sub| sub.into_group_response())
+            .map(|group| {
+                let subscriber_count = subscriber_counts.get(&group.id).copied().unwrap_or(0);
+                group.into_group_response(subscriber_count)
+            })
             .collect::<Vec<Group>>();
 
         Ok(Response::new(GroupsResponse { groups }))
     }
 
+    async fn list_groups(
+        &self,
+        request: Request<ListGroupsRequest>,
+    ) -> Result<Response<ListGroupsResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_entity = self
+            .group_service
+            .list_groups(
+                req.search.clone(),
+                req.visibility.clone(),
+                req.offset,
+                req.limit,
+            )
+            .await?;
+        let subscriber_counts = self.group_service.count_subscribers(&group_entity).await?;
+
+        let groups = group_entity
+            .into_iter()
+            .map(|group| {
+                let subscriber_count = subscriber_counts.get(&group.id).copied().unwrap_or(0);
+                group.into_group_response(subscriber_count)
+            })
+            .collect::<Vec<Group>>();
+
+        let count = self
+            .group_service
+            .count_groups(req.search, req.visibility)
+            .await?;
+
+        Ok(Response::new(ListGroupsResponse { groups, count }))
+    }
+
     async fn verify_token(
         &self,
         request: Request<VerifyTokenRequest>,
@@ -145,21 +714,372 @@ impl Notification for RequestHandler {
         request: Request<GetMessagesRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
         let req = request.into_inner();
+        let query = MessageQuery {
+            channels: req.channels,
+            patterns: req.patterns,
+            unacknowledged_only: req.unacknowledged,
+            user_id: req.user_id,
+            collapse_threads: req.collapse_threads,
+        };
 
         let messages = self
             .message_service
-            .get_messages(req.channels.clone(), req.offset, req.limit)
+            .get_messages(query.clone(), req.offset, req.limit)
+            .await?;
+        let messages = self
+            .threaded_messages(messages, query.collapse_threads)
+            .await?;
+
+        let count = self.message_service.get_messages_count(query).await?;
+
+        Ok(Response::new(MessagesResponse { messages, count }))
+    }
+
+    async fn get_thread(
+        &self,
+        request: Request<GetThreadRequest>,
+    ) -> Result<Response<MessagesResponse>, Status> {
+        let req = request.into_inner();
+
+        let messages = self.message_service.get_thread(req.id).await?;
+        let count = messages.len() as i64;
+        let messages = self.threaded_messages(messages, false).await?;
+
+        Ok(Response::new(MessagesResponse { messages, count }))
+    }
+
+    async fn acknowledge_message(
+        &self,
+        request: Request<AcknowledgeMessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let req = request.into_inner();
+
+        let message = self
+            .message_service
+            .acknowledge_message(req.id, req.user_id, req.note)
+            .await?;
+
+        Ok(Response::new(message.into_message_response()))
+    }
+
+    async fn update_message(
+        &self,
+        request: Request<UpdateMessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let req = request.into_inner();
+
+        let message = self
+            .message_service
+            .update_message(
+                req.id,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.subject,
+                req.message,
+            )
+            .await?;
+
+        Ok(Response::new(message.into_message_response()))
+    }
+
+    async fn retract_message(
+        &self,
+        request: Request<RetractMessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let req = request.into_inner();
+
+        let message = self
+            .message_service
+            .retract_message(req.id, req.actor.map(RoleMember::from).unwrap_or_default())
+            .await?;
+
+        Ok(Response::new(message.into_message_response()))
+    }
+
+    async fn get_message_history(
+        &self,
+        request: Request<GetMessageHistoryRequest>,
+    ) -> Result<Response<MessageHistoryResponse>, Status> {
+        let req = request.into_inner();
+
+        let edits = self
+            .message_service
+            .get_message_history(req.id)
             .await?
             .into_iter()
-            .map(|msg| msg.into_message_response())
-            .collect::<Vec<MessageResponse>>();
+            .map(|edit| edit.into_edit())
+            .collect();
 
-        let count = self
+        Ok(Response::new(MessageHistoryResponse { edits }))
+    }
+
+    async fn delete_message(
+        &self,
+        request: Request<DeleteMessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let req = request.into_inner();
+
+        let message = self
             .message_service
-            .get_messages_count(req.channels)
+            .delete_message(req.id, req.actor.map(RoleMember::from).unwrap_or_default())
             .await?;
 
-        Ok(Response::new(MessagesResponse { messages, count }))
+        Ok(Response::new(message.into_message_response()))
+    }
+
+    async fn dismiss_message(
+        &self,
+        request: Request<DismissMessageRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.message_service
+            .dismiss_message(req.id, req.user_id)
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully dismissed message!"),
+        }))
+    }
+
+    async fn pin_message(
+        &self,
+        request: Request<PinMessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let req = request.into_inner();
+
+        let expires_at = req
+            .expires_at
+            .map(OffsetDateTime::from_unix_timestamp)
+            .transpose()
+            .map_err(|_| Status::invalid_argument("expires_at is not a valid timestamp"))?;
+
+        let message = self
+            .message_service
+            .pin_message(
+                req.id,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                expires_at,
+            )
+            .await?;
+
+        Ok(Response::new(message.into_message_response()))
+    }
+
+    async fn unpin_message(
+        &self,
+        request: Request<UnpinMessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let req = request.into_inner();
+
+        let message = self
+            .message_service
+            .unpin_message(req.id, req.actor.map(RoleMember::from).unwrap_or_default())
+            .await?;
+
+        Ok(Response::new(message.into_message_response()))
+    }
+
+    async fn stream_messages(
+        &self,
+        request: Request<StreamMessagesRequest>,
+    ) -> Result<Response<Self::StreamMessagesStream>, Status> {
+        let req = request.into_inner();
+
+        let message_stream = self
+            .message_service
+            .stream_messages(req.user_id, req.patterns)
+            .await?;
+
+        let patterns = message_stream.patterns;
+        // lagging streams skip the events they missed instead of failing
+        let events =
+            BroadcastStream::new(message_stream.receiver).filter_map(move |event| match event {
+                Ok(event)
+                    if patterns
+                        .iter()
+                        .any(|pattern| pattern.matches(&event.message.channel)) =>
+                {
+                    Some(Ok(MessageEventResponse {
+                        event: event.event.to_string(),
+                        message: Some(event.message.into_message_response()),
+                    }))
+                }
+                _ => None,
+            });
+
+        Ok(Response::new(Box::pin(events) as Self::StreamMessagesStream))
+    }
+
+    async fn subscribe_channel_pattern(
+        &self,
+        request: Request<ChannelPatternRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.message_service
+            .subscribe_channel_pattern(req.user_id, req.pattern)
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully subscribed to channel pattern!"),
+        }))
+    }
+
+    async fn unsubscribe_channel_pattern(
+        &self,
+        request: Request<ChannelPatternRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.message_service
+            .unsubscribe_channel_pattern(req.user_id, req.pattern)
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully unsubscribed from channel pattern!"),
+        }))
+    }
+
+    async fn list_channel_patterns(
+        &self,
+        request: Request<ListChannelPatternsRequest>,
+    ) -> Result<Response<ChannelPatternsResponse>, Status> {
+        let req = request.into_inner();
+
+        let patterns = self
+            .message_service
+            .list_channel_patterns(req.user_id)
+            .await?
+            .into_iter()
+            .map(|subscription| subscription.pattern)
+            .collect::<Vec<String>>();
+
+        Ok(Response::new(ChannelPatternsResponse { patterns }))
+    }
+
+    async fn create_channel(
+        &self,
+        request: Request<CreateChannelRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.channel_service
+            .add_channel(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.name,
+                req.description,
+                req.default_priority,
+                req.retention_days,
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully created channel!"),
+        }))
+    }
+
+    async fn list_channels(
+        &self,
+        request: Request<ListChannelsRequest>,
+    ) -> Result<Response<ChannelsResponse>, Status> {
+        let req = request.into_inner();
+
+        let channels = self
+            .channel_service
+            .list_channels(req.group)
+            .await?
+            .into_iter()
+            .map(|channel| channel.into_channel_response())
+            .collect::<Vec<Channel>>();
+
+        Ok(Response::new(ChannelsResponse { channels }))
+    }
+
+    async fn update_channel(
+        &self,
+        request: Request<UpdateChannelRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        let retention_days = if req.clear_retention {
+            Some(None)
+        } else {
+            req.retention_days.map(Some)
+        };
+        let update = ChannelUpdate {
+            description: req.description,
+            default_priority: req.default_priority,
+            retention_days,
+        };
+
+        self.channel_service
+            .update_channel(
+                req.name,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                update,
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully updated channel!"),
+        }))
+    }
+
+    async fn delete_channel(
+        &self,
+        request: Request<DeleteChannelRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.channel_service
+            .remove_channel(
+                req.name,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+            )
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully deleted channel!"),
+        }))
+    }
+
+    async fn set_escalation_policy(
+        &self,
+        request: Request<SetEscalationPolicyRequest>,
+    ) -> Result<Response<EscalationPolicyResponse>, Status> {
+        let req = request.into_inner();
+
+        let steps = self
+            .escalation_service
+            .set_escalation_policy(
+                req.group,
+                req.actor.map(RoleMember::from).unwrap_or_default(),
+                req.steps,
+            )
+            .await?
+            .into_iter()
+            .map(|step| step.into_escalation_step())
+            .collect();
+
+        Ok(Response::new(EscalationPolicyResponse { steps }))
+    }
+
+    async fn get_escalation_policy(
+        &self,
+        request: Request<GetEscalationPolicyRequest>,
+    ) -> Result<Response<EscalationPolicyResponse>, Status> {
+        let req = request.into_inner();
+
+        let steps = self
+            .escalation_service
+            .get_escalation_policy(req.group)
+            .await?
+            .into_iter()
+            .map(|step| step.into_escalation_step())
+            .collect();
+
+        Ok(Response::new(EscalationPolicyResponse { steps }))
     }
 
     async fn add_message(
@@ -168,9 +1088,30 @@ impl Notification for RequestHandler {
     ) -> Result<Response<MessageResponse>, Status> {
         let req = request.into_inner();
 
+        let audience = req
+            .audience
+            .map(Audience::try_from)
+            .transpose()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let actor = req.actor.map(RoleMember::from).unwrap_or_default();
+
         let message = self
             .message_service
-            .add_message(req.channel, req.subject, req.message)
+            .add_message(
+                actor,
+                MessagePost {
+                    channel: req.channel,
+                    subject: req.subject,
+                    message: req.message,
+                    priority: req.priority,
+                    audience,
+                    thread: MessageThread {
+                        parent_id: req.parent_id,
+                        thread_key: req.thread_key,
+                    },
+                },
+            )
             .await?;
 
         Ok(Response::new(message.into_message_response()))
@@ -187,4 +1128,84 @@ impl Notification for RequestHandler {
         let message = format!("Successfully deleted {} messages", deleted_messages.len());
         Ok(Response::new(NotificationResponse { message }))
     }
+
+    async fn get_preferences(
+        &self,
+        request: Request<GetPreferencesRequest>,
+    ) -> Result<Response<PreferencesResponse>, Status> {
+        let req = request.into_inner();
+
+        let subscriber = self
+            .subscriber_service
+            .get_preferences(req.user_id, req.group)
+            .await?;
+
+        Ok(Response::new(subscriber.into_preferences_response()))
+    }
+
+    async fn update_preferences(
+        &self,
+        request: Request<UpdatePreferencesRequest>,
+    ) -> Result<Response<PreferencesResponse>, Status> {
+        let req = request.into_inner();
+
+        let muted_until = req
+            .muted_until
+            .map(OffsetDateTime::from_unix_timestamp)
+            .transpose()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let preferences = SubscriberPreferences {
+            muted_until,
+            opted_out_channels: req.opted_out_channels,
+            delivery_channels: req.delivery_channels,
+            digest_mode: req.digest_mode,
+        };
+
+        let subscriber = self
+            .subscriber_service
+            .update_preferences(req.user_id, req.group, preferences)
+            .await?;
+
+        Ok(Response::new(subscriber.into_preferences_response()))
+    }
+
+    async fn get_quiet_hours(
+        &self,
+        request: Request<GetQuietHoursRequest>,
+    ) -> Result<Response<QuietHoursResponse>, Status> {
+        let req = request.into_inner();
+
+        let quiet_hours = self.subscriber_service.get_quiet_hours(req.user_id).await?;
+
+        Ok(Response::new(quiet_hours.into_quiet_hours_response()))
+    }
+
+    async fn set_quiet_hours(
+        &self,
+        request: Request<SetQuietHoursRequest>,
+    ) -> Result<Response<QuietHoursResponse>, Status> {
+        let req = request.into_inner();
+
+        let quiet_hours = self
+            .subscriber_service
+            .set_quiet_hours(req.user_id, req.start_minute, req.end_minute, req.timezone)
+            .await?;
+
+        Ok(Response::new(quiet_hours.into_quiet_hours_response()))
+    }
+
+    async fn clear_quiet_hours(
+        &self,
+        request: Request<ClearQuietHoursRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let req = request.into_inner();
+
+        self.subscriber_service
+            .clear_quiet_hours(req.user_id)
+            .await?;
+
+        Ok(Response::new(NotificationResponse {
+            message: String::from("Successfully cleared quiet hours!"),
+        }))
+    }
 }
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::ServiceResult;
+use sqlx::types::time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::repository::{
+    delivery::IN_APP_DELIVERY_CHANNEL,
+    digest::{DigestEntity, DynDigestRepositoryTrait, NewDigest},
+    message::{DynMessageRepositoryTrait, MessageEntity},
+    subscriber::{DynSubscriberRepositoryTrait, SubscriberEntity},
+};
+
+use super::Job;
+
+const DIGEST_SUBJECT_TEMPLATE: &str = "Your {{period}} digest: {{count}} new messages";
+const DIGEST_ITEM_TEMPLATE: &str = "[{{channel}}] {{subject}}\n{{message}}\n";
+
+/// Replaces every `{{key}}` placeholder of `template` with its value.
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |rendered, (key, value)| {
+            rendered.replace(&format!("{{{{{}}}}}", key), value)
+        })
+}
+
+fn render_digest(subscriber: &SubscriberEntity, messages: &[MessageEntity]) -> NewDigest {
+    let subject = render(
+        DIGEST_SUBJECT_TEMPLATE,
+        &[
+            ("period", subscriber.digest_mode.as_str()),
+            ("count", messages.len().to_string().as_str()),
+        ],
+    );
+    let body = messages
+        .iter()
+        .map(|message| {
+            render(
+                DIGEST_ITEM_TEMPLATE,
+                &[
+                    ("channel", message.channel.as_str()),
+                    ("subject", message.subject.as_str()),
+                    ("message", message.message.as_str()),
+                ],
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let delivery_channels = subscriber
+        .delivery_channels
+        .iter()
+        .filter(|delivery_channel| *delivery_channel != IN_APP_DELIVERY_CHANNEL)
+        .cloned()
+        .collect::<Vec<String>>();
+
+    NewDigest {
+        subject,
+        body,
+        message_ids: messages.iter().map(|message| message.id).collect(),
+        delivery_channels,
+    }
+}
+
+pub struct DigestJob {
+    subscriber_repository: DynSubscriberRepositoryTrait,
+    message_repository: DynMessageRepositoryTrait,
+    digest_repository: DynDigestRepositoryTrait,
+}
+
+impl DigestJob {
+    pub fn new(
+        subscriber_repository: DynSubscriberRepositoryTrait,
+        message_repository: DynMessageRepositoryTrait,
+        digest_repository: DynDigestRepositoryTrait,
+    ) -> Self {
+        Self {
+            subscriber_repository,
+            message_repository,
+            digest_repository,
+        }
+    }
+
+    pub async fn send_due_digests(&self, now: OffsetDateTime) -> ServiceResult<Vec<DigestEntity>> {
+        let subscribers = self.subscriber_repository.list_due_digests(now).await?;
+        info!("{} subscriptions are due for a digest", subscribers.len());
+
+        let mut digests = Vec::new();
+        for subscriber in subscribers {
+            // the window starts when the digest mode was enabled, messages
+            // published before have already been delivered one by one
+            let since = match subscriber.last_digest_at {
+                Some(last_digest_at) => last_digest_at,
+                None => {
+                    error!(
+                        "digest subscription of user {} to group {} has no digest window",
+                        subscriber.user_id, subscriber.group_id
+                    );
+                    continue;
+                }
+            };
+
+            let messages = self
+                .message_repository
+                .list_undigested_messages(subscriber.user_id, subscriber.group_id, since)
+                .await?;
+
+            if messages.is_empty() {
+                self.digest_repository.skip_digest(&subscriber, now).await?;
+                continue;
+            }
+
+            let digest = render_digest(&subscriber, &messages);
+            let created_digest = self
+                .digest_repository
+                .add_digest(&subscriber, &digest, now)
+                .await?;
+            digests.push(created_digest);
+        }
+
+        info!("successfully sent {} digests", digests.len());
+        Ok(digests)
+    }
+}
+
+#[async_trait]
+impl Job for DigestJob {
+    fn name(&self) -> &'static str {
+        "digest"
+    }
+
+    async fn run(&self, now: OffsetDateTime) -> ServiceResult<()> {
+        self.send_due_digests(now).await?;
+        Ok(())
+    }
+}
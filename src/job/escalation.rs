@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::ServiceResult;
+use sqlx::types::time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::repository::{
+    delivery::{NewDelivery, IN_APP_DELIVERY_CHANNEL},
+    escalation::{DueEscalation, DynEscalationRepositoryTrait},
+    group::DynGroupRepositoryTrait,
+    message::{DynMessageRepositoryTrait, MessageFanout},
+    subscriber::{AudienceFilter, DynSubscriberRepositoryTrait},
+};
+
+use super::Job;
+
+/// Walks unacknowledged high priority messages through the escalation
+/// policy of their group, one step at a time.
+pub struct EscalationJob {
+    escalation_repository: DynEscalationRepositoryTrait,
+    message_repository: DynMessageRepositoryTrait,
+    subscriber_repository: DynSubscriberRepositoryTrait,
+    group_repository: DynGroupRepositoryTrait,
+}
+
+impl EscalationJob {
+    pub fn new(
+        escalation_repository: DynEscalationRepositoryTrait,
+        message_repository: DynMessageRepositoryTrait,
+        subscriber_repository: DynSubscriberRepositoryTrait,
+        group_repository: DynGroupRepositoryTrait,
+    ) -> Self {
+        Self {
+            escalation_repository,
+            message_repository,
+            subscriber_repository,
+            group_repository,
+        }
+    }
+
+    pub async fn escalate_messages(
+        &self,
+        now: OffsetDateTime,
+    ) -> ServiceResult<Vec<DueEscalation>> {
+        let due_escalations = self.escalation_repository.list_due_escalations(now).await?;
+
+        let mut escalations = vec![];
+        for escalation in due_escalations.into_iter() {
+            let fanout = self.escalation_fanout(&escalation).await?;
+            let escalated = self
+                .escalation_repository
+                .escalate_message(escalation.message_id, escalation.step, &fanout)
+                .await?;
+
+            if escalated {
+                escalations.push(escalation);
+            } else {
+                info!(
+                    "message {} was already escalated to step {}",
+                    escalation.message_id, escalation.step
+                );
+            }
+        }
+
+        info!("successfully escalated {} messages", escalations.len());
+        Ok(escalations)
+    }
+
+    /// Steps with a target group reach its subscribers that did not opt out of
+    /// the channel, on the step delivery channel or on their own ones. Other
+    /// steps reach the recipients of the message on the step delivery channel.
+    /// Steps targeting a deleted group are skipped without reaching anyone.
+    async fn escalation_fanout(&self, escalation: &DueEscalation) -> ServiceResult<MessageFanout> {
+        let target_group = match escalation.target_group_id {
+            Some(group_id) => match self.group_repository.get_group_by_id(group_id).await? {
+                Some(group) => Some(group),
+                None => {
+                    error!(
+                        "target group {} of escalation step {} of message {} is deleted, skipping the step",
+                        group_id, escalation.step, escalation.message_id
+                    );
+                    return Ok(MessageFanout::default());
+                }
+            },
+            None => None,
+        };
+
+        let mut fanout = MessageFanout::default();
+        match target_group {
+            Some(group) => {
+                let subscribers = self
+                    .subscriber_repository
+                    .resolve_audience(&group, &AudienceFilter::default())
+                    .await?;
+
+                for subscriber in subscribers
+                    .into_iter()
+                    .filter(|sub| !sub.is_opted_out(&escalation.channel))
+                {
+                    let delivery_channels = match &escalation.delivery_channel {
+                        Some(delivery_channel) => vec![delivery_channel.clone()],
+                        None => subscriber.delivery_channels.clone(),
+                    };
+                    fanout.deliveries.extend(
+                        delivery_channels
+                            .into_iter()
+                            .filter(|delivery_channel| *delivery_channel != IN_APP_DELIVERY_CHANNEL)
+                            .map(|delivery_channel| NewDelivery {
+                                user_id: subscriber.user_id,
+                                delivery_channel,
+                            }),
+                    );
+                    fanout.recipients.push(subscriber.user_id);
+                }
+            }
+            None => {
+                if let Some(delivery_channel) = &escalation.delivery_channel {
+                    let recipients = self
+                        .message_repository
+                        .list_recipients(escalation.message_id)
+                        .await?;
+                    fanout.deliveries = recipients
+                        .iter()
+                        .map(|user_id| NewDelivery {
+                            user_id: *user_id,
+                            delivery_channel: delivery_channel.clone(),
+                        })
+                        .collect();
+                }
+            }
+        }
+
+        Ok(fanout)
+    }
+}
+
+#[async_trait]
+impl Job for EscalationJob {
+    fn name(&self) -> &'static str {
+        "message escalation"
+    }
+
+    async fn run(&self, now: OffsetDateTime) -> ServiceResult<()> {
+        self.escalate_messages(now).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::ServiceResult;
+use sqlx::types::time::OffsetDateTime;
+use tracing::info;
+
+use crate::repository::{
+    message::DEFAULT_PRIORITY,
+    subscriber::{DynSubscriberRepositoryTrait, ExpiryNotice, SubscriberEntity},
+};
+
+use super::Job;
+
+/// Prefix of the channels expiry messages are posted on, one per group.
+pub const SUBSCRIPTION_EXPIRED_CHANNEL: &str = "subscription.expired";
+const SUBSCRIPTION_EXPIRED_SUBJECT: &str = "Subscription expired";
+const SUBSCRIPTION_EXPIRED_MESSAGE: &str = "Your subscription to this group has expired";
+
+/// Removes the subscriptions past their expiry and notifies the former
+/// subscribers in-app, with one message per group.
+pub struct SubscriptionExpiryJob {
+    subscriber_repository: DynSubscriberRepositoryTrait,
+}
+
+impl SubscriptionExpiryJob {
+    pub fn new(subscriber_repository: DynSubscriberRepositoryTrait) -> Self {
+        Self {
+            subscriber_repository,
+        }
+    }
+
+    pub async fn expire_subscriptions(
+        &self,
+        now: OffsetDateTime,
+    ) -> ServiceResult<Vec<SubscriberEntity>> {
+        let expired_subs = self
+            .subscriber_repository
+            .remove_expired_subs(
+                now,
+                &ExpiryNotice {
+                    channel_prefix: SUBSCRIPTION_EXPIRED_CHANNEL,
+                    subject: SUBSCRIPTION_EXPIRED_SUBJECT,
+                    message: SUBSCRIPTION_EXPIRED_MESSAGE,
+                    priority: DEFAULT_PRIORITY,
+                },
+            )
+            .await?;
+
+        info!("successfully expired {} subscriptions", expired_subs.len());
+        Ok(expired_subs)
+    }
+}
+
+#[async_trait]
+impl Job for SubscriptionExpiryJob {
+    fn name(&self) -> &'static str {
+        "subscription expiry"
+    }
+
+    async fn run(&self, now: OffsetDateTime) -> ServiceResult<()> {
+        self.expire_subscriptions(now).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,812 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::ServiceResult;
+use sqlx::types::time::OffsetDateTime;
+use tokio::task::JoinHandle;
+use tracing::error;
+
+pub mod digest;
+pub mod escalation;
+pub mod expiry;
+pub mod purge;
+pub mod retention;
+
+#[async_trait]
+pub trait Job {
+    fn name(&self) -> &'static str;
+    async fn run(&self, now: OffsetDateTime) -> ServiceResult<()>;
+}
+
+/// Runs `job` every `period` for as long as the service is up, failed runs are
+/// logged and retried on the next tick.
+pub fn spawn_job<J>(job: J, period: Duration) -> JoinHandle<()>
+where
+    J: Job + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = job.run(OffsetDateTime::now_utc()).await {
+                error!("{} job failed: {:?}", job.name(), e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::sync::Arc;
+
+    use sqlx::{
+        types::time::{Duration, OffsetDateTime},
+        PgPool,
+    };
+
+    use crate::{
+        job::{
+            digest::DigestJob,
+            escalation::EscalationJob,
+            expiry::{SubscriptionExpiryJob, SUBSCRIPTION_EXPIRED_CHANNEL},
+            purge::GroupPurgeJob,
+            retention::MessageRetentionJob,
+        },
+        repository::{
+            channel::{ChannelRepository, DynChannelRepositoryTrait},
+            channel_subscription::{
+                ChannelSubscriptionRepository, DynChannelSubscriptionRepositoryTrait,
+            },
+            delivery::{DeliveryRepository, DynDeliveryRepositoryTrait},
+            digest::{DigestRepository, DynDigestRepositoryTrait},
+            escalation::{DynEscalationRepositoryTrait, EscalationRepository, NewEscalationStep},
+            group::{DynGroupRepositoryTrait, GroupRepository},
+            invitation::{DynInvitationRepositoryTrait, InvitationRepository},
+            join_request::{DynJoinRequestRepositoryTrait, JoinRequestRepository},
+            message::{
+                DynMessageRepositoryTrait, MessageFanout, MessageFilter, MessageRepository,
+                NewMessage,
+            },
+            quiet_hours::{DynQuietHoursRepositoryTrait, QuietHoursRepository},
+            role::{DynRoleRepositoryTrait, RoleMember, RoleRepository},
+            subscriber::{
+                AudienceFilter, DynSubscriberRepositoryTrait, ExpiryNotice, SubscriberPreferences,
+                SubscriberRepository,
+            },
+        },
+        service::{
+            message::{Audience, DynMessageServiceTrait, MessagePost, MessageService},
+            subscriber::{DynSubscriberServiceTrait, SubscriberService},
+        },
+    };
+
+    struct AllTraits {
+        subscriber_repository: DynSubscriberRepositoryTrait,
+        group_repository: DynGroupRepositoryTrait,
+        delivery_repository: DynDeliveryRepositoryTrait,
+        message_repository: DynMessageRepositoryTrait,
+        channel_repository: DynChannelRepositoryTrait,
+        subscriber_service: DynSubscriberServiceTrait,
+        message_service: DynMessageServiceTrait,
+        digest_job: DigestJob,
+        group_purge_job: GroupPurgeJob,
+        subscription_expiry_job: SubscriptionExpiryJob,
+        message_retention_job: MessageRetentionJob,
+        escalation_repository: DynEscalationRepositoryTrait,
+        escalation_job: EscalationJob,
+    }
+
+    fn initialize_handler(pool: PgPool) -> AllTraits {
+        let subscriber_repository =
+            Arc::new(SubscriberRepository::new(pool.clone())) as DynSubscriberRepositoryTrait;
+        let group_repository =
+            Arc::new(GroupRepository::new(pool.clone())) as DynGroupRepositoryTrait;
+        let message_repository =
+            Arc::new(MessageRepository::new(pool.clone())) as DynMessageRepositoryTrait;
+        let quiet_hours_repository =
+            Arc::new(QuietHoursRepository::new(pool.clone())) as DynQuietHoursRepositoryTrait;
+        let delivery_repository =
+            Arc::new(DeliveryRepository::new(pool.clone())) as DynDeliveryRepositoryTrait;
+        let digest_repository =
+            Arc::new(DigestRepository::new(pool.clone())) as DynDigestRepositoryTrait;
+        let role_repository = Arc::new(RoleRepository::new(pool.clone())) as DynRoleRepositoryTrait;
+        let invitation_repository =
+            Arc::new(InvitationRepository::new(pool.clone())) as DynInvitationRepositoryTrait;
+        let join_request_repository =
+            Arc::new(JoinRequestRepository::new(pool.clone())) as DynJoinRequestRepositoryTrait;
+        let channel_repository =
+            Arc::new(ChannelRepository::new(pool.clone())) as DynChannelRepositoryTrait;
+        let channel_subscription_repository =
+            Arc::new(ChannelSubscriptionRepository::new(pool.clone()))
+                as DynChannelSubscriptionRepositoryTrait;
+        let escalation_repository =
+            Arc::new(EscalationRepository::new(pool.clone())) as DynEscalationRepositoryTrait;
+        let subscriber_service = Arc::new(SubscriberService::new(
+            subscriber_repository.clone(),
+            group_repository.clone(),
+            quiet_hours_repository.clone(),
+            role_repository.clone(),
+            invitation_repository,
+            join_request_repository,
+        )) as DynSubscriberServiceTrait;
+        let message_service = Arc::new(MessageService::new(
+            message_repository.clone(),
+            subscriber_repository.clone(),
+            group_repository.clone(),
+            channel_repository.clone(),
+            channel_subscription_repository,
+            role_repository,
+        )) as DynMessageServiceTrait;
+        let digest_job = DigestJob::new(
+            subscriber_repository.clone(),
+            message_repository.clone(),
+            digest_repository.clone(),
+        );
+        let group_purge_job = GroupPurgeJob::new(group_repository.clone(), Duration::days(30));
+        let subscription_expiry_job = SubscriptionExpiryJob::new(subscriber_repository.clone());
+        let message_retention_job = MessageRetentionJob::new(message_repository.clone());
+        let escalation_job = EscalationJob::new(
+            escalation_repository.clone(),
+            message_repository.clone(),
+            subscriber_repository.clone(),
+            group_repository.clone(),
+        );
+
+        AllTraits {
+            subscriber_repository,
+            group_repository,
+            delivery_repository,
+            message_repository,
+            channel_repository,
+            subscriber_service,
+            message_service,
+            digest_job,
+            group_purge_job,
+            subscription_expiry_job,
+            message_retention_job,
+            escalation_repository,
+            escalation_job,
+        }
+    }
+
+    #[sqlx::test]
+    async fn send_due_digests_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "channel", "", "normal", None)
+            .await?;
+
+        let sub_id = 0;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+        all_traits
+            .subscriber_service
+            .update_preferences(
+                sub_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: None,
+                    opted_out_channels: vec![],
+                    delivery_channels: vec!["in_app".to_string(), "email".to_string()],
+                    digest_mode: "daily".to_string(),
+                },
+            )
+            .await?;
+
+        let subject = "test_subject";
+        let audience = Audience {
+            group: group_name.to_string(),
+            filter: AudienceFilter::default(),
+        };
+        let added_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "channel".to_string(),
+                    subject: subject.to_string(),
+                    message: "message".to_string(),
+                    priority: "normal".to_string(),
+                    audience: Some(audience),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let message_deliveries = all_traits
+            .delivery_repository
+            .list_deliveries_by_message(added_message.id)
+            .await?;
+
+        assert!(message_deliveries.is_empty());
+
+        let now = OffsetDateTime::now_utc() + Duration::days(1);
+        let digests = all_traits.digest_job.send_due_digests(now).await?;
+
+        assert_eq!(digests.len(), 1);
+        let digest = digests.first().unwrap();
+        assert_eq!(digest.message_count, 1);
+        assert!(digest.body.contains(subject));
+
+        let digest_deliveries = all_traits
+            .delivery_repository
+            .list_deliveries_by_digest(digest.id)
+            .await?;
+
+        assert_eq!(digest_deliveries.len(), 1);
+        assert_eq!(digest_deliveries.first().unwrap().delivery_channel, "email");
+
+        let digests = all_traits.digest_job.send_due_digests(now).await?;
+
+        assert!(digests.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn retracted_message_deliveries_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "channel", "", "normal", None)
+            .await?;
+        for (sub_id, digest_mode) in [(0, "daily"), (1, "off")] {
+            all_traits
+                .subscriber_repository
+                .add_subscriber(sub_id, &group)
+                .await?;
+            all_traits
+                .subscriber_service
+                .update_preferences(
+                    sub_id,
+                    group_name.to_string(),
+                    SubscriberPreferences {
+                        muted_until: None,
+                        opted_out_channels: vec![],
+                        delivery_channels: vec!["in_app".to_string(), "email".to_string()],
+                        digest_mode: digest_mode.to_string(),
+                    },
+                )
+                .await?;
+        }
+
+        let added_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "channel".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    priority: "normal".to_string(),
+                    audience: Some(Audience {
+                        group: group_name.to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let message_deliveries = all_traits
+            .delivery_repository
+            .list_deliveries_by_message(added_message.id)
+            .await?;
+
+        assert_eq!(message_deliveries.len(), 1);
+
+        all_traits
+            .message_service
+            .retract_message(added_message.id, admin)
+            .await?;
+
+        let message_deliveries = all_traits
+            .delivery_repository
+            .list_deliveries_by_message(added_message.id)
+            .await?;
+
+        assert!(message_deliveries.is_empty());
+
+        let now = OffsetDateTime::now_utc() + Duration::days(1);
+        let digests = all_traits.digest_job.send_due_digests(now).await?;
+
+        assert!(digests.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn purge_groups_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        all_traits.group_repository.remove_group(group_name).await?;
+
+        let purged_groups = all_traits
+            .group_purge_job
+            .purge_groups(OffsetDateTime::now_utc())
+            .await?;
+
+        assert!(purged_groups.is_empty());
+
+        let now = OffsetDateTime::now_utc() + Duration::days(31);
+        let purged_groups = all_traits.group_purge_job.purge_groups(now).await?;
+
+        assert_eq!(purged_groups.len(), 1);
+        assert_eq!(purged_groups.first().unwrap().id, group.id);
+
+        let subs_list = all_traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert!(subs_list.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn expire_subscriptions_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        for user_id in 0..3 {
+            all_traits
+                .subscriber_repository
+                .add_subscriber(user_id, &group)
+                .await?;
+        }
+        let now = OffsetDateTime::now_utc();
+        all_traits
+            .subscriber_repository
+            .update_expiry(0, &group, Some(now - Duration::hours(1)))
+            .await?;
+        all_traits
+            .subscriber_repository
+            .update_expiry(1, &group, Some(now + Duration::hours(1)))
+            .await?;
+
+        let expired_subs = all_traits
+            .subscription_expiry_job
+            .expire_subscriptions(now)
+            .await?;
+
+        assert_eq!(expired_subs.len(), 1);
+        assert_eq!(expired_subs.first().unwrap().user_id, 0);
+
+        let expired_channel = format!("{}.group_name", SUBSCRIPTION_EXPIRED_CHANNEL);
+        let registered_channel = all_traits
+            .channel_repository
+            .get_channel(&expired_channel)
+            .await?;
+        assert_eq!(registered_channel.unwrap().group_id, group.id);
+
+        let expired_messages = all_traits
+            .message_repository
+            .get_messages(
+                &MessageFilter {
+                    channels: vec![expired_channel],
+                    ..Default::default()
+                },
+                0,
+                10,
+            )
+            .await?;
+
+        assert_eq!(expired_messages.len(), 1);
+        let expired_message = expired_messages.first().unwrap();
+        assert_eq!(expired_message.group_id, Some(group.id));
+        assert_eq!(expired_message.recipient_count, 1);
+
+        let subs_list = all_traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 2);
+
+        let expired_subs = all_traits
+            .subscription_expiry_job
+            .expire_subscriptions(now)
+            .await?;
+
+        assert!(expired_subs.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn expire_subscriptions_foreign_channel_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        let other_group = all_traits
+            .group_repository
+            .add_group("other_group", "admin_email", "token")
+            .await?;
+        let expired_channel = format!("{}.group_name", SUBSCRIPTION_EXPIRED_CHANNEL);
+        all_traits
+            .channel_repository
+            .add_channel(other_group.id, &expired_channel, "", "normal", None)
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        let now = OffsetDateTime::now_utc();
+        all_traits
+            .subscriber_repository
+            .update_expiry(0, &group, Some(now - Duration::hours(1)))
+            .await?;
+
+        let expire_result = all_traits
+            .subscription_expiry_job
+            .expire_subscriptions(now)
+            .await;
+
+        assert!(expire_result.is_err());
+
+        // the failed run leaves the expired subscription to the next one
+        let expired_subs = all_traits
+            .subscriber_repository
+            .remove_expired_subs(
+                now,
+                &ExpiryNotice {
+                    channel_prefix: "expired",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                },
+            )
+            .await?;
+
+        assert_eq!(expired_subs.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn clean_expired_messages_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "retained", "", "normal", Some(7))
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "kept", "", "normal", None)
+            .await?;
+        for channel in ["retained", "kept"] {
+            all_traits
+                .message_repository
+                .add_message(
+                    &NewMessage {
+                        channel,
+                        subject: "subject",
+                        message: "message",
+                        priority: "normal",
+                        group_id: Some(group.id),
+                        ..Default::default()
+                    },
+                    &MessageFanout::default(),
+                )
+                .await?;
+        }
+
+        let expired_messages = all_traits
+            .message_retention_job
+            .clean_expired_messages(OffsetDateTime::now_utc())
+            .await?;
+
+        assert!(expired_messages.is_empty());
+
+        let now = OffsetDateTime::now_utc() + Duration::days(8);
+        let expired_messages = all_traits
+            .message_retention_job
+            .clean_expired_messages(now)
+            .await?;
+
+        assert_eq!(expired_messages.len(), 1);
+        assert_eq!(expired_messages.first().unwrap().channel, "retained");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn escalate_messages_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        let on_call_group = all_traits
+            .group_repository
+            .add_group("on_call", "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(1, &group)
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(2, &on_call_group)
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "high", None)
+            .await?;
+        all_traits
+            .escalation_repository
+            .set_escalation_steps(
+                group.id,
+                &[
+                    NewEscalationStep {
+                        delay_minutes: 10,
+                        delivery_channel: Some("sms".to_string()),
+                        target_group_id: None,
+                    },
+                    NewEscalationStep {
+                        delay_minutes: 20,
+                        delivery_channel: None,
+                        target_group_id: Some(on_call_group.id),
+                    },
+                ],
+            )
+            .await?;
+
+        let message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: "group_name".to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let now = message.created_at;
+
+        let escalations = all_traits
+            .escalation_job
+            .escalate_messages(now + Duration::minutes(5))
+            .await?;
+        assert!(escalations.is_empty());
+
+        let escalations = all_traits
+            .escalation_job
+            .escalate_messages(now + Duration::minutes(15))
+            .await?;
+        assert_eq!(escalations.len(), 1);
+        assert_eq!(escalations.first().unwrap().step, 1);
+
+        let sms_deliveries = all_traits
+            .delivery_repository
+            .list_deliveries_by_message(message.id)
+            .await?
+            .into_iter()
+            .filter(|delivery| delivery.delivery_channel == "sms")
+            .map(|delivery| delivery.user_id)
+            .collect::<Vec<i64>>();
+        assert_eq!(sms_deliveries, vec![1]);
+
+        // a run that listed the message before it got escalated records nothing
+        let stale_escalation = all_traits
+            .escalation_repository
+            .escalate_message(
+                message.id,
+                1,
+                &MessageFanout {
+                    recipients: vec![3],
+                    deliveries: vec![],
+                },
+            )
+            .await?;
+        assert!(!stale_escalation);
+        assert!(!all_traits
+            .message_repository
+            .list_recipients(message.id)
+            .await?
+            .contains(&3));
+
+        let escalations = all_traits
+            .escalation_job
+            .escalate_messages(now + Duration::minutes(15))
+            .await?;
+        assert!(escalations.is_empty());
+
+        let escalations = all_traits
+            .escalation_job
+            .escalate_messages(now + Duration::minutes(25))
+            .await?;
+        assert_eq!(escalations.first().unwrap().step, 2);
+
+        let escalated_message = all_traits
+            .message_repository
+            .get_message(message.id)
+            .await?
+            .unwrap();
+        assert_eq!(escalated_message.escalation_step, 2);
+        assert_eq!(escalated_message.recipient_count, 2);
+
+        let acknowledged_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: "group_name".to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        all_traits
+            .message_service
+            .acknowledge_message(acknowledged_message.id, 1, None)
+            .await?;
+        let escalations = all_traits
+            .escalation_job
+            .escalate_messages(acknowledged_message.created_at + Duration::minutes(15))
+            .await?;
+        assert!(escalations.is_empty());
+
+        // steps targeting a deleted group are skipped
+        all_traits.group_repository.remove_group("on_call").await?;
+        let skipped_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: "group_name".to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        for minutes in [15, 25] {
+            all_traits
+                .escalation_job
+                .escalate_messages(skipped_message.created_at + Duration::minutes(minutes))
+                .await?;
+        }
+
+        let skipped_message = all_traits
+            .message_repository
+            .get_message(skipped_message.id)
+            .await?
+            .unwrap();
+        assert_eq!(skipped_message.escalation_step, 2);
+        assert_eq!(skipped_message.recipient_count, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn escalate_broadcast_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        let on_call_group = all_traits
+            .group_repository
+            .add_group("on_call", "admin_email", "token")
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(2, &on_call_group)
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "high", None)
+            .await?;
+        all_traits
+            .escalation_repository
+            .set_escalation_steps(
+                group.id,
+                &[NewEscalationStep {
+                    delay_minutes: 10,
+                    delivery_channel: None,
+                    target_group_id: Some(on_call_group.id),
+                }],
+            )
+            .await?;
+
+        // messages without an audience escalate through the group owning the channel
+        let message = all_traits
+            .message_service
+            .add_message(
+                admin,
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let escalations = all_traits
+            .escalation_job
+            .escalate_messages(message.created_at + Duration::minutes(15))
+            .await?;
+        assert_eq!(escalations.len(), 1);
+        assert_eq!(escalations.first().unwrap().message_id, message.id);
+        assert_eq!(
+            all_traits
+                .message_repository
+                .list_recipients(message.id)
+                .await?,
+            vec![2]
+        );
+
+        Ok(())
+    }
+}
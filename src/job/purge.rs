@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::ServiceResult;
+use sqlx::types::time::{Duration, OffsetDateTime};
+use tracing::info;
+
+use crate::repository::group::{DynGroupRepositoryTrait, GroupEntity};
+
+use super::Job;
+
+/// Hard deletes the groups that stayed deleted for longer than the grace
+/// period, along with their subscribers.
+pub struct GroupPurgeJob {
+    group_repository: DynGroupRepositoryTrait,
+    grace_period: Duration,
+}
+
+impl GroupPurgeJob {
+    pub fn new(group_repository: DynGroupRepositoryTrait, grace_period: Duration) -> Self {
+        Self {
+            group_repository,
+            grace_period,
+        }
+    }
+
+    pub async fn purge_groups(&self, now: OffsetDateTime) -> ServiceResult<Vec<GroupEntity>> {
+        let purged_groups = self
+            .group_repository
+            .purge_groups(now - self.grace_period)
+            .await?;
+
+        info!("successfully purged {} deleted groups", purged_groups.len());
+        Ok(purged_groups)
+    }
+}
+
+#[async_trait]
+impl Job for GroupPurgeJob {
+    fn name(&self) -> &'static str {
+        "group purge"
+    }
+
+    async fn run(&self, now: OffsetDateTime) -> ServiceResult<()> {
+        self.purge_groups(now).await?;
+        Ok(())
+    }
+}
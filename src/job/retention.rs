@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::ServiceResult;
+use sqlx::types::time::OffsetDateTime;
+use tracing::info;
+
+use crate::repository::message::{DynMessageRepositoryTrait, MessageEntity};
+
+use super::Job;
+
+/// Removes the messages older than the retention of their channel, channels
+/// without a retention keep their messages.
+pub struct MessageRetentionJob {
+    message_repository: DynMessageRepositoryTrait,
+}
+
+impl MessageRetentionJob {
+    pub fn new(message_repository: DynMessageRepositoryTrait) -> Self {
+        Self { message_repository }
+    }
+
+    pub async fn clean_expired_messages(
+        &self,
+        now: OffsetDateTime,
+    ) -> ServiceResult<Vec<MessageEntity>> {
+        let expired_messages = self.message_repository.clean_expired_messages(now).await?;
+
+        info!(
+            "successfully removed {} messages past their retention",
+            expired_messages.len()
+        );
+        Ok(expired_messages)
+    }
+}
+
+#[async_trait]
+impl Job for MessageRetentionJob {
+    fn name(&self) -> &'static str {
+        "message retention"
+    }
+
+    async fn run(&self, now: OffsetDateTime) -> ServiceResult<()> {
+        self.clean_expired_messages(now).await?;
+        Ok(())
+    }
+}
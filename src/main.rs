@@ -2,24 +2,45 @@ use clap::Parser;
 use dotenv::dotenv;
 use madtofan_microservice_common::notification::notification_server::NotificationServer;
 use madtofan_microservice_common::repository::connection_pool::ServiceConnectionManager;
+use sqlx::types::time;
 use std::sync::Arc;
+use std::time::Duration;
 use tonic::transport::Server;
-use tracing::{error, info};
+use tracing::info;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
 use crate::config::AppConfig;
 use crate::handler::notification::RequestHandler;
+use crate::job::digest::DigestJob;
+use crate::job::escalation::EscalationJob;
+use crate::job::expiry::SubscriptionExpiryJob;
+use crate::job::purge::GroupPurgeJob;
+use crate::job::retention::MessageRetentionJob;
+use crate::job::spawn_job;
+use crate::repository::channel::{ChannelRepository, DynChannelRepositoryTrait};
+use crate::repository::channel_subscription::{
+    ChannelSubscriptionRepository, DynChannelSubscriptionRepositoryTrait,
+};
+use crate::repository::digest::{DigestRepository, DynDigestRepositoryTrait};
+use crate::repository::escalation::{DynEscalationRepositoryTrait, EscalationRepository};
 use crate::repository::group::{DynGroupRepositoryTrait, GroupRepository};
+use crate::repository::invitation::{DynInvitationRepositoryTrait, InvitationRepository};
+use crate::repository::join_request::{DynJoinRequestRepositoryTrait, JoinRequestRepository};
 use crate::repository::message::{DynMessageRepositoryTrait, MessageRepository};
+use crate::repository::quiet_hours::{DynQuietHoursRepositoryTrait, QuietHoursRepository};
+use crate::repository::role::{DynRoleRepositoryTrait, RoleRepository};
 use crate::repository::subscriber::{DynSubscriberRepositoryTrait, SubscriberRepository};
 use crate::seed::SeedService;
+use crate::service::channel::{ChannelService, DynChannelServiceTrait};
+use crate::service::escalation::{DynEscalationServiceTrait, EscalationService};
 use crate::service::group::{DynGroupServiceTrait, GroupService};
 use crate::service::message::{DynMessageServiceTrait, MessageService};
 use crate::service::subscriber::{DynSubscriberServiceTrait, SubscriberService};
 
 mod config;
 mod handler;
+mod job;
 mod repository;
 mod seed;
 mod service;
@@ -44,7 +65,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         sqlx::migrate!()
             .run(&pg_pool)
             .await
-            .unwrap_or_else(|err| error!("There was an error during migration: {:?}", err));
+            .expect("could not run the database migrations");
     }
     info!("Database configured! initializing repositories...");
 
@@ -55,27 +76,107 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Arc::new(SubscriberRepository::new(pg_pool.clone())) as DynSubscriberRepositoryTrait;
     let group_repository =
         Arc::new(GroupRepository::new(pg_pool.clone())) as DynGroupRepositoryTrait;
-    let message_repository = Arc::new(MessageRepository::new(pg_pool)) as DynMessageRepositoryTrait;
+    let message_repository =
+        Arc::new(MessageRepository::new(pg_pool.clone())) as DynMessageRepositoryTrait;
+    let quiet_hours_repository =
+        Arc::new(QuietHoursRepository::new(pg_pool.clone())) as DynQuietHoursRepositoryTrait;
+    let digest_repository =
+        Arc::new(DigestRepository::new(pg_pool.clone())) as DynDigestRepositoryTrait;
+    let role_repository = Arc::new(RoleRepository::new(pg_pool.clone())) as DynRoleRepositoryTrait;
+    let invitation_repository =
+        Arc::new(InvitationRepository::new(pg_pool.clone())) as DynInvitationRepositoryTrait;
+    let join_request_repository =
+        Arc::new(JoinRequestRepository::new(pg_pool.clone())) as DynJoinRequestRepositoryTrait;
+    let channel_repository =
+        Arc::new(ChannelRepository::new(pg_pool.clone())) as DynChannelRepositoryTrait;
+    let channel_subscription_repository =
+        Arc::new(ChannelSubscriptionRepository::new(pg_pool.clone()))
+            as DynChannelSubscriptionRepositoryTrait;
+    let escalation_repository =
+        Arc::new(EscalationRepository::new(pg_pool)) as DynEscalationRepositoryTrait;
     info!("Repositories initialized, Initializing Services");
     let subscriber_service = Arc::new(SubscriberService::new(
-        subscriber_repository,
+        subscriber_repository.clone(),
         group_repository.clone(),
+        quiet_hours_repository,
+        role_repository.clone(),
+        invitation_repository,
+        join_request_repository,
     )) as DynSubscriberServiceTrait;
-    let group_service =
-        Arc::new(GroupService::new(group_repository.clone())) as DynGroupServiceTrait;
-    let message_service =
-        Arc::new(MessageService::new(message_repository)) as DynMessageServiceTrait;
+    let group_service = Arc::new(GroupService::new(
+        group_repository.clone(),
+        role_repository.clone(),
+    )) as DynGroupServiceTrait;
+    let message_service = Arc::new(MessageService::new(
+        message_repository.clone(),
+        subscriber_repository.clone(),
+        group_repository.clone(),
+        channel_repository.clone(),
+        channel_subscription_repository,
+        role_repository.clone(),
+    )) as DynMessageServiceTrait;
+    let channel_service = Arc::new(ChannelService::new(
+        channel_repository,
+        group_repository.clone(),
+        role_repository.clone(),
+    )) as DynChannelServiceTrait;
+    let escalation_service = Arc::new(EscalationService::new(
+        escalation_repository.clone(),
+        group_repository.clone(),
+        role_repository,
+        config.max_escalation_steps,
+    )) as DynEscalationServiceTrait;
     info!("Services initialized, Initializing Handler");
-    let request_handler = RequestHandler::new(subscriber_service, group_service, message_service);
+    let request_handler = RequestHandler::new(
+        subscriber_service,
+        group_service,
+        message_service,
+        channel_service,
+        escalation_service,
+    );
 
     if config.seed {
         info!("seeding enabled, creating test data...");
-        SeedService::new(group_repository)
+        SeedService::new(group_repository.clone())
             .seed()
             .await
             .expect("unexpected error occurred while seeding application data");
     }
 
+    info!("Starting background jobs...");
+    spawn_job(
+        DigestJob::new(
+            subscriber_repository.clone(),
+            message_repository.clone(),
+            digest_repository,
+        ),
+        Duration::from_secs(config.digest_job_interval),
+    );
+    spawn_job(
+        SubscriptionExpiryJob::new(subscriber_repository.clone()),
+        Duration::from_secs(config.subscription_expiry_job_interval),
+    );
+    spawn_job(
+        EscalationJob::new(
+            escalation_repository,
+            message_repository.clone(),
+            subscriber_repository,
+            group_repository.clone(),
+        ),
+        Duration::from_secs(config.escalation_job_interval),
+    );
+    spawn_job(
+        MessageRetentionJob::new(message_repository),
+        Duration::from_secs(config.message_retention_job_interval),
+    );
+    spawn_job(
+        GroupPurgeJob::new(
+            group_repository,
+            time::Duration::days(config.group_deletion_grace_days as i64),
+        ),
+        Duration::from_secs(config.group_purge_job_interval),
+    );
+
     info!("Service ready for request at {:#?}!", app_url);
     Server::builder()
         .add_service(NotificationServer::new(request_handler))
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    notification::channels_response::Channel, repository::connection_pool::ServiceConnectionPool,
+};
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow)]
+pub struct ChannelEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub group_id: i64,
+    pub name: String,
+    pub description: String,
+    pub default_priority: String,
+    pub retention_days: Option<i32>,
+}
+
+/// Changes to the settings of a channel, `None` leaves the field untouched.
+/// `retention_days` is set to `Some(None)` to keep the messages forever.
+#[derive(Default)]
+pub struct ChannelUpdate {
+    pub description: Option<String>,
+    pub default_priority: Option<String>,
+    pub retention_days: Option<Option<i32>>,
+}
+
+impl ChannelEntity {
+    pub fn into_channel_response(self) -> Channel {
+        Channel {
+            name: self.name,
+            description: self.description,
+            default_priority: self.default_priority,
+            retention_days: self.retention_days,
+            created_at: self.created_at.unix_timestamp(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait ChannelRepositoryTrait {
+    async fn get_channel(&self, name: &str) -> anyhow::Result<Option<ChannelEntity>>;
+    async fn add_channel(
+        &self,
+        group_id: i64,
+        name: &str,
+        description: &str,
+        default_priority: &str,
+        retention_days: Option<i32>,
+    ) -> anyhow::Result<ChannelEntity>;
+    async fn list_channels_by_group(&self, group_id: i64) -> anyhow::Result<Vec<ChannelEntity>>;
+    async fn update_channel(
+        &self,
+        name: &str,
+        update: &ChannelUpdate,
+    ) -> anyhow::Result<Option<ChannelEntity>>;
+    async fn remove_channel(&self, name: &str) -> anyhow::Result<Option<ChannelEntity>>;
+}
+
+pub type DynChannelRepositoryTrait = Arc<dyn ChannelRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct ChannelRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl ChannelRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ChannelRepositoryTrait for ChannelRepository {
+    async fn get_channel(&self, name: &str) -> anyhow::Result<Option<ChannelEntity>> {
+        query_as!(
+            ChannelEntity,
+            r#"
+                select *
+                from notification_channel
+                where name = $1::varchar
+            "#,
+            name,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for channel")
+    }
+
+    async fn add_channel(
+        &self,
+        group_id: i64,
+        name: &str,
+        description: &str,
+        default_priority: &str,
+        retention_days: Option<i32>,
+    ) -> anyhow::Result<ChannelEntity> {
+        query_as!(
+            ChannelEntity,
+            r#"
+                insert into notification_channel (
+                        group_id,
+                        name,
+                        description,
+                        default_priority,
+                        retention_days
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar,
+                        $3::varchar,
+                        $4::varchar,
+                        $5::int
+                    )
+                returning *
+            "#,
+            group_id,
+            name,
+            description,
+            default_priority,
+            retention_days,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the channel")
+    }
+
+    async fn list_channels_by_group(&self, group_id: i64) -> anyhow::Result<Vec<ChannelEntity>> {
+        query_as!(
+            ChannelEntity,
+            r#"
+                select *
+                from notification_channel
+                where group_id = $1::bigint
+                order by name
+            "#,
+            group_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing channels by group")
+    }
+
+    async fn update_channel(
+        &self,
+        name: &str,
+        update: &ChannelUpdate,
+    ) -> anyhow::Result<Option<ChannelEntity>> {
+        query_as!(
+            ChannelEntity,
+            r#"
+                update notification_channel
+                set
+                    description = coalesce($2::varchar, description),
+                    default_priority = coalesce($3::varchar, default_priority),
+                    retention_days = case when $4::boolean then $5::int else retention_days end,
+                    updated_at = current_timestamp
+                where name = $1::varchar
+                returning *
+            "#,
+            name,
+            update.description,
+            update.default_priority,
+            update.retention_days.is_some(),
+            update.retention_days.flatten(),
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while updating the channel")
+    }
+
+    async fn remove_channel(&self, name: &str) -> anyhow::Result<Option<ChannelEntity>> {
+        query_as!(
+            ChannelEntity,
+            r#"
+                delete from notification_channel
+                where name = $1::varchar
+                returning *
+            "#,
+            name,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the channel")
+    }
+}
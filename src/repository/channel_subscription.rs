@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+const SINGLE_SEGMENT_WILDCARD: &str = "*";
+const MULTI_SEGMENT_WILDCARD: &str = "#";
+
+/// A dot separated channel name where a `*` segment matches exactly one
+/// segment and a `#` segment matches zero or more segments, e.g. `billing.*`
+/// or `alerts.#`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelPattern {
+    segments: Vec<String>,
+}
+
+impl ChannelPattern {
+    /// Returns `None` when the pattern has empty segments, or segments mixing
+    /// wildcards with other characters.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let mut segments: Vec<String> = vec![];
+
+        for segment in pattern.split('.') {
+            let is_wildcard =
+                segment == SINGLE_SEGMENT_WILDCARD || segment == MULTI_SEGMENT_WILDCARD;
+            let is_literal = !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !is_wildcard && !is_literal {
+                return None;
+            }
+
+            // consecutive `#` match the same channels as a single one
+            if segment == MULTI_SEGMENT_WILDCARD
+                && segments
+                    .last()
+                    .is_some_and(|last| last == MULTI_SEGMENT_WILDCARD)
+            {
+                continue;
+            }
+            segments.push(segment.to_string());
+        }
+
+        Some(Self { segments })
+    }
+
+    pub fn matches(&self, channel: &str) -> bool {
+        let channel_segments: Vec<&str> = channel.split('.').collect();
+        matches_segments(&self.segments, &channel_segments)
+    }
+
+    /// Postgres regular expression matching the same channels as the pattern.
+    pub fn to_regex(&self) -> String {
+        let mut regex = String::from("^");
+        let mut needs_separator = false;
+
+        for segment in self.segments.iter() {
+            match segment.as_str() {
+                MULTI_SEGMENT_WILDCARD if self.segments.len() == 1 => regex.push_str(".+"),
+                MULTI_SEGMENT_WILDCARD if needs_separator => regex.push_str(r"(\.[^.]+)*"),
+                MULTI_SEGMENT_WILDCARD => regex.push_str(r"([^.]+\.)*"),
+                segment => {
+                    if needs_separator {
+                        regex.push_str(r"\.");
+                    }
+                    if segment == SINGLE_SEGMENT_WILDCARD {
+                        regex.push_str("[^.]+");
+                    } else {
+                        // literal segments only hold characters without a
+                        // special meaning in regular expressions
+                        regex.push_str(segment);
+                    }
+                    needs_separator = true;
+                }
+            }
+        }
+
+        regex.push('$');
+        regex
+    }
+}
+
+impl std::fmt::Display for ChannelPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.segments.join("."))
+    }
+}
+
+fn matches_segments(pattern: &[String], channel: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => channel.is_empty(),
+        Some((segment, rest)) if segment == MULTI_SEGMENT_WILDCARD => {
+            (0..=channel.len()).any(|skipped| matches_segments(rest, &channel[skipped..]))
+        }
+        Some((segment, rest)) => match channel.split_first() {
+            Some((channel_segment, channel_rest)) => {
+                (segment == SINGLE_SEGMENT_WILDCARD || segment == channel_segment)
+                    && matches_segments(rest, channel_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+#[derive(FromRow)]
+pub struct ChannelSubscriptionEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub user_id: i64,
+    pub pattern: String,
+}
+
+impl ChannelSubscriptionEntity {
+    pub fn channel_pattern(&self) -> Option<ChannelPattern> {
+        ChannelPattern::parse(&self.pattern)
+    }
+}
+
+#[async_trait]
+pub trait ChannelSubscriptionRepositoryTrait {
+    async fn add_channel_subscription(
+        &self,
+        user_id: i64,
+        pattern: &ChannelPattern,
+    ) -> anyhow::Result<Option<ChannelSubscriptionEntity>>;
+    async fn remove_channel_subscription(
+        &self,
+        user_id: i64,
+        pattern: &ChannelPattern,
+    ) -> anyhow::Result<Option<ChannelSubscriptionEntity>>;
+    async fn list_channel_subscriptions(
+        &self,
+        user_id: i64,
+    ) -> anyhow::Result<Vec<ChannelSubscriptionEntity>>;
+}
+
+pub type DynChannelSubscriptionRepositoryTrait =
+    Arc<dyn ChannelSubscriptionRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct ChannelSubscriptionRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl ChannelSubscriptionRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ChannelSubscriptionRepositoryTrait for ChannelSubscriptionRepository {
+    async fn add_channel_subscription(
+        &self,
+        user_id: i64,
+        pattern: &ChannelPattern,
+    ) -> anyhow::Result<Option<ChannelSubscriptionEntity>> {
+        query_as!(
+            ChannelSubscriptionEntity,
+            r#"
+                insert into notification_channel_subscription (
+                        user_id,
+                        pattern
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar
+                    )
+                on conflict (user_id, pattern) do nothing
+                returning *
+            "#,
+            user_id,
+            pattern.to_string(),
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while subscribing to the channel pattern")
+    }
+
+    async fn remove_channel_subscription(
+        &self,
+        user_id: i64,
+        pattern: &ChannelPattern,
+    ) -> anyhow::Result<Option<ChannelSubscriptionEntity>> {
+        query_as!(
+            ChannelSubscriptionEntity,
+            r#"
+                delete from notification_channel_subscription
+                where user_id = $1::bigint
+                and pattern = $2::varchar
+                returning *
+            "#,
+            user_id,
+            pattern.to_string(),
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while unsubscribing from the channel pattern")
+    }
+
+    async fn list_channel_subscriptions(
+        &self,
+        user_id: i64,
+    ) -> anyhow::Result<Vec<ChannelSubscriptionEntity>> {
+        query_as!(
+            ChannelSubscriptionEntity,
+            r#"
+                select *
+                from notification_channel_subscription
+                where user_id = $1::bigint
+                order by pattern
+            "#,
+            user_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing channel subscriptions")
+    }
+}
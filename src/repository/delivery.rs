@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+/// Delivery channel that is served by the messages stored in this service,
+/// every other delivery channel is handed over to an outbound sender.
+pub const IN_APP_DELIVERY_CHANNEL: &str = "in_app";
+pub const DELIVERY_CHANNELS: [&str; 4] = [IN_APP_DELIVERY_CHANNEL, "email", "push", "sms"];
+
+#[derive(FromRow)]
+pub struct DeliveryEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub message_id: Option<i64>,
+    pub digest_id: Option<i64>,
+    pub user_id: i64,
+    pub delivery_channel: String,
+    pub deliver_after: OffsetDateTime,
+    pub delivered_at: Option<OffsetDateTime>,
+}
+
+pub struct NewDelivery {
+    pub user_id: i64,
+    pub delivery_channel: String,
+}
+
+#[async_trait]
+pub trait DeliveryRepositoryTrait {
+    async fn list_deliveries_by_message(
+        &self,
+        message_id: i64,
+    ) -> anyhow::Result<Vec<DeliveryEntity>>;
+    async fn list_deliveries_by_digest(
+        &self,
+        digest_id: i64,
+    ) -> anyhow::Result<Vec<DeliveryEntity>>;
+}
+
+pub type DynDeliveryRepositoryTrait = Arc<dyn DeliveryRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct DeliveryRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl DeliveryRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DeliveryRepositoryTrait for DeliveryRepository {
+    async fn list_deliveries_by_message(
+        &self,
+        message_id: i64,
+    ) -> anyhow::Result<Vec<DeliveryEntity>> {
+        query_as!(
+            DeliveryEntity,
+            r#"
+                select *
+                from notification_delivery
+                where message_id = $1::bigint
+                order by id
+            "#,
+            message_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for message deliveries")
+    }
+
+    async fn list_deliveries_by_digest(
+        &self,
+        digest_id: i64,
+    ) -> anyhow::Result<Vec<DeliveryEntity>> {
+        query_as!(
+            DeliveryEntity,
+            r#"
+                select *
+                from notification_delivery
+                where digest_id = $1::bigint
+                order by id
+            "#,
+            digest_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for digest deliveries")
+    }
+}
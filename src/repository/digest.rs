@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+use super::subscriber::SubscriberEntity;
+
+#[derive(FromRow)]
+pub struct DigestEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub user_id: i64,
+    pub group_id: i64,
+    pub subject: String,
+    pub body: String,
+    pub message_count: i64,
+}
+
+pub struct NewDigest {
+    pub subject: String,
+    pub body: String,
+    pub message_ids: Vec<i64>,
+    pub delivery_channels: Vec<String>,
+}
+
+#[async_trait]
+pub trait DigestRepositoryTrait {
+    async fn add_digest(
+        &self,
+        subscriber: &SubscriberEntity,
+        digest: &NewDigest,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<DigestEntity>;
+    async fn skip_digest(
+        &self,
+        subscriber: &SubscriberEntity,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<()>;
+}
+
+pub type DynDigestRepositoryTrait = Arc<dyn DigestRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct DigestRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl DigestRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DigestRepositoryTrait for DigestRepository {
+    async fn add_digest(
+        &self,
+        subscriber: &SubscriberEntity,
+        digest: &NewDigest,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<DigestEntity> {
+        let mut tx = self.pool.begin().await?;
+
+        let created_digest = query_as!(
+            DigestEntity,
+            r#"
+                insert into notification_digest (
+                        user_id,
+                        group_id,
+                        subject,
+                        body,
+                        message_count
+                    )
+                values (
+                        $1::bigint,
+                        $2::bigint,
+                        $3::varchar,
+                        $4::varchar,
+                        $5::bigint
+                    )
+                returning *
+            "#,
+            subscriber.user_id,
+            subscriber.group_id,
+            digest.subject,
+            digest.body,
+            digest.message_ids.len() as i64,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("an unexpected error occured while creating the digest")?;
+
+        query!(
+            r#"
+                insert into notification_delivery (
+                        digest_id,
+                        user_id,
+                        delivery_channel,
+                        deliver_after
+                    )
+                select
+                    $1::bigint,
+                    $2::bigint,
+                    d.delivery_channel,
+                    case
+                        when qh.id is not null
+                        then notification_quiet_hours_end(
+                            qh.start_time,
+                            qh.end_time,
+                            qh.timezone,
+                            current_timestamp
+                        )
+                        else current_timestamp
+                    end
+                from unnest($3::text[]) as d(delivery_channel)
+                left join notification_quiet_hours as qh
+                on qh.user_id = $2::bigint
+            "#,
+            created_digest.id,
+            subscriber.user_id,
+            &digest.delivery_channels,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while queueing digest deliveries")?;
+
+        query!(
+            r#"
+                update notification_recipient
+                set digested_at = $3::timestamptz
+                where user_id = $1::bigint
+                and message_id = any($2::bigint[])
+            "#,
+            subscriber.user_id,
+            &digest.message_ids,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while marking messages as digested")?;
+
+        query!(
+            r#"
+                update notification_subscriber
+                set last_digest_at = $2::timestamptz
+                where id = $1::bigint
+            "#,
+            subscriber.id,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while updating the last digest")?;
+
+        tx.commit().await?;
+
+        Ok(created_digest)
+    }
+
+    async fn skip_digest(
+        &self,
+        subscriber: &SubscriberEntity,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+                update notification_subscriber
+                set last_digest_at = $2::timestamptz
+                where id = $1::bigint
+            "#,
+            subscriber.id,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while updating the last digest")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    notification::EscalationStep, repository::connection_pool::ServiceConnectionPool,
+};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+use super::message::{MessageFanout, URGENT_PRIORITY};
+
+/// Priorities of the messages that get escalated while nobody acknowledges
+/// them.
+pub const ESCALATED_PRIORITIES: [&str; 2] = ["high", URGENT_PRIORITY];
+
+#[derive(FromRow)]
+pub struct EscalationStepEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub group_id: i64,
+    pub step: i32,
+    pub delay_minutes: i32,
+    pub delivery_channel: Option<String>,
+    pub target_group_id: Option<i64>,
+    pub target_group: Option<String>,
+}
+
+impl EscalationStepEntity {
+    pub fn into_escalation_step(self) -> EscalationStep {
+        EscalationStep {
+            delay_minutes: self.delay_minutes,
+            delivery_channel: self.delivery_channel,
+            target_group: self.target_group,
+        }
+    }
+}
+
+/// A step of an escalation policy, fired `delay_minutes` after an
+/// unacknowledged message was posted. The message is sent again on
+/// `delivery_channel`, to the subscribers of `target_group_id` when set or to
+/// its own recipients otherwise.
+pub struct NewEscalationStep {
+    pub delay_minutes: i32,
+    pub delivery_channel: Option<String>,
+    pub target_group_id: Option<i64>,
+}
+
+/// The next escalation step due for an unacknowledged message.
+#[derive(FromRow)]
+pub struct DueEscalation {
+    pub message_id: i64,
+    pub channel: String,
+    pub step: i32,
+    pub delivery_channel: Option<String>,
+    pub target_group_id: Option<i64>,
+}
+
+#[async_trait]
+pub trait EscalationRepositoryTrait {
+    async fn list_escalation_steps(
+        &self,
+        group_id: i64,
+    ) -> anyhow::Result<Vec<EscalationStepEntity>>;
+    async fn set_escalation_steps(
+        &self,
+        group_id: i64,
+        steps: &[NewEscalationStep],
+    ) -> anyhow::Result<Vec<EscalationStepEntity>>;
+    async fn list_due_escalations(&self, now: OffsetDateTime)
+        -> anyhow::Result<Vec<DueEscalation>>;
+    async fn escalate_message(
+        &self,
+        message_id: i64,
+        step: i32,
+        fanout: &MessageFanout,
+    ) -> anyhow::Result<bool>;
+}
+
+pub type DynEscalationRepositoryTrait = Arc<dyn EscalationRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct EscalationRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl EscalationRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EscalationRepositoryTrait for EscalationRepository {
+    async fn list_escalation_steps(
+        &self,
+        group_id: i64,
+    ) -> anyhow::Result<Vec<EscalationStepEntity>> {
+        query_as!(
+            EscalationStepEntity,
+            r#"
+                select
+                    es.id,
+                    es.created_at,
+                    es.updated_at,
+                    es.group_id,
+                    es.step,
+                    es.delay_minutes,
+                    es.delivery_channel,
+                    es.target_group_id,
+                    ng.name as "target_group?"
+                from notification_escalation_step as es
+                left join notification_group as ng
+                on ng.id = es.target_group_id
+                where es.group_id = $1::bigint
+                order by es.step
+            "#,
+            group_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing escalation steps")
+    }
+
+    async fn set_escalation_steps(
+        &self,
+        group_id: i64,
+        steps: &[NewEscalationStep],
+    ) -> anyhow::Result<Vec<EscalationStepEntity>> {
+        let mut tx = self.pool.begin().await?;
+
+        query!(
+            "delete from notification_escalation_step where group_id = $1::bigint",
+            group_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while removing escalation steps")?;
+
+        for (index, step) in steps.iter().enumerate() {
+            query!(
+                r#"
+                    insert into notification_escalation_step (
+                            group_id,
+                            step,
+                            delay_minutes,
+                            delivery_channel,
+                            target_group_id
+                        )
+                    values (
+                            $1::bigint,
+                            $2::int,
+                            $3::int,
+                            $4::varchar,
+                            $5::bigint
+                        )
+                "#,
+                group_id,
+                index as i32 + 1,
+                step.delay_minutes,
+                step.delivery_channel,
+                step.target_group_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("an unexpected error occured while adding escalation step")?;
+        }
+
+        tx.commit().await?;
+
+        self.list_escalation_steps(group_id).await
+    }
+
+    async fn list_due_escalations(
+        &self,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<Vec<DueEscalation>> {
+        let priorities = ESCALATED_PRIORITIES
+            .iter()
+            .map(|priority| priority.to_string())
+            .collect::<Vec<String>>();
+
+        query_as!(
+            DueEscalation,
+            r#"
+                select
+                    nm.id as message_id,
+                    nm.channel,
+                    es.step,
+                    es.delivery_channel,
+                    es.target_group_id
+                from notification_message as nm
+                join notification_channel as nc
+                on nc.name = nm.channel
+                join notification_escalation_step as es
+                on es.group_id = nc.group_id
+                and es.step = nm.escalation_step + 1
+                where nm.acknowledged_at is null
+                and nm.retracted_at is null
+                and nm.priority = any($2::text[])
+                and nm.created_at + make_interval(mins => es.delay_minutes) <= $1::timestamptz
+                order by nm.created_at
+            "#,
+            now,
+            &priorities,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for due escalations")
+    }
+
+    /// Records `step` as done for the message and fans it out again,
+    /// deliveries of escalations skip the quiet hours of the recipients.
+    /// Nothing is recorded when the previous step is no longer the last one
+    /// done, as when another run escalated the message in the meantime.
+    async fn escalate_message(
+        &self,
+        message_id: i64,
+        step: i32,
+        fanout: &MessageFanout,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        query!(
+            r#"
+                insert into notification_recipient (
+                        message_id,
+                        user_id
+                    )
+                select $1::bigint, unnest($2::bigint[])
+                on conflict (message_id, user_id) do nothing
+            "#,
+            message_id,
+            &fanout.recipients,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while recording escalation recipients")?;
+
+        let (delivery_users, delivery_channels): (Vec<i64>, Vec<String>) = fanout
+            .deliveries
+            .iter()
+            .map(|delivery| (delivery.user_id, delivery.delivery_channel.clone()))
+            .unzip();
+
+        query!(
+            r#"
+                insert into notification_delivery (
+                        message_id,
+                        user_id,
+                        delivery_channel
+                    )
+                select $1::bigint, d.user_id, d.delivery_channel
+                from unnest($2::bigint[], $3::text[]) as d(user_id, delivery_channel)
+            "#,
+            message_id,
+            &delivery_users,
+            &delivery_channels,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while queueing escalation deliveries")?;
+
+        let escalated_message = query!(
+            r#"
+                update notification_message
+                set
+                    escalation_step = $2::int,
+                    recipient_count = (
+                        select count(*)
+                        from notification_recipient
+                        where message_id = $1::bigint
+                    )
+                where id = $1::bigint
+                and escalation_step = $2::int - 1
+            "#,
+            message_id,
+            step,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while recording message escalation")?;
+
+        if escalated_message.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
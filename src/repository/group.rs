@@ -1,11 +1,20 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Context;
 use async_trait::async_trait;
 use madtofan_microservice_common::{
     notification::groups_response::Group, repository::connection_pool::ServiceConnectionPool,
 };
-use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+pub const GROUP_VISIBILITY_PUBLIC: &str = "public";
+pub const GROUP_VISIBILITY_INVITE_ONLY: &str = "invite_only";
+pub const GROUP_VISIBILITY_CLOSED: &str = "closed";
+pub const GROUP_VISIBILITIES: [&str; 3] = [
+    GROUP_VISIBILITY_PUBLIC,
+    GROUP_VISIBILITY_INVITE_ONLY,
+    GROUP_VISIBILITY_CLOSED,
+];
 
 #[derive(FromRow)]
 pub struct GroupEntity {
@@ -15,29 +24,94 @@ pub struct GroupEntity {
     pub name: String,
     pub admin_email: String,
     pub token: String,
+    pub description: String,
+    pub icon_url: String,
+    pub visibility: String,
+    pub deleted_at: Option<OffsetDateTime>,
+    pub parent_id: Option<i64>,
+}
+
+/// Changes to the metadata of a group, `None` leaves the field untouched.
+/// `parent_id` is set to `Some(None)` to detach a group from its parent.
+#[derive(Default)]
+pub struct GroupUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon_url: Option<String>,
+    pub visibility: Option<String>,
+    pub parent_id: Option<Option<i64>>,
 }
 
 impl GroupEntity {
-    pub fn into_group_response(self) -> Group {
-        Group { name: self.name }
+    pub fn into_group_response(self, subscriber_count: i64) -> Group {
+        Group {
+            name: self.name,
+            description: self.description,
+            icon_url: self.icon_url,
+            visibility: self.visibility,
+            created_at: self.created_at.unix_timestamp(),
+            subscriber_count,
+        }
+    }
+}
+
+/// Narrows down the groups listed to the admin console, `None` matches every
+/// group.
+#[derive(Default)]
+pub struct GroupFilter {
+    pub search: Option<String>,
+    pub visibility: Option<String>,
+}
+
+impl GroupFilter {
+    /// Escapes the `like` wildcards of the search so it matches literally.
+    fn search_pattern(&self) -> Option<String> {
+        self.search.as_ref().map(|search| {
+            search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        })
     }
 }
 
 #[async_trait]
 pub trait GroupRepositoryTrait {
     async fn get_group(&self, name: &str) -> anyhow::Result<Option<GroupEntity>>;
+    async fn get_group_by_id(&self, id: i64) -> anyhow::Result<Option<GroupEntity>>;
     async fn add_group(
         &self,
         name: &str,
         admin_email: &str,
         token: &str,
     ) -> anyhow::Result<GroupEntity>;
-    async fn remove_group(
+    async fn remove_group(&self, name: &str) -> anyhow::Result<Option<GroupEntity>>;
+    async fn get_deleted_group(&self, name: &str) -> anyhow::Result<Option<GroupEntity>>;
+    async fn restore_group(&self, id: i64) -> anyhow::Result<Option<GroupEntity>>;
+    async fn purge_groups(
+        &self,
+        deleted_before: OffsetDateTime,
+    ) -> anyhow::Result<Vec<GroupEntity>>;
+    async fn update_group(
         &self,
         name: &str,
-        admin_email: &str,
+        update: &GroupUpdate,
     ) -> anyhow::Result<Option<GroupEntity>>;
-    async fn list_groups_by_sub(&self, user_id: i64) -> anyhow::Result<Vec<GroupEntity>>;
+    async fn list_descendant_ids(&self, id: i64) -> anyhow::Result<Vec<i64>>;
+    async fn list_groups_by_sub(
+        &self,
+        user_id: i64,
+        effective: bool,
+    ) -> anyhow::Result<Vec<GroupEntity>>;
+    async fn list_groups(
+        &self,
+        filter: &GroupFilter,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<GroupEntity>>;
+    async fn count_groups(&self, filter: &GroupFilter) -> anyhow::Result<i64>;
+    async fn get_groups_by_names(&self, names: &[String]) -> anyhow::Result<Vec<GroupEntity>>;
+    async fn count_subscribers(&self, group_ids: &[i64]) -> anyhow::Result<HashMap<i64, i64>>;
 }
 
 pub type DynGroupRepositoryTrait = Arc<dyn GroupRepositoryTrait + Send + Sync>;
@@ -64,10 +138,16 @@ impl GroupRepositoryTrait for GroupRepository {
                     name,
                     admin_email,
                     token,
+                    description,
+                    icon_url,
+                    visibility,
+                    deleted_at,
+                    parent_id,
                     created_at,
                     updated_at
                 from notification_group
                 where name = $1::varchar
+                and deleted_at is null
             "#,
             name,
         )
@@ -76,13 +156,31 @@ impl GroupRepositoryTrait for GroupRepository {
         .context("an unexpected error occured while searching for group")
     }
 
+    async fn get_group_by_id(&self, id: i64) -> anyhow::Result<Option<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                select *
+                from notification_group
+                where id = $1::bigint
+                and deleted_at is null
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for group")
+    }
+
     async fn add_group(
         &self,
         name: &str,
         admin_email: &str,
         token: &str,
     ) -> anyhow::Result<GroupEntity> {
-        query_as!(
+        let mut tx = self.pool.begin().await?;
+
+        let created_group = query_as!(
             GroupEntity,
             r#"
                 insert into notification_group (
@@ -101,52 +199,325 @@ impl GroupRepositoryTrait for GroupRepository {
             admin_email,
             token,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
-        .context("an unexpected error occured while creating the subscription group")
+        .context("an unexpected error occured while creating the subscription group")?;
+
+        // the admin email of a new group is its first owner
+        query!(
+            r#"
+                insert into notification_group_role (
+                        group_id,
+                        email,
+                        role
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar,
+                        'owner'
+                    )
+            "#,
+            created_group.id,
+            admin_email,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while creating the group owner")?;
+
+        tx.commit().await?;
+
+        Ok(created_group)
     }
 
-    async fn remove_group(
+    async fn remove_group(&self, name: &str) -> anyhow::Result<Option<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                update notification_group
+                set
+                    deleted_at = current_timestamp,
+                    updated_at = current_timestamp
+                where name = $1::varchar
+                and deleted_at is null
+                returning *
+            "#,
+            name,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the subscription group")
+    }
+
+    async fn get_deleted_group(&self, name: &str) -> anyhow::Result<Option<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                select *
+                from notification_group
+                where name = $1::varchar
+                and deleted_at is not null
+                order by deleted_at desc
+                limit 1
+            "#,
+            name,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for deleted group")
+    }
+
+    async fn restore_group(&self, id: i64) -> anyhow::Result<Option<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                update notification_group
+                set
+                    deleted_at = null,
+                    updated_at = current_timestamp
+                where id = $1::bigint
+                and deleted_at is not null
+                returning *
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while restoring the subscription group")
+    }
+
+    async fn purge_groups(
+        &self,
+        deleted_before: OffsetDateTime,
+    ) -> anyhow::Result<Vec<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                delete from notification_group
+                where deleted_at < $1::timestamptz
+                returning *
+            "#,
+            deleted_before,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while purging deleted groups")
+    }
+
+    async fn update_group(
         &self,
         name: &str,
-        admin_email: &str,
+        update: &GroupUpdate,
     ) -> anyhow::Result<Option<GroupEntity>> {
         query_as!(
             GroupEntity,
             r#"
-                delete from notification_group 
+                update notification_group
+                set
+                    name = coalesce($2::varchar, name),
+                    description = coalesce($3::varchar, description),
+                    icon_url = coalesce($4::varchar, icon_url),
+                    visibility = coalesce($5::varchar, visibility),
+                    parent_id = case when $6::boolean then $7::bigint else parent_id end,
+                    updated_at = current_timestamp
                 where name = $1::varchar
-                and admin_email = $2::varchar
+                and deleted_at is null
                 returning *
             "#,
             name,
-            admin_email,
+            update.name,
+            update.description,
+            update.icon_url,
+            update.visibility,
+            update.parent_id.is_some(),
+            update.parent_id.flatten(),
         )
         .fetch_optional(&self.pool)
         .await
-        .context("an unexpected error occured while removing the subscription group")
+        .context("an unexpected error occured while updating the subscription group")
     }
 
-    async fn list_groups_by_sub(&self, user_id: i64) -> anyhow::Result<Vec<GroupEntity>> {
+    async fn list_descendant_ids(&self, id: i64) -> anyhow::Result<Vec<i64>> {
+        let descendants = query!(
+            r#"
+                with recursive descendant as (
+                    select id
+                    from notification_group
+                    where id = $1::bigint
+                    union
+                    select child.id
+                    from notification_group as child
+                    join descendant
+                    on child.parent_id = descendant.id
+                )
+                select id as "id!"
+                from descendant
+            "#,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for descendant groups")?;
+
+        Ok(descendants
+            .into_iter()
+            .map(|descendant| descendant.id)
+            .collect())
+    }
+
+    async fn list_groups_by_sub(
+        &self,
+        user_id: i64,
+        effective: bool,
+    ) -> anyhow::Result<Vec<GroupEntity>> {
+        // a subscriber of a group also receives the messages of all its
+        // ancestors, which makes them effective memberships
         query_as!(
             GroupEntity,
             r#"
+                with recursive membership as (
+                    select ng.*
+                    from notification_group as ng
+                    join notification_subscriber as ns
+                    on ng.id = ns.group_id
+                    where ns.user_id = $1::bigint
+                    and (ns.expires_at is null or ns.expires_at > current_timestamp)
+                    and ng.deleted_at is null
+                    union
+                    select parent.*
+                    from notification_group as parent
+                    join membership
+                    on parent.id = membership.parent_id
+                    where $2::boolean
+                    and parent.deleted_at is null
+                )
                 select
-                    ng.id as id,
-                    ng.name as name,
-                    ng.admin_email as admin_email,
-                    ng.token as token,
-                    ng.created_at as created_at,
-                    ng.updated_at as updated_at
-                from notification_group as ng
-                join notification_subscriber as ns
-                on ng.id = ns.group_id
-                where ns.user_id = $1::bigint
+                    id as "id!",
+                    name as "name!",
+                    admin_email as "admin_email!",
+                    token as "token!",
+                    description as "description!",
+                    icon_url as "icon_url!",
+                    visibility as "visibility!",
+                    deleted_at,
+                    parent_id,
+                    created_at as "created_at!",
+                    updated_at as "updated_at!"
+                from membership
             "#,
-            user_id
+            user_id,
+            effective,
         )
         .fetch_all(&self.pool)
         .await
         .context("an unexpected error occured while search for subscribers by group")
     }
+
+    async fn list_groups(
+        &self,
+        filter: &GroupFilter,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                select
+                    id,
+                    name,
+                    admin_email,
+                    token,
+                    description,
+                    icon_url,
+                    visibility,
+                    deleted_at,
+                    parent_id,
+                    created_at,
+                    updated_at
+                from notification_group
+                where ($1::text is null or name ilike '%' || $1::text || '%' escape '\')
+                and ($2::varchar is null or visibility = $2::varchar)
+                and deleted_at is null
+                order by name
+                limit $3::bigint
+                offset $4::bigint
+            "#,
+            filter.search_pattern(),
+            filter.visibility,
+            limit,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing groups")
+    }
+
+    async fn count_groups(&self, filter: &GroupFilter) -> anyhow::Result<i64> {
+        let count_result = query!(
+            r#"
+                select count(*) as "count!"
+                from notification_group
+                where ($1::text is null or name ilike '%' || $1::text || '%' escape '\')
+                and ($2::varchar is null or visibility = $2::varchar)
+                and deleted_at is null
+            "#,
+            filter.search_pattern(),
+            filter.visibility,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while counting groups")?;
+
+        Ok(count_result.count)
+    }
+
+    async fn get_groups_by_names(&self, names: &[String]) -> anyhow::Result<Vec<GroupEntity>> {
+        query_as!(
+            GroupEntity,
+            r#"
+                select
+                    id,
+                    name,
+                    admin_email,
+                    token,
+                    description,
+                    icon_url,
+                    visibility,
+                    deleted_at,
+                    parent_id,
+                    created_at,
+                    updated_at
+                from notification_group
+                where name = any($1::text[])
+                and deleted_at is null
+            "#,
+            names,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for groups")
+    }
+
+    async fn count_subscribers(&self, group_ids: &[i64]) -> anyhow::Result<HashMap<i64, i64>> {
+        let counts = query!(
+            r#"
+                select
+                    g.id as "group_id!",
+                    count(s.id) as "count!"
+                from unnest($1::bigint[]) as g(id)
+                left join notification_subscriber as s
+                on s.group_id = g.id
+                and (s.expires_at is null or s.expires_at > current_timestamp)
+                group by g.id
+            "#,
+            group_ids,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while counting subscribers of groups")?;
+
+        Ok(counts
+            .into_iter()
+            .map(|count| (count.group_id, count.count))
+            .collect())
+    }
 }
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    notification::InvitationResponse, repository::connection_pool::ServiceConnectionPool,
+};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow)]
+pub struct InvitationEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub group_id: i64,
+    pub code: String,
+    pub expires_at: Option<OffsetDateTime>,
+    pub max_uses: Option<i32>,
+    pub use_count: i32,
+}
+
+impl InvitationEntity {
+    pub fn into_invitation_response(self) -> InvitationResponse {
+        InvitationResponse {
+            code: self.code,
+            expires_at: self.expires_at.map(|date| date.unix_timestamp()),
+            max_uses: self.max_uses,
+            use_count: self.use_count,
+        }
+    }
+}
+
+#[async_trait]
+pub trait InvitationRepositoryTrait {
+    async fn add_invitation(
+        &self,
+        group_id: i64,
+        expires_at: Option<OffsetDateTime>,
+        max_uses: Option<i32>,
+    ) -> anyhow::Result<InvitationEntity>;
+    async fn redeem_invitation(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        code: &str,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<Option<InvitationEntity>>;
+}
+
+pub type DynInvitationRepositoryTrait = Arc<dyn InvitationRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct InvitationRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl InvitationRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl InvitationRepositoryTrait for InvitationRepository {
+    async fn add_invitation(
+        &self,
+        group_id: i64,
+        expires_at: Option<OffsetDateTime>,
+        max_uses: Option<i32>,
+    ) -> anyhow::Result<InvitationEntity> {
+        query_as!(
+            InvitationEntity,
+            r#"
+                insert into notification_invitation (
+                        group_id,
+                        expires_at,
+                        max_uses
+                    )
+                values (
+                        $1::bigint,
+                        $2::timestamptz,
+                        $3::int
+                    )
+                returning *
+            "#,
+            group_id,
+            expires_at,
+            max_uses,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the invitation")
+    }
+
+    /// Uses the invitation up and subscribes the user to the group at once, so
+    /// an invitation is never used without the user getting subscribed.
+    async fn redeem_invitation(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        code: &str,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<Option<InvitationEntity>> {
+        let mut tx = self.pool.begin().await?;
+
+        // expired and used up invitations are left untouched
+        let redeemed_invitation = query_as!(
+            InvitationEntity,
+            r#"
+                update notification_invitation
+                set
+                    use_count = use_count + 1,
+                    updated_at = current_timestamp
+                where group_id = $1::bigint
+                and code = $2::varchar
+                and (expires_at is null or expires_at > $3::timestamptz)
+                and (max_uses is null or use_count < max_uses)
+                returning *
+            "#,
+            group_id,
+            code,
+            now,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("an unexpected error occured while redeeming the invitation")?;
+
+        if redeemed_invitation.is_none() {
+            return Ok(None);
+        }
+
+        query!(
+            r#"
+                insert into notification_subscriber (
+                        user_id,
+                        group_id
+                    )
+                values (
+                        $1::bigint,
+                        $2::bigint
+                    )
+                on conflict (user_id, group_id) do update
+                set
+                    expires_at = excluded.expires_at,
+                    updated_at = current_timestamp
+                where notification_subscriber.expires_at <= current_timestamp
+            "#,
+            user_id,
+            group_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while creating the invited subscriber")?;
+
+        tx.commit().await?;
+
+        Ok(redeemed_invitation)
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    notification::join_requests_response::JoinRequest,
+    repository::connection_pool::ServiceConnectionPool,
+};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+pub const JOIN_REQUEST_PENDING: &str = "pending";
+pub const JOIN_REQUEST_APPROVED: &str = "approved";
+pub const JOIN_REQUEST_REJECTED: &str = "rejected";
+
+#[derive(FromRow)]
+pub struct JoinRequestEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub group_id: i64,
+    pub user_id: i64,
+    pub status: String,
+    pub decided_at: Option<OffsetDateTime>,
+}
+
+impl JoinRequestEntity {
+    pub fn into_join_request_response(self) -> JoinRequest {
+        JoinRequest {
+            id: self.id,
+            user_id: self.user_id,
+            status: self.status,
+            created_at: self.created_at.unix_timestamp(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait JoinRequestRepositoryTrait {
+    async fn add_join_request(
+        &self,
+        group_id: i64,
+        user_id: i64,
+    ) -> anyhow::Result<Option<JoinRequestEntity>>;
+    async fn list_join_requests(
+        &self,
+        group_id: i64,
+        status: &str,
+    ) -> anyhow::Result<Vec<JoinRequestEntity>>;
+    async fn decide_join_request(
+        &self,
+        id: i64,
+        group_id: i64,
+        status: &str,
+    ) -> anyhow::Result<Option<JoinRequestEntity>>;
+}
+
+pub type DynJoinRequestRepositoryTrait = Arc<dyn JoinRequestRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct JoinRequestRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl JoinRequestRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JoinRequestRepositoryTrait for JoinRequestRepository {
+    async fn add_join_request(
+        &self,
+        group_id: i64,
+        user_id: i64,
+    ) -> anyhow::Result<Option<JoinRequestEntity>> {
+        query_as!(
+            JoinRequestEntity,
+            r#"
+                insert into notification_join_request (
+                        group_id,
+                        user_id
+                    )
+                values (
+                        $1::bigint,
+                        $2::bigint
+                    )
+                on conflict (group_id, user_id) where status = 'pending' do nothing
+                returning *
+            "#,
+            group_id,
+            user_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the join request")
+    }
+
+    async fn list_join_requests(
+        &self,
+        group_id: i64,
+        status: &str,
+    ) -> anyhow::Result<Vec<JoinRequestEntity>> {
+        query_as!(
+            JoinRequestEntity,
+            r#"
+                select *
+                from notification_join_request
+                where group_id = $1::bigint
+                and status = $2::varchar
+                order by created_at
+            "#,
+            group_id,
+            status,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for join requests")
+    }
+
+    /// Approved join requests subscribe their user in the same transaction as
+    /// the decision.
+    async fn decide_join_request(
+        &self,
+        id: i64,
+        group_id: i64,
+        status: &str,
+    ) -> anyhow::Result<Option<JoinRequestEntity>> {
+        let mut tx = self.pool.begin().await?;
+
+        let join_request = query_as!(
+            JoinRequestEntity,
+            r#"
+                update notification_join_request
+                set
+                    status = $3::varchar,
+                    decided_at = current_timestamp,
+                    updated_at = current_timestamp
+                where id = $1::bigint
+                and group_id = $2::bigint
+                and status = 'pending'
+                returning *
+            "#,
+            id,
+            group_id,
+            status,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("an unexpected error occured while deciding the join request")?;
+
+        let join_request = match join_request {
+            Some(join_request) => join_request,
+            None => return Ok(None),
+        };
+
+        if join_request.status == JOIN_REQUEST_APPROVED {
+            query!(
+                r#"
+                    insert into notification_subscriber (
+                            user_id,
+                            group_id
+                        )
+                    values (
+                            $1::bigint,
+                            $2::bigint
+                        )
+                    on conflict (user_id, group_id) do update
+                    set
+                        expires_at = excluded.expires_at,
+                        updated_at = current_timestamp
+                    where notification_subscriber.expires_at <= current_timestamp
+                "#,
+                join_request.user_id,
+                group_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("an unexpected error occured while subscribing the approved user")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(Some(join_request))
+    }
+}
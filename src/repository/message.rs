@@ -3,11 +3,24 @@ use std::sync::Arc;
 use anyhow::Context;
 use async_trait::async_trait;
 use madtofan_microservice_common::{
-    notification::MessageResponse, repository::connection_pool::ServiceConnectionPool,
+    notification::{
+        message_history_response::Edit, message_response::Acknowledgement, MessageResponse,
+    },
+    repository::connection_pool::ServiceConnectionPool,
 };
 use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
 
-#[derive(FromRow)]
+use super::{channel_subscription::ChannelPattern, delivery::NewDelivery};
+
+pub const DEFAULT_PRIORITY: &str = "normal";
+/// Urgent messages are delivered right away, even during quiet hours.
+pub const URGENT_PRIORITY: &str = "urgent";
+pub const MESSAGE_PRIORITIES: [&str; 4] = ["low", DEFAULT_PRIORITY, "high", URGENT_PRIORITY];
+/// Threads started without a key are named after their first message with
+/// this prefix.
+pub const GENERATED_THREAD_PREFIX: &str = "message-";
+
+#[derive(Clone, FromRow)]
 pub struct MessageEntity {
     pub id: i64,
     pub created_at: OffsetDateTime,
@@ -15,36 +28,195 @@ pub struct MessageEntity {
     pub channel: String,
     pub subject: String,
     pub message: String,
+    pub group_id: Option<i64>,
+    pub recipient_count: i64,
+    pub priority: String,
+    pub acknowledged_by: Option<i64>,
+    pub acknowledged_at: Option<OffsetDateTime>,
+    pub acknowledgement_note: Option<String>,
+    pub escalation_step: i32,
+    pub retracted_at: Option<OffsetDateTime>,
+    pub pinned: bool,
+    pub pinned_at: Option<OffsetDateTime>,
+    pub pin_expires_at: Option<OffsetDateTime>,
+    pub parent_id: Option<i64>,
+    pub thread_key: Option<String>,
+    pub thread: String,
 }
 
 impl MessageEntity {
+    /// Pins stop applying once they expire, without the message being
+    /// unpinned.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+            && !self
+                .pin_expires_at
+                .is_some_and(|pin_expires_at| pin_expires_at <= OffsetDateTime::now_utc())
+    }
+
     pub fn into_message_response(self) -> MessageResponse {
+        let acknowledgement = match (self.acknowledged_by, self.acknowledged_at) {
+            (Some(user_id), Some(acknowledged_at)) => Some(Acknowledgement {
+                user_id,
+                date: acknowledged_at.unix_timestamp(),
+                note: self.acknowledgement_note,
+            }),
+            _ => None,
+        };
+
+        let pinned = self.is_pinned();
+
         MessageResponse {
             id: self.id,
             subject: self.subject,
             message: self.message,
             channel: self.channel,
             date: self.created_at.unix_timestamp(),
+            recipient_count: self.recipient_count,
+            priority: self.priority,
+            acknowledgement,
+            escalation_step: self.escalation_step,
+            updated_at: self.updated_at.unix_timestamp(),
+            retracted_at: self
+                .retracted_at
+                .map(|retracted_at| retracted_at.unix_timestamp()),
+            pinned,
+            pin_expires_at: self
+                .pin_expires_at
+                .map(|pin_expires_at| pin_expires_at.unix_timestamp()),
+            parent_id: self.parent_id,
+            thread: self.thread,
+            reply_count: 0,
+        }
+    }
+}
+
+/// Subject and body of a message before one of its edits.
+#[derive(FromRow)]
+pub struct MessageEditEntity {
+    pub id: i64,
+    pub message_id: i64,
+    pub subject: String,
+    pub message: String,
+    pub created_at: OffsetDateTime,
+}
+
+impl MessageEditEntity {
+    pub fn into_edit(self) -> Edit {
+        Edit {
+            subject: self.subject,
+            message: self.message,
+            date: self.created_at.unix_timestamp(),
         }
     }
 }
 
+/// Number of replies to a message in its thread.
+#[derive(FromRow)]
+pub struct ReplyCountEntity {
+    pub message_id: i64,
+    pub reply_count: i64,
+}
+
+/// A message to post. Messages sharing a `thread_key` on a channel form a
+/// thread, messages without one start their own thread.
+#[derive(Default)]
+pub struct NewMessage<'a> {
+    pub channel: &'a str,
+    pub subject: &'a str,
+    pub message: &'a str,
+    pub priority: &'a str,
+    pub group_id: Option<i64>,
+    pub parent_id: Option<i64>,
+    pub thread_key: Option<&'a str>,
+}
+
+/// Users that receive a message in-app, and the outbound deliveries queued
+/// for them.
+#[derive(Default)]
+pub struct MessageFanout {
+    pub recipients: Vec<i64>,
+    pub deliveries: Vec<NewDelivery>,
+}
+
+/// Channels of the messages to search, listed exactly or matched by channel
+/// patterns. Messages dismissed by `dismissed_by` are left out when set, and
+/// threads are collapsed to their latest message with `collapse_threads`.
+#[derive(Default)]
+pub struct MessageFilter {
+    pub channels: Vec<String>,
+    pub patterns: Vec<ChannelPattern>,
+    pub unacknowledged_only: bool,
+    pub dismissed_by: Option<i64>,
+    pub collapse_threads: bool,
+}
+
+impl MessageFilter {
+    fn pattern_regexes(&self) -> Vec<String> {
+        self.patterns.iter().map(ChannelPattern::to_regex).collect()
+    }
+}
+
 #[async_trait]
 pub trait MessageRepositoryTrait {
     async fn get_messages(
         &self,
-        channels: Vec<String>,
+        filter: &MessageFilter,
         offset: i64,
         limit: i64,
     ) -> anyhow::Result<Vec<MessageEntity>>;
-    async fn get_messages_count(&self, channels: Vec<String>) -> anyhow::Result<i64>;
-    async fn add_message(
+    async fn get_messages_count(&self, filter: &MessageFilter) -> anyhow::Result<i64>;
+    async fn get_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>>;
+    async fn is_recipient(&self, id: i64, user_id: i64) -> anyhow::Result<bool>;
+    async fn list_recipients(&self, id: i64) -> anyhow::Result<Vec<i64>>;
+    async fn update_message(
+        &self,
+        id: i64,
+        subject: Option<&str>,
+        message: Option<&str>,
+    ) -> anyhow::Result<Option<MessageEntity>>;
+    async fn retract_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>>;
+    async fn delete_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>>;
+    async fn pin_message(
+        &self,
+        id: i64,
+        expires_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<MessageEntity>>;
+    async fn unpin_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>>;
+    async fn dismiss_message(&self, id: i64, user_id: i64) -> anyhow::Result<bool>;
+    async fn list_message_edits(&self, id: i64) -> anyhow::Result<Vec<MessageEditEntity>>;
+    async fn acknowledge_message(
+        &self,
+        id: i64,
+        user_id: i64,
+        note: Option<&str>,
+    ) -> anyhow::Result<Option<MessageEntity>>;
+    async fn list_thread_messages(
         &self,
         channel: &str,
-        subject: &str,
-        message: &str,
+        thread: &str,
+    ) -> anyhow::Result<Vec<MessageEntity>>;
+    async fn count_replies(
+        &self,
+        ids: &[i64],
+        whole_thread: bool,
+    ) -> anyhow::Result<Vec<ReplyCountEntity>>;
+    async fn add_message(
+        &self,
+        new_message: &NewMessage<'_>,
+        fanout: &MessageFanout,
     ) -> anyhow::Result<MessageEntity>;
     async fn clean_messages(&self, date: i64) -> anyhow::Result<Vec<MessageEntity>>;
+    async fn clean_expired_messages(
+        &self,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<Vec<MessageEntity>>;
+    async fn list_undigested_messages(
+        &self,
+        user_id: i64,
+        group_id: i64,
+        since: OffsetDateTime,
+    ) -> anyhow::Result<Vec<MessageEntity>>;
 }
 
 pub type DynMessageRepositoryTrait = Arc<dyn MessageRepositoryTrait + Send + Sync>;
@@ -64,7 +236,7 @@ impl MessageRepository {
 impl MessageRepositoryTrait for MessageRepository {
     async fn get_messages(
         &self,
-        channels: Vec<String>,
+        filter: &MessageFilter,
         offset: i64,
         limit: i64,
     ) -> anyhow::Result<Vec<MessageEntity>> {
@@ -73,24 +245,80 @@ impl MessageRepositoryTrait for MessageRepository {
             r#"
                 select *
                 from notification_message
-                where channel = any($1::text[])
-                order by created_at desc
+                where (channel = any($1::text[]) or channel ~ any($4::text[]))
+                and (not $5::boolean or acknowledged_at is null)
+                and not exists (
+                    select 1
+                    from notification_message_dismissal as nmd
+                    where nmd.message_id = notification_message.id
+                    and nmd.user_id = $6::bigint
+                )
+                and (not $7::boolean or not exists (
+                    select 1
+                    from notification_message as newer
+                    where newer.channel = notification_message.channel
+                    and newer.thread = notification_message.thread
+                    and (newer.created_at, newer.id) > (notification_message.created_at, notification_message.id)
+                    and (not $5::boolean or newer.acknowledged_at is null)
+                    and not exists (
+                        select 1
+                        from notification_message_dismissal as nmd
+                        where nmd.message_id = newer.id
+                        and nmd.user_id = $6::bigint
+                    )
+                ))
+                order by
+                    (pinned and (pin_expires_at is null or pin_expires_at > current_timestamp)) desc,
+                    created_at desc
                 limit $2::int
                 offset $3::int
             "#,
-            &channels,
+            &filter.channels,
             limit as i32,
             offset as i32,
+            &filter.pattern_regexes(),
+            filter.unacknowledged_only,
+            filter.dismissed_by,
+            filter.collapse_threads,
         )
         .fetch_all(&self.pool)
         .await
         .context("an unexpected error occured while searching for group")
     }
 
-    async fn get_messages_count(&self, channels: Vec<String>) -> anyhow::Result<i64> {
+    async fn get_messages_count(&self, filter: &MessageFilter) -> anyhow::Result<i64> {
         let count_result = query!(
-            "select count(*) from notification_message where channel = any($1::text[])",
-            &channels
+            r#"
+                select count(*)
+                from notification_message
+                where (channel = any($1::text[]) or channel ~ any($2::text[]))
+                and (not $3::boolean or acknowledged_at is null)
+                and not exists (
+                    select 1
+                    from notification_message_dismissal as nmd
+                    where nmd.message_id = notification_message.id
+                    and nmd.user_id = $4::bigint
+                )
+                and (not $5::boolean or not exists (
+                    select 1
+                    from notification_message as newer
+                    where newer.channel = notification_message.channel
+                    and newer.thread = notification_message.thread
+                    and (newer.created_at, newer.id) > (notification_message.created_at, notification_message.id)
+                    and (not $3::boolean or newer.acknowledged_at is null)
+                    and not exists (
+                        select 1
+                        from notification_message_dismissal as nmd
+                        where nmd.message_id = newer.id
+                        and nmd.user_id = $4::bigint
+                    )
+                ))
+            "#,
+            &filter.channels,
+            &filter.pattern_regexes(),
+            filter.unacknowledged_only,
+            filter.dismissed_by,
+            filter.collapse_threads,
         )
         .fetch_one(&self.pool)
         .await?;
@@ -98,34 +326,449 @@ impl MessageRepositoryTrait for MessageRepository {
         Ok(count_result.count.unwrap())
     }
 
-    async fn add_message(
+    async fn get_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                select *
+                from notification_message
+                where id = $1::bigint
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for message")
+    }
+
+    async fn is_recipient(&self, id: i64, user_id: i64) -> anyhow::Result<bool> {
+        let recipient_result = query!(
+            r#"
+                select exists (
+                    select 1
+                    from notification_recipient
+                    where message_id = $1::bigint
+                    and user_id = $2::bigint
+                ) as "is_recipient!"
+            "#,
+            id,
+            user_id,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for message recipient")?;
+
+        Ok(recipient_result.is_recipient)
+    }
+
+    async fn list_recipients(&self, id: i64) -> anyhow::Result<Vec<i64>> {
+        let recipients = query!(
+            r#"
+                select user_id
+                from notification_recipient
+                where message_id = $1::bigint
+                order by user_id
+            "#,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing message recipients")?;
+
+        Ok(recipients
+            .into_iter()
+            .map(|recipient| recipient.user_id)
+            .collect())
+    }
+
+    /// Keeps the previous subject and body in the edit history, retracted
+    /// messages are not edited and `None` is returned for them.
+    async fn update_message(
+        &self,
+        id: i64,
+        subject: Option<&str>,
+        message: Option<&str>,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        let mut tx = self.pool.begin().await?;
+
+        query!(
+            r#"
+                insert into notification_message_edit (
+                        message_id,
+                        subject,
+                        message
+                    )
+                select id, subject, message
+                from notification_message
+                where id = $1::bigint
+                and retracted_at is null
+            "#,
+            id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while recording the message edit")?;
+
+        let updated_message = query_as!(
+            MessageEntity,
+            r#"
+                update notification_message
+                set
+                    subject = coalesce($2::varchar, subject),
+                    message = coalesce($3::varchar, message),
+                    updated_at = current_timestamp
+                where id = $1::bigint
+                and retracted_at is null
+                returning *
+            "#,
+            id,
+            subject,
+            message,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("an unexpected error occured while updating the message")?;
+
+        tx.commit().await?;
+
+        Ok(updated_message)
+    }
+
+    /// Retracting a message also cancels its deliveries that are not sent yet.
+    async fn retract_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>> {
+        let mut tx = self.pool.begin().await?;
+
+        let retracted_message = query_as!(
+            MessageEntity,
+            r#"
+                update notification_message
+                set
+                    retracted_at = current_timestamp,
+                    pinned = false,
+                    pinned_at = null,
+                    pin_expires_at = null,
+                    updated_at = current_timestamp
+                where id = $1::bigint
+                and retracted_at is null
+                returning *
+            "#,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("an unexpected error occured while retracting the message")?;
+
+        if retracted_message.is_some() {
+            query!(
+                r#"
+                    delete from notification_delivery
+                    where message_id = $1::bigint
+                    and delivered_at is null
+                "#,
+                id,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("an unexpected error occured while cancelling the message deliveries")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(retracted_message)
+    }
+
+    async fn delete_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                delete from notification_message
+                where id = $1::bigint
+                returning *
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the message")
+    }
+
+    /// Pinning an already pinned message replaces its expiry, `None` is
+    /// returned when the message is missing or retracted.
+    async fn pin_message(
+        &self,
+        id: i64,
+        expires_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                update notification_message
+                set
+                    pinned = true,
+                    pinned_at = current_timestamp,
+                    pin_expires_at = $2::timestamptz,
+                    updated_at = current_timestamp
+                where id = $1::bigint
+                and retracted_at is null
+                returning *
+            "#,
+            id,
+            expires_at,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while pinning the message")
+    }
+
+    async fn unpin_message(&self, id: i64) -> anyhow::Result<Option<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                update notification_message
+                set
+                    pinned = false,
+                    pinned_at = null,
+                    pin_expires_at = null,
+                    updated_at = current_timestamp
+                where id = $1::bigint
+                and pinned
+                returning *
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while unpinning the message")
+    }
+
+    /// Returns `false` when the user already dismissed the message.
+    async fn dismiss_message(&self, id: i64, user_id: i64) -> anyhow::Result<bool> {
+        let dismissal_result = query!(
+            r#"
+                insert into notification_message_dismissal (
+                        message_id,
+                        user_id
+                    )
+                values (
+                        $1::bigint,
+                        $2::bigint
+                    )
+                on conflict (message_id, user_id) do nothing
+            "#,
+            id,
+            user_id,
+        )
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while dismissing the message")?;
+
+        Ok(dismissal_result.rows_affected() > 0)
+    }
+
+    async fn list_message_edits(&self, id: i64) -> anyhow::Result<Vec<MessageEditEntity>> {
+        query_as!(
+            MessageEditEntity,
+            r#"
+                select *
+                from notification_message_edit
+                where message_id = $1::bigint
+                order by created_at, id
+            "#,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing message edits")
+    }
+
+    /// Only the first acknowledgement of a message is recorded, `None` is
+    /// returned when the message is missing or already acknowledged.
+    async fn acknowledge_message(
+        &self,
+        id: i64,
+        user_id: i64,
+        note: Option<&str>,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                update notification_message
+                set
+                    acknowledged_by = $2::bigint,
+                    acknowledged_at = current_timestamp,
+                    acknowledgement_note = $3::varchar
+                where id = $1::bigint
+                and acknowledged_at is null
+                returning *
+            "#,
+            id,
+            user_id,
+            note,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while acknowledging the message")
+    }
+
+    async fn list_thread_messages(
         &self,
         channel: &str,
-        subject: &str,
-        message: &str,
-    ) -> anyhow::Result<MessageEntity> {
+        thread: &str,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
         query_as!(
+            MessageEntity,
+            r#"
+                select *
+                from notification_message
+                where channel = $1::varchar
+                and thread = $2::varchar
+                order by created_at, id
+            "#,
+            channel,
+            thread,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while listing thread messages")
+    }
+
+    /// Replies to a message are the messages posted after it in its thread.
+    /// With `whole_thread`, the message stands for its whole thread and every
+    /// other message of the thread is counted, as for collapsed threads.
+    async fn count_replies(
+        &self,
+        ids: &[i64],
+        whole_thread: bool,
+    ) -> anyhow::Result<Vec<ReplyCountEntity>> {
+        query_as!(
+            ReplyCountEntity,
+            r#"
+                select
+                    nm.id as message_id,
+                    count(reply.id) as "reply_count!"
+                from notification_message as nm
+                join notification_message as reply
+                on reply.channel = nm.channel
+                and reply.thread = nm.thread
+                and reply.id <> nm.id
+                and ($2::boolean or (reply.created_at, reply.id) > (nm.created_at, nm.id))
+                where nm.id = any($1::bigint[])
+                group by nm.id
+            "#,
+            ids,
+            whole_thread,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while counting message replies")
+    }
+
+    async fn add_message(
+        &self,
+        new_message: &NewMessage<'_>,
+        fanout: &MessageFanout,
+    ) -> anyhow::Result<MessageEntity> {
+        let mut tx = self.pool.begin().await?;
+
+        let created_message = query_as!(
             MessageEntity,
             r#"
                 insert into notification_message (
                         channel,
                         subject,
-                        message
+                        message,
+                        priority,
+                        group_id,
+                        recipient_count,
+                        parent_id,
+                        thread_key
                     )
                 values (
                         $1::varchar,
                         $2::varchar,
-                        $3::varchar
+                        $3::varchar,
+                        $4::varchar,
+                        $5::bigint,
+                        $6::bigint,
+                        $7::bigint,
+                        $8::varchar
                     )
                 returning *
             "#,
-            channel,
-            subject,
-            message,
+            new_message.channel,
+            new_message.subject,
+            new_message.message,
+            new_message.priority,
+            new_message.group_id,
+            fanout.recipients.len() as i64,
+            new_message.parent_id,
+            new_message.thread_key,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
-        .context("an unexpected error occured while creating notification message")
+        .context("an unexpected error occured while creating notification message")?;
+
+        query!(
+            r#"
+                insert into notification_recipient (
+                        message_id,
+                        user_id
+                    )
+                select $1::bigint, unnest($2::bigint[])
+            "#,
+            created_message.id,
+            &fanout.recipients,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while recording message recipients")?;
+
+        let (delivery_users, delivery_channels): (Vec<i64>, Vec<String>) = fanout
+            .deliveries
+            .iter()
+            .map(|delivery| (delivery.user_id, delivery.delivery_channel.clone()))
+            .unzip();
+
+        // deliveries of non-urgent messages are held back until the quiet
+        // hours of the recipient are over
+        query!(
+            r#"
+                insert into notification_delivery (
+                        message_id,
+                        user_id,
+                        delivery_channel,
+                        deliver_after
+                    )
+                select
+                    $1::bigint,
+                    d.user_id,
+                    d.delivery_channel,
+                    case
+                        when $4::boolean and qh.id is not null
+                        then notification_quiet_hours_end(
+                            qh.start_time,
+                            qh.end_time,
+                            qh.timezone,
+                            current_timestamp
+                        )
+                        else current_timestamp
+                    end
+                from unnest($2::bigint[], $3::text[]) as d(user_id, delivery_channel)
+                left join notification_quiet_hours as qh
+                on qh.user_id = d.user_id
+            "#,
+            created_message.id,
+            &delivery_users,
+            &delivery_channels,
+            created_message.priority != URGENT_PRIORITY,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while queueing message deliveries")?;
+
+        tx.commit().await?;
+
+        Ok(created_message)
     }
 
     async fn clean_messages(&self, date: i64) -> anyhow::Result<Vec<MessageEntity>> {
@@ -147,4 +790,53 @@ impl MessageRepositoryTrait for MessageRepository {
         .await
         .context("an unexpected error occured while removing the subscription group")
     }
+
+    async fn clean_expired_messages(
+        &self,
+        now: OffsetDateTime,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                delete from notification_message as nm
+                using notification_channel as nc
+                where nm.channel = nc.name
+                and nm.created_at < $1::timestamptz - make_interval(days => nc.retention_days)
+                returning nm.*
+            "#,
+            now,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while removing expired messages")
+    }
+
+    async fn list_undigested_messages(
+        &self,
+        user_id: i64,
+        group_id: i64,
+        since: OffsetDateTime,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                select nm.*
+                from notification_message as nm
+                join notification_recipient as nr
+                on nm.id = nr.message_id
+                where nr.user_id = $1::bigint
+                and nm.group_id = $2::bigint
+                and nm.created_at >= $3::timestamptz
+                and nm.retracted_at is null
+                and nr.digested_at is null
+                order by nm.created_at
+            "#,
+            user_id,
+            group_id,
+            since,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for undigested messages")
+    }
 }
@@ -1,24 +1,56 @@
+pub mod channel;
+pub mod channel_subscription;
+pub mod delivery;
+pub mod digest;
+pub mod escalation;
 pub mod group;
+pub mod invitation;
+pub mod join_request;
 pub mod message;
+pub mod quiet_hours;
+pub mod role;
 pub mod subscriber;
 
 #[cfg(test)]
 pub mod test {
     use std::{sync::Arc, thread, time};
 
-    use sqlx::PgPool;
+    use sqlx::{
+        types::time::{Duration, OffsetDateTime, Time},
+        PgPool,
+    };
 
-    use crate::repository::group::{DynGroupRepositoryTrait, GroupRepository};
+    use crate::repository::group::{
+        DynGroupRepositoryTrait, GroupFilter, GroupRepository, GroupUpdate,
+    };
 
     use super::{
-        message::{DynMessageRepositoryTrait, MessageRepository},
-        subscriber::{DynSubscriberRepositoryTrait, SubscriberRepository},
+        channel_subscription::{
+            ChannelPattern, ChannelSubscriptionRepository, DynChannelSubscriptionRepositoryTrait,
+        },
+        invitation::{DynInvitationRepositoryTrait, InvitationRepository},
+        join_request::{
+            DynJoinRequestRepositoryTrait, JoinRequestRepository, JOIN_REQUEST_APPROVED,
+            JOIN_REQUEST_REJECTED,
+        },
+        message::{
+            DynMessageRepositoryTrait, MessageFanout, MessageFilter, MessageRepository, NewMessage,
+        },
+        quiet_hours::{DynQuietHoursRepositoryTrait, QuietHoursRepository},
+        subscriber::{
+            AudienceFilter, DynSubscriberRepositoryTrait, ExpiryNotice, SubscriberPage,
+            SubscriberPreferences, SubscriberRepository, TagFilter, SUBSCRIBER_SORT_CREATED_AT,
+        },
     };
 
     struct AllTraits {
         subscriber_repository: DynSubscriberRepositoryTrait,
         group_repository: DynGroupRepositoryTrait,
         message_repository: DynMessageRepositoryTrait,
+        quiet_hours_repository: DynQuietHoursRepositoryTrait,
+        channel_subscription_repository: DynChannelSubscriptionRepositoryTrait,
+        invitation_repository: DynInvitationRepositoryTrait,
+        join_request_repository: DynJoinRequestRepositoryTrait,
     }
 
     fn initialize_handler(pool: PgPool) -> AllTraits {
@@ -28,11 +60,24 @@ pub mod test {
             Arc::new(GroupRepository::new(pool.clone())) as DynGroupRepositoryTrait;
         let message_repository =
             Arc::new(MessageRepository::new(pool.clone())) as DynMessageRepositoryTrait;
+        let quiet_hours_repository =
+            Arc::new(QuietHoursRepository::new(pool.clone())) as DynQuietHoursRepositoryTrait;
+        let channel_subscription_repository =
+            Arc::new(ChannelSubscriptionRepository::new(pool.clone()))
+                as DynChannelSubscriptionRepositoryTrait;
+        let invitation_repository =
+            Arc::new(InvitationRepository::new(pool.clone())) as DynInvitationRepositoryTrait;
+        let join_request_repository =
+            Arc::new(JoinRequestRepository::new(pool)) as DynJoinRequestRepositoryTrait;
 
         AllTraits {
             subscriber_repository,
             group_repository,
             message_repository,
+            quiet_hours_repository,
+            channel_subscription_repository,
+            invitation_repository,
+            join_request_repository,
         }
     }
 
@@ -64,7 +109,7 @@ pub mod test {
             .await?;
         traits
             .group_repository
-            .remove_group(group_to_remove, group_to_remove_admin_email)
+            .remove_group(group_to_remove)
             .await?;
 
         let obtained_group = traits.group_repository.get_group(group_to_remove).await?;
@@ -101,7 +146,10 @@ pub mod test {
             .add_subscriber(sub_2_id, &group2)
             .await?;
 
-        let group_list = traits.group_repository.list_groups_by_sub(sub_1_id).await?;
+        let group_list = traits
+            .group_repository
+            .list_groups_by_sub(sub_1_id, false)
+            .await?;
 
         assert_eq!(group_list.len(), 1);
         assert_eq!(group_list.first().unwrap().name, group_1_name);
@@ -109,6 +157,244 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn expired_subscriptions_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(1, &group)
+            .await?;
+
+        let expires_at = OffsetDateTime::now_utc() - Duration::minutes(1);
+        let updated_sub = traits
+            .subscriber_repository
+            .update_expiry(0, &group, Some(expires_at))
+            .await?;
+
+        assert!(updated_sub.unwrap().expires_at.is_some());
+
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 1);
+        assert_eq!(subs_list.first().unwrap().user_id, 1);
+
+        let group_list = traits.group_repository.list_groups_by_sub(0, false).await?;
+
+        assert!(group_list.is_empty());
+
+        let removed_subs = traits
+            .subscriber_repository
+            .remove_expired_subs(
+                OffsetDateTime::now_utc(),
+                &ExpiryNotice {
+                    channel_prefix: "expired",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                },
+            )
+            .await?;
+
+        assert_eq!(removed_subs.len(), 1);
+        assert!(traits
+            .subscriber_repository
+            .get_subscriber(0, &group)
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn renew_expired_subscription_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        traits
+            .subscriber_repository
+            .update_expiry(
+                0,
+                &group,
+                Some(OffsetDateTime::now_utc() - Duration::minutes(1)),
+            )
+            .await?;
+
+        assert!(traits
+            .subscriber_repository
+            .get_subscriber(0, &group)
+            .await?
+            .is_none());
+
+        let renewed_sub = traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+
+        assert!(renewed_sub.unwrap().expires_at.is_none());
+
+        traits
+            .subscriber_repository
+            .add_subscriber(1, &group)
+            .await?;
+        traits
+            .subscriber_repository
+            .update_expiry(
+                1,
+                &group,
+                Some(OffsetDateTime::now_utc() - Duration::minutes(1)),
+            )
+            .await?;
+        traits
+            .subscriber_repository
+            .import_subscribers(&group, &[1], &[])
+            .await?;
+        let removed_subs = traits
+            .subscriber_repository
+            .remove_expired_subs(
+                OffsetDateTime::now_utc(),
+                &ExpiryNotice {
+                    channel_prefix: "expired",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                },
+            )
+            .await?;
+
+        assert!(removed_subs.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_groups_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        traits
+            .group_repository
+            .add_group("group_1", "admin_email", "token")
+            .await?;
+        traits
+            .group_repository
+            .add_group("xgroup1", "admin_email", "token")
+            .await?;
+
+        // the underscore of the search is not a wildcard
+        let filter = GroupFilter {
+            search: Some("p_".to_string()),
+            visibility: None,
+        };
+        let groups = traits.group_repository.list_groups(&filter, 0, 10).await?;
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups.first().unwrap().name, "group_1");
+        assert_eq!(traits.group_repository.count_groups(&filter).await?, 1);
+
+        let groups = traits
+            .group_repository
+            .list_groups(&GroupFilter::default(), 1, 10)
+            .await?;
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups.first().unwrap().name, "xgroup1");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_subscriber_twice_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+
+        let sub_id = 0;
+        let first_subscription = traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+        let second_subscription = traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+
+        assert!(first_subscription.is_some());
+        assert!(second_subscription.is_none());
+
+        let subscribers_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subscribers_list.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bulk_subscribers_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group1 = traits
+            .group_repository
+            .add_group("group_1_name", "admin_email", "token")
+            .await?;
+        let group2 = traits
+            .group_repository
+            .add_group("group_2_name", "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group1)
+            .await?;
+
+        let groups = vec![group1, group2];
+        let added_subscribers = traits
+            .subscriber_repository
+            .add_subscribers(&[0, 1], &groups)
+            .await?;
+
+        assert_eq!(added_subscribers.len(), 3);
+
+        let removed_subscribers = traits
+            .subscriber_repository
+            .remove_subscribers(&[1], &groups)
+            .await?;
+
+        assert_eq!(removed_subscribers.len(), 2);
+
+        let group_names = vec!["group_1_name".to_string(), "group_2_name".to_string()];
+        let obtained_groups = traits
+            .group_repository
+            .get_groups_by_names(&group_names)
+            .await?;
+
+        assert_eq!(obtained_groups.len(), 2);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn list_subs_by_group_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
@@ -129,7 +415,8 @@ pub mod test {
         let sub1 = traits
             .subscriber_repository
             .add_subscriber(sub_1_id, &group1)
-            .await?;
+            .await?
+            .unwrap();
 
         let sub_2_id = 1;
         traits
@@ -148,6 +435,65 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn list_subs_page_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        for user_id in 0..3 {
+            traits
+                .subscriber_repository
+                .add_subscriber(user_id, &group)
+                .await?;
+        }
+
+        let page = SubscriberPage {
+            cursor: None,
+            limit: 2,
+            sort_by: SUBSCRIBER_SORT_CREATED_AT.to_string(),
+            descending: false,
+            tags: TagFilter::default(),
+        };
+        let first_page = traits
+            .subscriber_repository
+            .list_subs_page(&group, &page)
+            .await?;
+
+        assert_eq!(first_page.len(), 2);
+        assert_eq!(first_page.last().unwrap().user_id, 1);
+
+        // the cursor still points to the right place once its subscriber is gone
+        traits
+            .subscriber_repository
+            .remove_subscriber(1, group_name)
+            .await?;
+
+        let page = SubscriberPage {
+            cursor: Some(first_page.last().unwrap().cursor()),
+            ..page
+        };
+        let second_page = traits
+            .subscriber_repository
+            .list_subs_page(&group, &page)
+            .await?;
+
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page.first().unwrap().user_id, 2);
+
+        let count = traits
+            .subscriber_repository
+            .count_subs_by_group(&group, &TagFilter::default())
+            .await?;
+
+        assert_eq!(count, 2);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn remove_subscriber_from_group_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
@@ -168,7 +514,8 @@ pub mod test {
         let sub2 = traits
             .subscriber_repository
             .add_subscriber(sub_2_address, &group)
-            .await?;
+            .await?
+            .unwrap();
 
         traits
             .subscriber_repository
@@ -186,6 +533,292 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn resolve_audience_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+
+        for user_id in 0..4 {
+            traits
+                .subscriber_repository
+                .add_subscriber(user_id, &group)
+                .await?;
+        }
+
+        let filter = AudienceFilter {
+            include_user_ids: vec![0, 1, 2],
+            exclude_user_ids: vec![1],
+            ..Default::default()
+        };
+        let audience = traits
+            .subscriber_repository
+            .resolve_audience(&group, &filter)
+            .await?;
+
+        let user_ids = audience.iter().map(|sub| sub.user_id).collect::<Vec<i64>>();
+        assert_eq!(user_ids.len(), 2);
+        assert!(user_ids.contains(&0));
+        assert!(user_ids.contains(&2));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn resolve_audience_by_tags_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        for user_id in 0..3 {
+            traits
+                .subscriber_repository
+                .add_subscriber(user_id, &group)
+                .await?;
+        }
+        traits
+            .subscriber_repository
+            .set_tags(0, &group, &["oncall".to_string(), "sre".to_string()])
+            .await?;
+        traits
+            .subscriber_repository
+            .set_tags(1, &group, &["oncall".to_string()])
+            .await?;
+
+        let tags = vec!["oncall".to_string(), "sre".to_string()];
+        let any_filter = AudienceFilter {
+            tags: TagFilter {
+                tags: tags.clone(),
+                match_all: false,
+            },
+            ..Default::default()
+        };
+        let audience = traits
+            .subscriber_repository
+            .resolve_audience(&group, &any_filter)
+            .await?;
+
+        assert_eq!(audience.len(), 2);
+
+        let all_filter = AudienceFilter {
+            tags: TagFilter {
+                tags,
+                match_all: true,
+            },
+            ..Default::default()
+        };
+        let audience = traits
+            .subscriber_repository
+            .resolve_audience(&group, &all_filter)
+            .await?;
+
+        assert_eq!(audience.len(), 1);
+        assert_eq!(audience.first().unwrap().user_id, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn group_hierarchy_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let company = traits
+            .group_repository
+            .add_group("company", "admin_email", "token")
+            .await?;
+        let department = traits
+            .group_repository
+            .add_group("department", "admin_email", "token")
+            .await?;
+        let team = traits
+            .group_repository
+            .add_group("team", "admin_email", "token")
+            .await?;
+        traits
+            .group_repository
+            .update_group(
+                "department",
+                &GroupUpdate {
+                    parent_id: Some(Some(company.id)),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        traits
+            .group_repository
+            .update_group(
+                "team",
+                &GroupUpdate {
+                    parent_id: Some(Some(department.id)),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &company)
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &team)
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(1, &team)
+            .await?;
+
+        let audience = traits
+            .subscriber_repository
+            .resolve_audience(&company, &AudienceFilter::default())
+            .await?;
+
+        assert_eq!(audience.len(), 2);
+        let closest_sub = audience.iter().find(|sub| sub.user_id == 0).unwrap();
+        assert_eq!(closest_sub.group_id, company.id);
+
+        let audience = traits
+            .subscriber_repository
+            .resolve_audience(&department, &AudienceFilter::default())
+            .await?;
+
+        assert_eq!(audience.len(), 2);
+
+        let descendant_ids = traits
+            .group_repository
+            .list_descendant_ids(department.id)
+            .await?;
+
+        assert_eq!(descendant_ids.len(), 2);
+        assert!(descendant_ids.contains(&team.id));
+        assert!(!descendant_ids.contains(&company.id));
+
+        let direct_groups = traits.group_repository.list_groups_by_sub(1, false).await?;
+
+        assert_eq!(direct_groups.len(), 1);
+
+        let effective_groups = traits.group_repository.list_groups_by_sub(1, true).await?;
+
+        assert_eq!(effective_groups.len(), 3);
+
+        traits
+            .group_repository
+            .update_group(
+                "team",
+                &GroupUpdate {
+                    parent_id: Some(None),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let team = traits.group_repository.get_group("team").await?.unwrap();
+
+        assert!(team.parent_id.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_preferences_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+
+        let sub_id = 0;
+        traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+
+        let preferences = SubscriberPreferences {
+            muted_until: None,
+            opted_out_channels: vec!["channel".to_string()],
+            delivery_channels: vec!["email".to_string()],
+            digest_mode: "off".to_string(),
+        };
+        traits
+            .subscriber_repository
+            .update_preferences(sub_id, &group, &preferences)
+            .await?;
+
+        let subscriber = traits
+            .subscriber_repository
+            .get_subscriber(sub_id, &group)
+            .await?
+            .unwrap();
+
+        assert!(subscriber.is_opted_out("channel"));
+        assert_eq!(subscriber.delivery_channels, preferences.delivery_channels);
+        assert!(subscriber.last_digest_at.is_none());
+
+        // the digest window starts when the digest mode is enabled, and is
+        // kept through later preference updates
+        let digest_preferences = SubscriberPreferences {
+            digest_mode: "daily".to_string(),
+            ..preferences
+        };
+        let enabled_digest_at = traits
+            .subscriber_repository
+            .update_preferences(sub_id, &group, &digest_preferences)
+            .await?
+            .unwrap()
+            .last_digest_at;
+        assert!(enabled_digest_at.is_some());
+
+        let updated_subscriber = traits
+            .subscriber_repository
+            .update_preferences(
+                sub_id,
+                &group,
+                &SubscriberPreferences {
+                    opted_out_channels: vec![],
+                    ..digest_preferences
+                },
+            )
+            .await?
+            .unwrap();
+        assert_eq!(updated_subscriber.last_digest_at, enabled_digest_at);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_quiet_hours_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let sub_id = 0;
+        let start_time = Time::from_hms(22, 0, 0)?;
+        let end_time = Time::from_hms(7, 0, 0)?;
+        traits
+            .quiet_hours_repository
+            .set_quiet_hours(sub_id, end_time, start_time, "UTC")
+            .await?;
+        traits
+            .quiet_hours_repository
+            .set_quiet_hours(sub_id, start_time, end_time, "Asia/Kuala_Lumpur")
+            .await?;
+
+        let quiet_hours = traits
+            .quiet_hours_repository
+            .get_quiet_hours(sub_id)
+            .await?
+            .unwrap();
+
+        assert_eq!(quiet_hours.start_time, start_time);
+        assert_eq!(quiet_hours.end_time, end_time);
+        assert_eq!(quiet_hours.timezone, "Asia/Kuala_Lumpur");
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn add_message_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
@@ -194,7 +827,16 @@ pub mod test {
 
         let added_message = traits
             .message_repository
-            .add_message("channel1", "subject", message)
+            .add_message(
+                &NewMessage {
+                    channel: "channel1",
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         assert_eq!(added_message.message, message);
@@ -208,20 +850,41 @@ pub mod test {
 
         let channel = "channel1";
         let message = "test_message";
-        let channels = vec![channel.to_string()];
+        let filter = MessageFilter {
+            channels: vec![channel.to_string()],
+            ..Default::default()
+        };
 
         traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
         traits
             .message_repository
-            .add_message(channel, "subject", message)
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let obtained_messages = traits
             .message_repository
-            .get_messages(channels, 0, 50)
+            .get_messages(&filter, 0, 50)
             .await?;
 
         assert_eq!(obtained_messages.len(), 2);
@@ -235,24 +898,54 @@ pub mod test {
         let traits = initialize_handler(pool);
 
         let channel = "channel1";
-        let channels = vec![channel.to_string()];
+        let filter = MessageFilter {
+            channels: vec![channel.to_string()],
+            ..Default::default()
+        };
 
         traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
         traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
         traits
             .message_repository
-            .add_message("channel2", "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel: "channel2",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let message_count = traits
             .message_repository
-            .get_messages_count(channels)
+            .get_messages_count(&filter)
             .await?;
 
         assert_eq!(message_count, 2);
@@ -265,11 +958,23 @@ pub mod test {
         let traits = initialize_handler(pool);
 
         let channel = "channel1";
-        let channels = vec![channel.to_string()];
+        let filter = MessageFilter {
+            channels: vec![channel.to_string()],
+            ..Default::default()
+        };
         let message = "test_message";
         let first_message = traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let first_message_time = first_message.created_at;
@@ -278,7 +983,16 @@ pub mod test {
 
         traits
             .message_repository
-            .add_message(channel, "subject", message)
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         traits
@@ -288,7 +1002,7 @@ pub mod test {
 
         let left_messages = traits
             .message_repository
-            .get_messages(channels, 0, 50)
+            .get_messages(&filter, 0, 50)
             .await?;
 
         assert_eq!(left_messages.len(), 1);
@@ -296,4 +1010,635 @@ pub mod test {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn acknowledge_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let message = traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "alerts",
+                    subject: "subject",
+                    message: "message",
+                    priority: "urgent",
+                    ..Default::default()
+                },
+                &MessageFanout {
+                    recipients: vec![1],
+                    deliveries: vec![],
+                },
+            )
+            .await?;
+
+        assert!(
+            traits
+                .message_repository
+                .is_recipient(message.id, 1)
+                .await?
+        );
+        assert!(
+            !traits
+                .message_repository
+                .is_recipient(message.id, 2)
+                .await?
+        );
+
+        let acknowledged_message = traits
+            .message_repository
+            .acknowledge_message(message.id, 1, Some("on it"))
+            .await?
+            .unwrap();
+        assert_eq!(acknowledged_message.acknowledged_by, Some(1));
+        assert_eq!(
+            acknowledged_message.acknowledgement_note.as_deref(),
+            Some("on it")
+        );
+
+        let second_acknowledgement = traits
+            .message_repository
+            .acknowledge_message(message.id, 2, None)
+            .await?;
+        assert!(second_acknowledgement.is_none());
+
+        let filter = MessageFilter {
+            channels: vec!["alerts".to_string()],
+            unacknowledged_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages_count(&filter)
+                .await?,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let message = traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "alerts",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+
+        let updated_message = traits
+            .message_repository
+            .update_message(message.id, Some("new subject"), None)
+            .await?
+            .unwrap();
+        assert_eq!(updated_message.subject, "new subject");
+        assert_eq!(updated_message.message, "message");
+        assert!(updated_message.updated_at > message.updated_at);
+
+        traits
+            .message_repository
+            .retract_message(message.id)
+            .await?;
+        let retracted_update = traits
+            .message_repository
+            .update_message(message.id, None, Some("new message"))
+            .await?;
+        assert!(retracted_update.is_none());
+
+        let edits = traits
+            .message_repository
+            .list_message_edits(message.id)
+            .await?;
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits.first().unwrap().subject, "subject");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn pin_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let mut messages = vec![];
+        for body in ["first", "second", "third"] {
+            let message = traits
+                .message_repository
+                .add_message(
+                    &NewMessage {
+                        channel: "news",
+                        subject: "subject",
+                        message: body,
+                        priority: "normal",
+                        ..Default::default()
+                    },
+                    &MessageFanout::default(),
+                )
+                .await?;
+            messages.push(message);
+        }
+
+        let pinned_message = traits
+            .message_repository
+            .pin_message(messages[0].id, None)
+            .await?
+            .unwrap();
+        assert!(pinned_message.is_pinned());
+        let expired_message = traits
+            .message_repository
+            .pin_message(
+                messages[1].id,
+                Some(OffsetDateTime::now_utc() - Duration::minutes(1)),
+            )
+            .await?
+            .unwrap();
+        assert!(!expired_message.is_pinned());
+
+        let filter = MessageFilter {
+            channels: vec![String::from("news")],
+            ..Default::default()
+        };
+        let obtained_messages = traits
+            .message_repository
+            .get_messages(&filter, 0, 50)
+            .await?
+            .into_iter()
+            .map(|message| message.message)
+            .collect::<Vec<String>>();
+        assert_eq!(obtained_messages, vec!["first", "third", "second"]);
+
+        traits
+            .message_repository
+            .unpin_message(messages[0].id)
+            .await?
+            .unwrap();
+        assert!(traits
+            .message_repository
+            .unpin_message(messages[0].id)
+            .await?
+            .is_none());
+
+        traits
+            .message_repository
+            .retract_message(messages[2].id)
+            .await?;
+        assert!(traits
+            .message_repository
+            .pin_message(messages[2].id, None)
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn redeem_invitation_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        let now = OffsetDateTime::now_utc();
+
+        let expired_invitation = traits
+            .invitation_repository
+            .add_invitation(group.id, Some(now - Duration::minutes(1)), None)
+            .await?;
+        assert!(traits
+            .invitation_repository
+            .redeem_invitation(group.id, 0, &expired_invitation.code, now)
+            .await?
+            .is_none());
+
+        let invitation = traits
+            .invitation_repository
+            .add_invitation(group.id, Some(now + Duration::days(1)), Some(1))
+            .await?;
+        let redeemed_invitation = traits
+            .invitation_repository
+            .redeem_invitation(group.id, 0, &invitation.code, now)
+            .await?
+            .unwrap();
+        assert_eq!(redeemed_invitation.use_count, 1);
+        assert!(traits
+            .invitation_repository
+            .redeem_invitation(group.id, 1, &invitation.code, now)
+            .await?
+            .is_none());
+
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+        assert_eq!(
+            subs_list
+                .iter()
+                .map(|sub| sub.user_id)
+                .collect::<Vec<i64>>(),
+            vec![0]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn decide_join_request_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        let mut join_request_ids = vec![];
+        for user_id in [0, 1] {
+            let join_request = traits
+                .join_request_repository
+                .add_join_request(group.id, user_id)
+                .await?
+                .unwrap();
+            join_request_ids.push(join_request.id);
+        }
+
+        traits
+            .join_request_repository
+            .decide_join_request(join_request_ids[0], group.id, JOIN_REQUEST_APPROVED)
+            .await?
+            .unwrap();
+        traits
+            .join_request_repository
+            .decide_join_request(join_request_ids[1], group.id, JOIN_REQUEST_REJECTED)
+            .await?
+            .unwrap();
+        assert!(traits
+            .join_request_repository
+            .decide_join_request(join_request_ids[0], group.id, JOIN_REQUEST_APPROVED)
+            .await?
+            .is_none());
+
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+        assert_eq!(subs_list.len(), 1);
+        assert_eq!(subs_list.first().unwrap().user_id, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn thread_messages_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let opened = traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "incidents",
+                    subject: "incident",
+                    message: "opened",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+        assert_eq!(opened.thread, format!("message-{}", opened.id));
+
+        let resolved = traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "incidents",
+                    subject: "incident",
+                    message: "resolved",
+                    priority: "normal",
+                    parent_id: Some(opened.id),
+                    thread_key: Some(opened.thread.as_str()),
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+        let unrelated = traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "incidents",
+                    subject: "maintenance",
+                    message: "scheduled",
+                    priority: "normal",
+                    thread_key: Some("maintenance"),
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+
+        let thread = traits
+            .message_repository
+            .list_thread_messages("incidents", &opened.thread)
+            .await?
+            .into_iter()
+            .map(|message| message.id)
+            .collect::<Vec<i64>>();
+        assert_eq!(thread, vec![opened.id, resolved.id]);
+
+        let filter = MessageFilter {
+            channels: vec![String::from("incidents")],
+            collapse_threads: true,
+            ..Default::default()
+        };
+        let collapsed_messages = traits
+            .message_repository
+            .get_messages(&filter, 0, 50)
+            .await?
+            .into_iter()
+            .map(|message| message.id)
+            .collect::<Vec<i64>>();
+        assert_eq!(collapsed_messages, vec![unrelated.id, resolved.id]);
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages_count(&filter)
+                .await?,
+            2
+        );
+
+        let reply_counts = traits
+            .message_repository
+            .count_replies(&[resolved.id, unrelated.id], true)
+            .await?;
+        assert_eq!(reply_counts.len(), 1);
+        assert_eq!(reply_counts.first().unwrap().message_id, resolved.id);
+        assert_eq!(reply_counts.first().unwrap().reply_count, 1);
+
+        // outside of collapsed threads only the later messages are replies
+        let reply_counts = traits
+            .message_repository
+            .count_replies(&[opened.id, resolved.id], false)
+            .await?;
+        assert_eq!(reply_counts.len(), 1);
+        assert_eq!(reply_counts.first().unwrap().message_id, opened.id);
+        assert_eq!(reply_counts.first().unwrap().reply_count, 1);
+
+        // hidden messages do not stand for their thread once collapsed
+        traits
+            .message_repository
+            .dismiss_message(resolved.id, 1)
+            .await?;
+        let dismissed_filter = MessageFilter {
+            channels: vec![String::from("incidents")],
+            dismissed_by: Some(1),
+            collapse_threads: true,
+            ..Default::default()
+        };
+        let collapsed_messages = traits
+            .message_repository
+            .get_messages(&dismissed_filter, 0, 50)
+            .await?
+            .into_iter()
+            .map(|message| message.id)
+            .collect::<Vec<i64>>();
+        assert_eq!(collapsed_messages, vec![unrelated.id, opened.id]);
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages_count(&dismissed_filter)
+                .await?,
+            2
+        );
+
+        traits
+            .message_repository
+            .acknowledge_message(resolved.id, 1, None)
+            .await?;
+        let unacknowledged_filter = MessageFilter {
+            channels: vec![String::from("incidents")],
+            unacknowledged_only: true,
+            collapse_threads: true,
+            ..Default::default()
+        };
+        let collapsed_messages = traits
+            .message_repository
+            .get_messages(&unacknowledged_filter, 0, 50)
+            .await?
+            .into_iter()
+            .map(|message| message.id)
+            .collect::<Vec<i64>>();
+        assert_eq!(collapsed_messages, vec![unrelated.id, opened.id]);
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages_count(&unacknowledged_filter)
+                .await?,
+            2
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn dismiss_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let message = traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel: "alerts",
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+
+        assert!(
+            traits
+                .message_repository
+                .dismiss_message(message.id, 1)
+                .await?
+        );
+        assert!(
+            !traits
+                .message_repository
+                .dismiss_message(message.id, 1)
+                .await?
+        );
+
+        let dismissed_filter = MessageFilter {
+            channels: vec!["alerts".to_string()],
+            dismissed_by: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages_count(&dismissed_filter)
+                .await?,
+            0
+        );
+        let other_filter = MessageFilter {
+            channels: vec!["alerts".to_string()],
+            dismissed_by: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages(&other_filter, 0, 10)
+                .await?
+                .len(),
+            1
+        );
+
+        let deleted_message = traits.message_repository.delete_message(message.id).await?;
+        assert!(deleted_message.is_some());
+        assert!(traits
+            .message_repository
+            .get_message(message.id)
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn channel_pattern_test() {
+        assert!(ChannelPattern::parse("").is_none());
+        assert!(ChannelPattern::parse("billing.").is_none());
+        assert!(ChannelPattern::parse("billing.in*").is_none());
+        assert!(ChannelPattern::parse("billing invoices").is_none());
+        assert_eq!(
+            ChannelPattern::parse("alerts.#.#").unwrap().to_string(),
+            "alerts.#"
+        );
+
+        let single = ChannelPattern::parse("billing.*").unwrap();
+        assert!(single.matches("billing.invoice"));
+        assert!(!single.matches("billing"));
+        assert!(!single.matches("billing.invoice.paid"));
+
+        let multi = ChannelPattern::parse("alerts.#").unwrap();
+        assert!(multi.matches("alerts"));
+        assert!(multi.matches("alerts.db.down"));
+        assert!(!multi.matches("alerting.db"));
+
+        let inner = ChannelPattern::parse("#.down").unwrap();
+        assert!(inner.matches("down"));
+        assert!(inner.matches("alerts.db.down"));
+        assert!(!inner.matches("alerts.db.up"));
+    }
+
+    #[sqlx::test]
+    async fn get_messages_by_pattern_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        for channel in [
+            "billing.invoice",
+            "billing.invoice.paid",
+            "alerts",
+            "alerts.db.down",
+            "news",
+        ] {
+            traits
+                .message_repository
+                .add_message(
+                    &NewMessage {
+                        channel,
+                        subject: "subject",
+                        message: "message",
+                        priority: "normal",
+                        ..Default::default()
+                    },
+                    &MessageFanout::default(),
+                )
+                .await?;
+        }
+
+        let filter = MessageFilter {
+            channels: vec![String::from("news")],
+            patterns: vec![
+                ChannelPattern::parse("billing.*").unwrap(),
+                ChannelPattern::parse("alerts.#").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let mut obtained_channels = traits
+            .message_repository
+            .get_messages(&filter, 0, 50)
+            .await?
+            .into_iter()
+            .map(|message| message.channel)
+            .collect::<Vec<String>>();
+        obtained_channels.sort();
+
+        assert_eq!(
+            obtained_channels,
+            vec!["alerts", "alerts.db.down", "billing.invoice", "news"]
+        );
+        assert_eq!(
+            traits
+                .message_repository
+                .get_messages_count(&filter)
+                .await?,
+            4
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn channel_subscription_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let user_id = 1;
+        let pattern = ChannelPattern::parse("billing.*").unwrap();
+
+        let subscription = traits
+            .channel_subscription_repository
+            .add_channel_subscription(user_id, &pattern)
+            .await?;
+        assert_eq!(subscription.unwrap().pattern, "billing.*");
+
+        let duplicate_subscription = traits
+            .channel_subscription_repository
+            .add_channel_subscription(user_id, &pattern)
+            .await?;
+        assert!(duplicate_subscription.is_none());
+
+        let subscriptions = traits
+            .channel_subscription_repository
+            .list_channel_subscriptions(user_id)
+            .await?;
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(
+            subscriptions.first().unwrap().channel_pattern(),
+            Some(pattern.clone())
+        );
+
+        traits
+            .channel_subscription_repository
+            .remove_channel_subscription(user_id, &pattern)
+            .await?;
+        let subscriptions = traits
+            .channel_subscription_repository
+            .list_channel_subscriptions(user_id)
+            .await?;
+        assert!(subscriptions.is_empty());
+
+        Ok(())
+    }
 }
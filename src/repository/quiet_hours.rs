@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    notification::QuietHoursResponse, repository::connection_pool::ServiceConnectionPool,
+};
+use sqlx::{
+    query, query_as,
+    types::time::{OffsetDateTime, Time},
+    FromRow,
+};
+
+#[derive(FromRow)]
+pub struct QuietHoursEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub user_id: i64,
+    pub start_time: Time,
+    pub end_time: Time,
+    pub timezone: String,
+}
+
+impl QuietHoursEntity {
+    pub fn into_quiet_hours_response(self) -> QuietHoursResponse {
+        QuietHoursResponse {
+            start_minute: i32::from(self.start_time.hour()) * 60
+                + i32::from(self.start_time.minute()),
+            end_minute: i32::from(self.end_time.hour()) * 60 + i32::from(self.end_time.minute()),
+            timezone: self.timezone,
+        }
+    }
+}
+
+#[async_trait]
+pub trait QuietHoursRepositoryTrait {
+    async fn get_quiet_hours(&self, user_id: i64) -> anyhow::Result<Option<QuietHoursEntity>>;
+    async fn set_quiet_hours(
+        &self,
+        user_id: i64,
+        start_time: Time,
+        end_time: Time,
+        timezone: &str,
+    ) -> anyhow::Result<QuietHoursEntity>;
+    async fn remove_quiet_hours(&self, user_id: i64) -> anyhow::Result<Option<QuietHoursEntity>>;
+    async fn is_valid_timezone(&self, timezone: &str) -> anyhow::Result<bool>;
+}
+
+pub type DynQuietHoursRepositoryTrait = Arc<dyn QuietHoursRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct QuietHoursRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl QuietHoursRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl QuietHoursRepositoryTrait for QuietHoursRepository {
+    async fn get_quiet_hours(&self, user_id: i64) -> anyhow::Result<Option<QuietHoursEntity>> {
+        query_as!(
+            QuietHoursEntity,
+            r#"
+                select *
+                from notification_quiet_hours
+                where user_id = $1::bigint
+            "#,
+            user_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for quiet hours")
+    }
+
+    async fn set_quiet_hours(
+        &self,
+        user_id: i64,
+        start_time: Time,
+        end_time: Time,
+        timezone: &str,
+    ) -> anyhow::Result<QuietHoursEntity> {
+        query_as!(
+            QuietHoursEntity,
+            r#"
+                insert into notification_quiet_hours (
+                        user_id,
+                        start_time,
+                        end_time,
+                        timezone
+                    )
+                values (
+                        $1::bigint,
+                        $2::time,
+                        $3::time,
+                        $4::varchar
+                    )
+                on conflict (user_id) do update
+                set
+                    start_time = excluded.start_time,
+                    end_time = excluded.end_time,
+                    timezone = excluded.timezone,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            user_id,
+            start_time,
+            end_time,
+            timezone,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while setting quiet hours")
+    }
+
+    async fn remove_quiet_hours(&self, user_id: i64) -> anyhow::Result<Option<QuietHoursEntity>> {
+        query_as!(
+            QuietHoursEntity,
+            r#"
+                delete from notification_quiet_hours
+                where user_id = $1::bigint
+                returning *
+            "#,
+            user_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing quiet hours")
+    }
+
+    async fn is_valid_timezone(&self, timezone: &str) -> anyhow::Result<bool> {
+        let timezone_result = query!(
+            r#"
+                select exists (
+                    select 1 from pg_timezone_names where name = $1::text
+                ) as "valid!"
+            "#,
+            timezone,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while validating the timezone")?;
+
+        Ok(timezone_result.valid)
+    }
+}
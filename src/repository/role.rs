@@ -0,0 +1,303 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    notification::GroupMember, repository::connection_pool::ServiceConnectionPool,
+};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+/// Roles a member can hold in a group, ordered from the least to the most
+/// privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GroupRole {
+    Viewer,
+    Publisher,
+    Admin,
+    Owner,
+}
+
+impl GroupRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupRole::Viewer => "viewer",
+            GroupRole::Publisher => "publisher",
+            GroupRole::Admin => "admin",
+            GroupRole::Owner => "owner",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "viewer" => Some(GroupRole::Viewer),
+            "publisher" => Some(GroupRole::Publisher),
+            "admin" => Some(GroupRole::Admin),
+            "owner" => Some(GroupRole::Owner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub struct RoleEntity {
+    pub id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub group_id: i64,
+    pub user_id: Option<i64>,
+    pub email: Option<String>,
+    pub role: String,
+}
+
+impl RoleEntity {
+    pub fn group_role(&self) -> Option<GroupRole> {
+        GroupRole::from_name(&self.role)
+    }
+}
+
+/// A member of a group is either known by its user id or, when it has no
+/// account yet, by its email.
+#[derive(Clone, Default)]
+pub struct RoleMember {
+    pub user_id: Option<i64>,
+    pub email: Option<String>,
+}
+
+impl From<GroupMember> for RoleMember {
+    fn from(member: GroupMember) -> Self {
+        Self {
+            user_id: member.user_id,
+            email: member.email,
+        }
+    }
+}
+
+#[async_trait]
+pub trait RoleRepositoryTrait {
+    async fn get_role(
+        &self,
+        group_id: i64,
+        member: &RoleMember,
+    ) -> anyhow::Result<Option<RoleEntity>>;
+    async fn set_role(
+        &self,
+        group_id: i64,
+        member: &RoleMember,
+        role: GroupRole,
+    ) -> anyhow::Result<RoleEntity>;
+    async fn remove_role(
+        &self,
+        group_id: i64,
+        member: &RoleMember,
+    ) -> anyhow::Result<Option<RoleEntity>>;
+    async fn transfer_ownership(
+        &self,
+        group_id: i64,
+        new_owner: &RoleMember,
+    ) -> anyhow::Result<RoleEntity>;
+}
+
+pub type DynRoleRepositoryTrait = Arc<dyn RoleRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct RoleRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl RoleRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RoleRepositoryTrait for RoleRepository {
+    async fn get_role(
+        &self,
+        group_id: i64,
+        member: &RoleMember,
+    ) -> anyhow::Result<Option<RoleEntity>> {
+        // a member known by both its user id and its email holds the most
+        // privileged of its roles
+        query_as!(
+            RoleEntity,
+            r#"
+                select *
+                from notification_group_role
+                where group_id = $1::bigint
+                and (user_id = $2::bigint or email = $3::varchar)
+                order by case role
+                    when 'owner' then 0
+                    when 'admin' then 1
+                    when 'publisher' then 2
+                    else 3
+                end
+                limit 1
+            "#,
+            group_id,
+            member.user_id,
+            member.email,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for the group role")
+    }
+
+    async fn set_role(
+        &self,
+        group_id: i64,
+        member: &RoleMember,
+        role: GroupRole,
+    ) -> anyhow::Result<RoleEntity> {
+        let mut tx = self.pool.begin().await?;
+
+        query!(
+            r#"
+                delete from notification_group_role
+                where group_id = $1::bigint
+                and (user_id = $2::bigint or email = $3::varchar)
+            "#,
+            group_id,
+            member.user_id,
+            member.email,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while replacing the group role")?;
+
+        let created_role = query_as!(
+            RoleEntity,
+            r#"
+                insert into notification_group_role (
+                        group_id,
+                        user_id,
+                        email,
+                        role
+                    )
+                values (
+                        $1::bigint,
+                        $2::bigint,
+                        $3::varchar,
+                        $4::varchar
+                    )
+                returning *
+            "#,
+            group_id,
+            member.user_id,
+            member.email,
+            role.as_str(),
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("an unexpected error occured while setting the group role")?;
+
+        tx.commit().await?;
+
+        Ok(created_role)
+    }
+
+    async fn remove_role(
+        &self,
+        group_id: i64,
+        member: &RoleMember,
+    ) -> anyhow::Result<Option<RoleEntity>> {
+        query_as!(
+            RoleEntity,
+            r#"
+                delete from notification_group_role
+                where group_id = $1::bigint
+                and (user_id = $2::bigint or email = $3::varchar)
+                returning *
+            "#,
+            group_id,
+            member.user_id,
+            member.email,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the group role")
+    }
+
+    async fn transfer_ownership(
+        &self,
+        group_id: i64,
+        new_owner: &RoleMember,
+    ) -> anyhow::Result<RoleEntity> {
+        let mut tx = self.pool.begin().await?;
+
+        // the previous owner stays on as an admin of the group
+        query!(
+            r#"
+                update notification_group_role
+                set
+                    role = 'admin',
+                    updated_at = current_timestamp
+                where group_id = $1::bigint
+                and role = 'owner'
+            "#,
+            group_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while demoting the group owner")?;
+
+        query!(
+            r#"
+                delete from notification_group_role
+                where group_id = $1::bigint
+                and (user_id = $2::bigint or email = $3::varchar)
+            "#,
+            group_id,
+            new_owner.user_id,
+            new_owner.email,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while replacing the new owner role")?;
+
+        let owner_role = query_as!(
+            RoleEntity,
+            r#"
+                insert into notification_group_role (
+                        group_id,
+                        user_id,
+                        email,
+                        role
+                    )
+                values (
+                        $1::bigint,
+                        $2::bigint,
+                        $3::varchar,
+                        'owner'
+                    )
+                returning *
+            "#,
+            group_id,
+            new_owner.user_id,
+            new_owner.email,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("an unexpected error occured while transferring the group ownership")?;
+
+        // the admin email of the group always names its owner
+        query!(
+            r#"
+                update notification_group
+                set
+                    admin_email = coalesce($2::varchar, ''),
+                    updated_at = current_timestamp
+                where id = $1::bigint
+            "#,
+            group_id,
+            new_owner.email,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while updating the group admin email")?;
+
+        tx.commit().await?;
+
+        Ok(owner_role)
+    }
+}
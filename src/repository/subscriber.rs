@@ -1,15 +1,25 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Context;
 use async_trait::async_trait;
 use madtofan_microservice_common::{
-    notification::subscribers_response::Subscriber,
+    notification::{subscribers_response::Subscriber, PreferencesResponse},
     repository::connection_pool::ServiceConnectionPool,
 };
-use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+use sqlx::{
+    query, query_as,
+    types::time::{Duration, OffsetDateTime},
+    FromRow,
+};
 
 use super::group::GroupEntity;
 
+pub const DIGEST_MODE_OFF: &str = "off";
+pub const DIGEST_MODES: [&str; 3] = [DIGEST_MODE_OFF, "daily", "weekly"];
+pub const SUBSCRIBER_SORT_CREATED_AT: &str = "created_at";
+pub const SUBSCRIBER_SORT_USER_ID: &str = "user_id";
+pub const SUBSCRIBER_SORT_FIELDS: [&str; 2] = [SUBSCRIBER_SORT_CREATED_AT, SUBSCRIBER_SORT_USER_ID];
+
 #[derive(FromRow)]
 pub struct SubscriberEntity {
     pub id: i64,
@@ -17,23 +27,146 @@ pub struct SubscriberEntity {
     pub updated_at: OffsetDateTime,
     pub user_id: i64,
     pub group_id: i64,
+    pub tags: Vec<String>,
+    pub muted_until: Option<OffsetDateTime>,
+    pub opted_out_channels: Vec<String>,
+    pub delivery_channels: Vec<String>,
+    pub digest_mode: String,
+    pub last_digest_at: Option<OffsetDateTime>,
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+/// How a subscriber wants to receive the messages of a group.
+pub struct SubscriberPreferences {
+    pub muted_until: Option<OffsetDateTime>,
+    pub opted_out_channels: Vec<String>,
+    pub delivery_channels: Vec<String>,
+    pub digest_mode: String,
+}
+
+/// Matches subscribers tagged with any of `tags`, or with all of them when
+/// `match_all` is set. Empty tags match every subscriber.
+#[derive(Clone, Default)]
+pub struct TagFilter {
+    pub tags: Vec<String>,
+    pub match_all: bool,
+}
+
+/// Message posted to the former subscribers of a group once their
+/// subscriptions expired, on the `<channel_prefix>.<group name>` channel of
+/// the group.
+pub struct ExpiryNotice<'a> {
+    pub channel_prefix: &'a str,
+    pub subject: &'a str,
+    pub message: &'a str,
+    pub priority: &'a str,
+}
+
+/// Narrows the subscribers of a group down to the recipients of a message.
+/// Empty lists are treated as "no restriction".
+#[derive(Default)]
+pub struct AudienceFilter {
+    pub joined_after: Option<OffsetDateTime>,
+    pub joined_before: Option<OffsetDateTime>,
+    pub include_user_ids: Vec<i64>,
+    pub exclude_user_ids: Vec<i64>,
+    pub tags: TagFilter,
+}
+
+/// Position of the last subscriber of a page. It carries every sort key so
+/// that the next page can still be found once that subscriber is removed.
+pub struct SubscriberCursor {
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+    pub id: i64,
+}
+
+impl SubscriberCursor {
+    pub fn encode(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            self.created_at.unix_timestamp_nanos(),
+            self.user_id,
+            self.id
+        )
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let mut parts = cursor.split('.');
+        let created_at = parts.next()?.parse::<i128>().ok()?;
+        let user_id = parts.next()?.parse::<i64>().ok()?;
+        let id = parts.next()?.parse::<i64>().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            created_at: OffsetDateTime::from_unix_timestamp_nanos(created_at).ok()?,
+            user_id,
+            id,
+        })
+    }
+}
+
+pub struct SubscriberPage {
+    pub cursor: Option<SubscriberCursor>,
+    pub limit: i64,
+    pub sort_by: String,
+    pub descending: bool,
+    pub tags: TagFilter,
 }
 
 impl SubscriberEntity {
+    pub fn cursor(&self) -> SubscriberCursor {
+        SubscriberCursor {
+            created_at: self.created_at,
+            user_id: self.user_id,
+            id: self.id,
+        }
+    }
+
     pub fn into_subscriber_response(self) -> Subscriber {
         Subscriber {
             user_id: self.user_id,
+            created_at: self.created_at.unix_timestamp(),
+            expires_at: self.expires_at.map(|date| date.unix_timestamp()),
+            preferences: Some(self.into_preferences_response()),
         }
     }
+
+    pub fn into_preferences_response(self) -> PreferencesResponse {
+        PreferencesResponse {
+            muted_until: self.muted_until.map(|date| date.unix_timestamp()),
+            opted_out_channels: self.opted_out_channels,
+            delivery_channels: self.delivery_channels,
+            digest_mode: self.digest_mode,
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted_until
+            .is_some_and(|muted_until| muted_until > OffsetDateTime::now_utc())
+    }
+
+    pub fn is_opted_out(&self, channel: &str) -> bool {
+        self.opted_out_channels
+            .iter()
+            .any(|opted_out| opted_out == channel)
+    }
 }
 
 #[async_trait]
 pub trait SubscriberRepositoryTrait {
+    async fn get_subscriber(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+    ) -> anyhow::Result<Option<SubscriberEntity>>;
     async fn add_subscriber(
         &self,
         user_id: i64,
         group: &GroupEntity,
-    ) -> anyhow::Result<SubscriberEntity>;
+    ) -> anyhow::Result<Option<SubscriberEntity>>;
     async fn remove_subscriber(
         &self,
         user_id: i64,
@@ -43,6 +176,61 @@ pub trait SubscriberRepositoryTrait {
         &self,
         group: &GroupEntity,
     ) -> anyhow::Result<Vec<SubscriberEntity>>;
+    async fn list_subs_page(
+        &self,
+        group: &GroupEntity,
+        page: &SubscriberPage,
+    ) -> anyhow::Result<Vec<SubscriberEntity>>;
+    async fn count_subs_by_group(
+        &self,
+        group: &GroupEntity,
+        tags: &TagFilter,
+    ) -> anyhow::Result<i64>;
+    async fn resolve_audience(
+        &self,
+        group: &GroupEntity,
+        filter: &AudienceFilter,
+    ) -> anyhow::Result<Vec<SubscriberEntity>>;
+    async fn update_preferences(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+        preferences: &SubscriberPreferences,
+    ) -> anyhow::Result<Option<SubscriberEntity>>;
+    async fn list_due_digests(&self, now: OffsetDateTime) -> anyhow::Result<Vec<SubscriberEntity>>;
+    async fn add_subscribers(
+        &self,
+        user_ids: &[i64],
+        groups: &[GroupEntity],
+    ) -> anyhow::Result<Vec<SubscriberEntity>>;
+    async fn remove_subscribers(
+        &self,
+        user_ids: &[i64],
+        groups: &[GroupEntity],
+    ) -> anyhow::Result<Vec<SubscriberEntity>>;
+    async fn import_subscribers(
+        &self,
+        group: &GroupEntity,
+        added_user_ids: &[i64],
+        removed_user_ids: &[i64],
+    ) -> anyhow::Result<()>;
+    async fn update_expiry(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+        expires_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<SubscriberEntity>>;
+    async fn set_tags(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+        tags: &[String],
+    ) -> anyhow::Result<Option<SubscriberEntity>>;
+    async fn remove_expired_subs(
+        &self,
+        now: OffsetDateTime,
+        notice: &ExpiryNotice<'_>,
+    ) -> anyhow::Result<Vec<SubscriberEntity>>;
 }
 
 pub type DynSubscriberRepositoryTrait = Arc<dyn SubscriberRepositoryTrait + Send + Sync>;
@@ -60,11 +248,34 @@ impl SubscriberRepository {
 
 #[async_trait]
 impl SubscriberRepositoryTrait for SubscriberRepository {
+    async fn get_subscriber(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+    ) -> anyhow::Result<Option<SubscriberEntity>> {
+        query_as!(
+            SubscriberEntity,
+            r#"
+                select *
+                from notification_subscriber
+                where user_id = $1::bigint
+                and group_id = $2::bigint
+                and (expires_at is null or expires_at > current_timestamp)
+            "#,
+            user_id,
+            group.id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for subscriber")
+    }
+
     async fn add_subscriber(
         &self,
         user_id: i64,
         group: &GroupEntity,
-    ) -> anyhow::Result<SubscriberEntity> {
+    ) -> anyhow::Result<Option<SubscriberEntity>> {
+        // an expired subscription that is not swept yet is renewed in place
         query_as!(
             SubscriberEntity,
             r#"
@@ -76,12 +287,17 @@ impl SubscriberRepositoryTrait for SubscriberRepository {
                         $1::bigint,
                         $2::bigint
                     )
+                on conflict (user_id, group_id) do update
+                set
+                    expires_at = excluded.expires_at,
+                    updated_at = current_timestamp
+                where notification_subscriber.expires_at <= current_timestamp
                 returning *
             "#,
             user_id,
             group.id,
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
         .context("an unexpected error occured while creating the subscriber")
     }
@@ -97,7 +313,12 @@ impl SubscriberRepositoryTrait for SubscriberRepository {
                 delete from notification_subscriber 
                 where 
                     user_id = $1::bigint 
-                    and group_id = (select id from notification_group where name = $2::varchar)
+                    and group_id = (
+                        select id
+                        from notification_group
+                        where name = $2::varchar
+                        and deleted_at is null
+                    )
                 returning *
             "#,
             user_id,
@@ -119,10 +340,18 @@ impl SubscriberRepositoryTrait for SubscriberRepository {
                     id,
                     user_id,
                     group_id,
+                    tags,
+                    muted_until,
+                    opted_out_channels,
+                    delivery_channels,
+                    digest_mode,
+                    last_digest_at,
+                    expires_at,
                     created_at,
                     updated_at
                 from notification_subscriber
                 where group_id = $1::bigint
+                and (expires_at is null or expires_at > current_timestamp)
             "#,
             group.id
         )
@@ -130,4 +359,598 @@ impl SubscriberRepositoryTrait for SubscriberRepository {
         .await
         .context("an unexpected error occured while search for subscribers by group")
     }
+
+    async fn list_subs_page(
+        &self,
+        group: &GroupEntity,
+        page: &SubscriberPage,
+    ) -> anyhow::Result<Vec<SubscriberEntity>> {
+        // One query per sort so each keyset predicate and ordering can walk the
+        // matching index, an empty cursor starts from the open end of the range.
+        let subscribers = match (page.sort_by.as_str(), page.descending) {
+            (SUBSCRIBER_SORT_USER_ID, false) => {
+                query_as!(
+                    SubscriberEntity,
+                    r#"
+                select
+                    id,
+                    user_id,
+                    group_id,
+                    tags,
+                    muted_until,
+                    opted_out_channels,
+                    delivery_channels,
+                    digest_mode,
+                    last_digest_at,
+                    expires_at,
+                    created_at,
+                    updated_at
+                from notification_subscriber
+                where group_id = $1::bigint
+                and (expires_at is null or expires_at > current_timestamp)
+                and (
+                    cardinality($2::text[]) = 0
+                    or case
+                        when $3::boolean then tags @> $2::text[]
+                        else tags && $2::text[]
+                    end
+                )
+                and user_id > $4::bigint
+                order by user_id asc
+                limit $5::int
+            "#,
+                    group.id,
+                    &page.tags.tags,
+                    page.tags.match_all,
+                    page.cursor
+                        .as_ref()
+                        .map_or(i64::MIN, |cursor| cursor.user_id),
+                    page.limit as i32,
+                )
+                .fetch_all(&self.pool)
+                .await
+            }
+            (SUBSCRIBER_SORT_USER_ID, true) => {
+                query_as!(
+                    SubscriberEntity,
+                    r#"
+                select
+                    id,
+                    user_id,
+                    group_id,
+                    tags,
+                    muted_until,
+                    opted_out_channels,
+                    delivery_channels,
+                    digest_mode,
+                    last_digest_at,
+                    expires_at,
+                    created_at,
+                    updated_at
+                from notification_subscriber
+                where group_id = $1::bigint
+                and (expires_at is null or expires_at > current_timestamp)
+                and (
+                    cardinality($2::text[]) = 0
+                    or case
+                        when $3::boolean then tags @> $2::text[]
+                        else tags && $2::text[]
+                    end
+                )
+                and user_id < $4::bigint
+                order by user_id desc
+                limit $5::int
+            "#,
+                    group.id,
+                    &page.tags.tags,
+                    page.tags.match_all,
+                    page.cursor
+                        .as_ref()
+                        .map_or(i64::MAX, |cursor| cursor.user_id),
+                    page.limit as i32,
+                )
+                .fetch_all(&self.pool)
+                .await
+            }
+            (_, false) => {
+                query_as!(
+                    SubscriberEntity,
+                    r#"
+                select
+                    id,
+                    user_id,
+                    group_id,
+                    tags,
+                    muted_until,
+                    opted_out_channels,
+                    delivery_channels,
+                    digest_mode,
+                    last_digest_at,
+                    expires_at,
+                    created_at,
+                    updated_at
+                from notification_subscriber
+                where group_id = $1::bigint
+                and (expires_at is null or expires_at > current_timestamp)
+                and (
+                    cardinality($2::text[]) = 0
+                    or case
+                        when $3::boolean then tags @> $2::text[]
+                        else tags && $2::text[]
+                    end
+                )
+                and (created_at, id) > (coalesce($4::timestamptz, '-infinity'), $5::bigint)
+                order by created_at asc, id asc
+                limit $6::int
+            "#,
+                    group.id,
+                    &page.tags.tags,
+                    page.tags.match_all,
+                    page.cursor.as_ref().map(|cursor| cursor.created_at),
+                    page.cursor.as_ref().map_or(0, |cursor| cursor.id),
+                    page.limit as i32,
+                )
+                .fetch_all(&self.pool)
+                .await
+            }
+            (_, true) => {
+                query_as!(
+                    SubscriberEntity,
+                    r#"
+                select
+                    id,
+                    user_id,
+                    group_id,
+                    tags,
+                    muted_until,
+                    opted_out_channels,
+                    delivery_channels,
+                    digest_mode,
+                    last_digest_at,
+                    expires_at,
+                    created_at,
+                    updated_at
+                from notification_subscriber
+                where group_id = $1::bigint
+                and (expires_at is null or expires_at > current_timestamp)
+                and (
+                    cardinality($2::text[]) = 0
+                    or case
+                        when $3::boolean then tags @> $2::text[]
+                        else tags && $2::text[]
+                    end
+                )
+                and (created_at, id) < (coalesce($4::timestamptz, 'infinity'), $5::bigint)
+                order by created_at desc, id desc
+                limit $6::int
+            "#,
+                    group.id,
+                    &page.tags.tags,
+                    page.tags.match_all,
+                    page.cursor.as_ref().map(|cursor| cursor.created_at),
+                    page.cursor.as_ref().map_or(0, |cursor| cursor.id),
+                    page.limit as i32,
+                )
+                .fetch_all(&self.pool)
+                .await
+            }
+        };
+
+        subscribers.context("an unexpected error occured while paging subscribers by group")
+    }
+
+    async fn count_subs_by_group(
+        &self,
+        group: &GroupEntity,
+        tags: &TagFilter,
+    ) -> anyhow::Result<i64> {
+        let count_result = query!(
+            r#"
+                select count(*) as "count!"
+                from notification_subscriber
+                where group_id = $1::bigint
+                and (expires_at is null or expires_at > current_timestamp)
+                and (
+                    cardinality($2::text[]) = 0
+                    or case
+                        when $3::boolean then tags @> $2::text[]
+                        else tags && $2::text[]
+                    end
+                )
+            "#,
+            group.id,
+            &tags.tags,
+            tags.match_all,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while counting subscribers by group")?;
+
+        Ok(count_result.count)
+    }
+
+    async fn resolve_audience(
+        &self,
+        group: &GroupEntity,
+        filter: &AudienceFilter,
+    ) -> anyhow::Result<Vec<SubscriberEntity>> {
+        // the audience of a group includes the subscribers of all its
+        // descendants, a user subscribed at several levels is only reached
+        // once through its closest subscription
+        query_as!(
+            SubscriberEntity,
+            r#"
+                with recursive descendant as (
+                    select
+                        id,
+                        0 as depth
+                    from notification_group
+                    where id = $1::bigint
+                    union all
+                    select
+                        child.id,
+                        descendant.depth + 1
+                    from notification_group as child
+                    join descendant
+                    on child.parent_id = descendant.id
+                    where child.deleted_at is null
+                )
+                select distinct on (ns.user_id)
+                    ns.id,
+                    ns.user_id,
+                    ns.group_id,
+                    ns.tags,
+                    ns.muted_until,
+                    ns.opted_out_channels,
+                    ns.delivery_channels,
+                    ns.digest_mode,
+                    ns.last_digest_at,
+                    ns.expires_at,
+                    ns.created_at,
+                    ns.updated_at
+                from notification_subscriber as ns
+                join descendant
+                on ns.group_id = descendant.id
+                where (ns.expires_at is null or ns.expires_at > current_timestamp)
+                and ($2::timestamptz is null or ns.created_at >= $2::timestamptz)
+                and ($3::timestamptz is null or ns.created_at < $3::timestamptz)
+                and (cardinality($4::bigint[]) = 0 or ns.user_id = any($4::bigint[]))
+                and not (ns.user_id = any($5::bigint[]))
+                and (
+                    cardinality($6::text[]) = 0
+                    or case
+                        when $7::boolean then ns.tags @> $6::text[]
+                        else ns.tags && $6::text[]
+                    end
+                )
+                order by ns.user_id, descendant.depth
+            "#,
+            group.id,
+            filter.joined_after,
+            filter.joined_before,
+            &filter.include_user_ids,
+            &filter.exclude_user_ids,
+            &filter.tags.tags,
+            filter.tags.match_all,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while resolving the message audience")
+    }
+
+    async fn update_preferences(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+        preferences: &SubscriberPreferences,
+    ) -> anyhow::Result<Option<SubscriberEntity>> {
+        query_as!(
+            SubscriberEntity,
+            r#"
+                update notification_subscriber
+                set
+                    muted_until = $3::timestamptz,
+                    opted_out_channels = $4::text[],
+                    delivery_channels = $5::text[],
+                    digest_mode = $6::varchar,
+                    last_digest_at = case
+                        when digest_mode = 'off' and $6::varchar <> 'off'
+                        then current_timestamp
+                        else last_digest_at
+                    end,
+                    updated_at = current_timestamp
+                where user_id = $1::bigint
+                and group_id = $2::bigint
+                returning *
+            "#,
+            user_id,
+            group.id,
+            preferences.muted_until,
+            &preferences.opted_out_channels,
+            &preferences.delivery_channels,
+            preferences.digest_mode,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while updating subscriber preferences")
+    }
+
+    async fn list_due_digests(&self, now: OffsetDateTime) -> anyhow::Result<Vec<SubscriberEntity>> {
+        query_as!(
+            SubscriberEntity,
+            r#"
+                select *
+                from notification_subscriber
+                where digest_mode <> 'off'
+                and (expires_at is null or expires_at > $1::timestamptz)
+                and group_id in (
+                    select id
+                    from notification_group
+                    where deleted_at is null
+                )
+                and last_digest_at + case digest_mode
+                        when 'daily' then interval '1 day'
+                        else interval '7 days'
+                    end <= $1::timestamptz
+            "#,
+            now,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while searching for due digests")
+    }
+
+    async fn add_subscribers(
+        &self,
+        user_ids: &[i64],
+        groups: &[GroupEntity],
+    ) -> anyhow::Result<Vec<SubscriberEntity>> {
+        let group_ids = groups.iter().map(|group| group.id).collect::<Vec<i64>>();
+
+        query_as!(
+            SubscriberEntity,
+            r#"
+                insert into notification_subscriber (
+                        user_id,
+                        group_id
+                    )
+                select u.user_id, g.group_id
+                from unnest($1::bigint[]) as u(user_id)
+                cross join unnest($2::bigint[]) as g(group_id)
+                on conflict (user_id, group_id) do update
+                set
+                    expires_at = excluded.expires_at,
+                    updated_at = current_timestamp
+                where notification_subscriber.expires_at <= current_timestamp
+                returning *
+            "#,
+            user_ids,
+            &group_ids,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the subscribers")
+    }
+
+    async fn remove_subscribers(
+        &self,
+        user_ids: &[i64],
+        groups: &[GroupEntity],
+    ) -> anyhow::Result<Vec<SubscriberEntity>> {
+        let group_ids = groups.iter().map(|group| group.id).collect::<Vec<i64>>();
+
+        query_as!(
+            SubscriberEntity,
+            r#"
+                delete from notification_subscriber
+                where user_id = any($1::bigint[])
+                and group_id = any($2::bigint[])
+                returning *
+            "#,
+            user_ids,
+            &group_ids,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the subscribers")
+    }
+
+    /// Adds and removes the imported subscribers at once, so a failing import
+    /// leaves the group untouched.
+    async fn import_subscribers(
+        &self,
+        group: &GroupEntity,
+        added_user_ids: &[i64],
+        removed_user_ids: &[i64],
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        query!(
+            r#"
+                insert into notification_subscriber (
+                        user_id,
+                        group_id
+                    )
+                select unnest($1::bigint[]), $2::bigint
+                on conflict (user_id, group_id) do update
+                set
+                    expires_at = excluded.expires_at,
+                    updated_at = current_timestamp
+                where notification_subscriber.expires_at <= current_timestamp
+            "#,
+            added_user_ids,
+            group.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while creating the imported subscribers")?;
+
+        query!(
+            r#"
+                delete from notification_subscriber
+                where user_id = any($1::bigint[])
+                and group_id = $2::bigint
+            "#,
+            removed_user_ids,
+            group.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("an unexpected error occured while removing the replaced subscribers")?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn update_expiry(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+        expires_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<SubscriberEntity>> {
+        query_as!(
+            SubscriberEntity,
+            r#"
+                update notification_subscriber
+                set
+                    expires_at = $3::timestamptz,
+                    updated_at = current_timestamp
+                where user_id = $1::bigint
+                and group_id = $2::bigint
+                returning *
+            "#,
+            user_id,
+            group.id,
+            expires_at,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while updating the subscription expiry")
+    }
+
+    async fn set_tags(
+        &self,
+        user_id: i64,
+        group: &GroupEntity,
+        tags: &[String],
+    ) -> anyhow::Result<Option<SubscriberEntity>> {
+        query_as!(
+            SubscriberEntity,
+            r#"
+                update notification_subscriber
+                set
+                    tags = $3::text[],
+                    updated_at = current_timestamp
+                where user_id = $1::bigint
+                and group_id = $2::bigint
+                returning *
+            "#,
+            user_id,
+            group.id,
+            tags,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while setting the subscriber tags")
+    }
+
+    /// Removes the expired subscriptions and posts the expiry notice of every
+    /// group they belonged to at once, the notice channel of a group is
+    /// registered along with its first notice.
+    async fn remove_expired_subs(
+        &self,
+        now: OffsetDateTime,
+        notice: &ExpiryNotice<'_>,
+    ) -> anyhow::Result<Vec<SubscriberEntity>> {
+        let mut tx = self.pool.begin().await?;
+
+        let expired_subs = query_as!(
+            SubscriberEntity,
+            r#"
+                delete from notification_subscriber
+                where expires_at <= $1::timestamptz
+                returning *
+            "#,
+            now,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("an unexpected error occured while removing expired subscribers")?;
+
+        let mut recipients_by_group: HashMap<i64, Vec<i64>> = HashMap::new();
+        for subscriber in expired_subs.iter() {
+            recipients_by_group
+                .entry(subscriber.group_id)
+                .or_default()
+                .push(subscriber.user_id);
+        }
+
+        for (group_id, recipients) in recipients_by_group {
+            let notice_channel = query!(
+                r#"
+                    insert into notification_channel (
+                            group_id,
+                            name
+                        )
+                    select id, $2::varchar || '.' || name
+                    from notification_group
+                    where id = $1::bigint
+                    on conflict (name) do update
+                    set name = excluded.name
+                    where notification_channel.group_id = excluded.group_id
+                    returning name
+                "#,
+                group_id,
+                notice.channel_prefix,
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .context("an unexpected error occured while registering the expiry channel")?
+            .context("the expiry channel belongs to another group")?;
+
+            query!(
+                r#"
+                    with created_message as (
+                        insert into notification_message (
+                                channel,
+                                subject,
+                                message,
+                                priority,
+                                group_id,
+                                recipient_count
+                            )
+                        values (
+                                $1::varchar,
+                                $2::varchar,
+                                $3::varchar,
+                                $4::varchar,
+                                $5::bigint,
+                                cardinality($6::bigint[])
+                            )
+                        returning id
+                    )
+                    insert into notification_recipient (
+                            message_id,
+                            user_id
+                        )
+                    select created_message.id, unnest($6::bigint[])
+                    from created_message
+                "#,
+                notice_channel.name,
+                notice.subject,
+                notice.message,
+                notice.priority,
+                group_id,
+                &recipients,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("an unexpected error occured while creating the expiry message")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(expired_subs)
+    }
 }
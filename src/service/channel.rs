@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use tracing::{error, info};
+
+use crate::{
+    repository::{
+        channel::{ChannelEntity, ChannelUpdate, DynChannelRepositoryTrait},
+        group::{DynGroupRepositoryTrait, GroupEntity},
+        message::{DEFAULT_PRIORITY, MESSAGE_PRIORITIES},
+        role::{DynRoleRepositoryTrait, GroupRole, RoleMember},
+    },
+    service::group::authorize,
+};
+
+fn validate_priority(priority: &str) -> ServiceResult<()> {
+    if !MESSAGE_PRIORITIES.contains(&priority) {
+        error!("unknown message priority {:?}", priority);
+        return Err(ServiceError::ObjectConflict(String::from(
+            "unknown message priority",
+        )));
+    }
+
+    Ok(())
+}
+
+fn validate_retention(retention_days: Option<i32>) -> ServiceResult<()> {
+    if retention_days.is_some_and(|retention_days| retention_days <= 0) {
+        error!("channel retention {:?} is not positive", retention_days);
+        return Err(ServiceError::ObjectConflict(String::from(
+            "channel retention must be positive",
+        )));
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+pub trait ChannelServiceTrait {
+    async fn add_channel(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        name: String,
+        description: String,
+        default_priority: String,
+        retention_days: Option<i32>,
+    ) -> ServiceResult<ChannelEntity>;
+    async fn list_channels(&self, group_name: String) -> ServiceResult<Vec<ChannelEntity>>;
+    async fn update_channel(
+        &self,
+        name: String,
+        actor: RoleMember,
+        update: ChannelUpdate,
+    ) -> ServiceResult<ChannelEntity>;
+    async fn remove_channel(&self, name: String, actor: RoleMember)
+        -> ServiceResult<ChannelEntity>;
+}
+
+pub type DynChannelServiceTrait = Arc<dyn ChannelServiceTrait + Sync + Send>;
+
+pub struct ChannelService {
+    repository: DynChannelRepositoryTrait,
+    group_repository: DynGroupRepositoryTrait,
+    role_repository: DynRoleRepositoryTrait,
+}
+
+impl ChannelService {
+    pub fn new(
+        repository: DynChannelRepositoryTrait,
+        group_repository: DynGroupRepositoryTrait,
+        role_repository: DynRoleRepositoryTrait,
+    ) -> Self {
+        Self {
+            repository,
+            group_repository,
+            role_repository,
+        }
+    }
+
+    async fn existing_group(&self, group_name: &str) -> ServiceResult<GroupEntity> {
+        match self.group_repository.get_group(group_name).await? {
+            Some(group) => Ok(group),
+            None => {
+                error!("group {:?} does not exist", group_name);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "group does not exist",
+                )))
+            }
+        }
+    }
+
+    /// Channels are managed by the admins of the group owning them.
+    async fn authorize_channel(
+        &self,
+        name: &str,
+        actor: &RoleMember,
+    ) -> ServiceResult<ChannelEntity> {
+        let channel = match self.repository.get_channel(name).await? {
+            Some(channel) => channel,
+            None => {
+                error!("channel {:?} does not exist", name);
+                return Err(ServiceError::NotFound(String::from(
+                    "channel does not exist",
+                )));
+            }
+        };
+
+        let group = match self
+            .group_repository
+            .get_group_by_id(channel.group_id)
+            .await?
+        {
+            Some(group) => group,
+            None => {
+                error!("group of channel {:?} does not exist", name);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group does not exist",
+                )));
+            }
+        };
+        authorize(&self.role_repository, &group, actor, GroupRole::Admin).await?;
+
+        Ok(channel)
+    }
+}
+
+#[async_trait]
+impl ChannelServiceTrait for ChannelService {
+    async fn add_channel(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        name: String,
+        description: String,
+        default_priority: String,
+        retention_days: Option<i32>,
+    ) -> ServiceResult<ChannelEntity> {
+        if name.trim().is_empty() {
+            error!("channel name is empty");
+            return Err(ServiceError::ObjectConflict(String::from(
+                "channel name can not be empty",
+            )));
+        }
+
+        let default_priority = if default_priority.is_empty() {
+            String::from(DEFAULT_PRIORITY)
+        } else {
+            default_priority
+        };
+        validate_priority(&default_priority)?;
+        validate_retention(retention_days)?;
+
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        if self.repository.get_channel(&name).await?.is_some() {
+            error!("channel {:?} already exists", &name);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "channel name is taken",
+            )));
+        }
+
+        info!("adding channel {:?} to group {:?}", &name, &group_name);
+        let channel = self
+            .repository
+            .add_channel(
+                group.id,
+                &name,
+                &description,
+                &default_priority,
+                retention_days,
+            )
+            .await?;
+
+        info!("channel successfully added");
+        Ok(channel)
+    }
+
+    async fn list_channels(&self, group_name: String) -> ServiceResult<Vec<ChannelEntity>> {
+        let group = self.existing_group(&group_name).await?;
+
+        info!("listing channels of group {:?}", &group_name);
+        let channels = self.repository.list_channels_by_group(group.id).await?;
+
+        Ok(channels)
+    }
+
+    async fn update_channel(
+        &self,
+        name: String,
+        actor: RoleMember,
+        update: ChannelUpdate,
+    ) -> ServiceResult<ChannelEntity> {
+        if let Some(default_priority) = &update.default_priority {
+            validate_priority(default_priority)?;
+        }
+        validate_retention(update.retention_days.flatten())?;
+
+        self.authorize_channel(&name, &actor).await?;
+
+        info!("updating channel {:?}", &name);
+        match self.repository.update_channel(&name, &update).await? {
+            Some(channel) => {
+                info!("channel successfully updated");
+                Ok(channel)
+            }
+            None => {
+                error!("channel {:?} does not exist", &name);
+                Err(ServiceError::NotFound(String::from(
+                    "channel does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn remove_channel(
+        &self,
+        name: String,
+        actor: RoleMember,
+    ) -> ServiceResult<ChannelEntity> {
+        self.authorize_channel(&name, &actor).await?;
+
+        info!("removing channel {:?}", &name);
+        match self.repository.remove_channel(&name).await? {
+            Some(channel) => {
+                info!("channel successfully removed");
+                Ok(channel)
+            }
+            None => {
+                error!("channel {:?} does not exist", &name);
+                Err(ServiceError::NotFound(String::from(
+                    "channel does not exist",
+                )))
+            }
+        }
+    }
+}
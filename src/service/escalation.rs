@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    notification::EscalationStep,
+};
+use tracing::{error, info};
+
+use crate::{
+    repository::{
+        delivery::{DELIVERY_CHANNELS, IN_APP_DELIVERY_CHANNEL},
+        escalation::{DynEscalationRepositoryTrait, EscalationStepEntity, NewEscalationStep},
+        group::{DynGroupRepositoryTrait, GroupEntity},
+        role::{DynRoleRepositoryTrait, GroupRole, RoleMember},
+    },
+    service::group::authorize,
+};
+
+#[async_trait]
+pub trait EscalationServiceTrait {
+    async fn get_escalation_policy(
+        &self,
+        group_name: String,
+    ) -> ServiceResult<Vec<EscalationStepEntity>>;
+    async fn set_escalation_policy(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        steps: Vec<EscalationStep>,
+    ) -> ServiceResult<Vec<EscalationStepEntity>>;
+}
+
+pub type DynEscalationServiceTrait = Arc<dyn EscalationServiceTrait + Sync + Send>;
+
+pub struct EscalationService {
+    repository: DynEscalationRepositoryTrait,
+    group_repository: DynGroupRepositoryTrait,
+    role_repository: DynRoleRepositoryTrait,
+    max_steps: usize,
+}
+
+impl EscalationService {
+    pub fn new(
+        repository: DynEscalationRepositoryTrait,
+        group_repository: DynGroupRepositoryTrait,
+        role_repository: DynRoleRepositoryTrait,
+        max_steps: usize,
+    ) -> Self {
+        Self {
+            repository,
+            group_repository,
+            role_repository,
+            max_steps,
+        }
+    }
+
+    async fn existing_group(&self, group_name: &str) -> ServiceResult<GroupEntity> {
+        match self.group_repository.get_group(group_name).await? {
+            Some(group) => Ok(group),
+            None => {
+                error!("group {:?} does not exist", group_name);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "group does not exist",
+                )))
+            }
+        }
+    }
+
+    /// Escalating to another group requires to be admin of that group too.
+    async fn new_escalation_step(
+        &self,
+        step: EscalationStep,
+        actor: &RoleMember,
+    ) -> ServiceResult<NewEscalationStep> {
+        if step.delivery_channel.is_none() && step.target_group.is_none() {
+            error!("escalation step has neither delivery channel nor target group");
+            return Err(ServiceError::ObjectConflict(String::from(
+                "escalation step needs a delivery channel or a target group",
+            )));
+        }
+
+        if let Some(delivery_channel) = &step.delivery_channel {
+            if delivery_channel == IN_APP_DELIVERY_CHANNEL
+                || !DELIVERY_CHANNELS.contains(&delivery_channel.as_str())
+            {
+                error!("unknown escalation delivery channel {:?}", delivery_channel);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "unknown delivery channel",
+                )));
+            }
+        }
+
+        let target_group_id = match &step.target_group {
+            Some(target_group) => {
+                let target_group = self.existing_group(target_group).await?;
+                authorize(
+                    &self.role_repository,
+                    &target_group,
+                    actor,
+                    GroupRole::Admin,
+                )
+                .await?;
+                Some(target_group.id)
+            }
+            None => None,
+        };
+
+        Ok(NewEscalationStep {
+            delay_minutes: step.delay_minutes,
+            delivery_channel: step.delivery_channel,
+            target_group_id,
+        })
+    }
+}
+
+#[async_trait]
+impl EscalationServiceTrait for EscalationService {
+    async fn get_escalation_policy(
+        &self,
+        group_name: String,
+    ) -> ServiceResult<Vec<EscalationStepEntity>> {
+        let group = self.existing_group(&group_name).await?;
+
+        let steps = self.repository.list_escalation_steps(group.id).await?;
+
+        Ok(steps)
+    }
+
+    async fn set_escalation_policy(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        steps: Vec<EscalationStep>,
+    ) -> ServiceResult<Vec<EscalationStepEntity>> {
+        if steps.len() > self.max_steps {
+            error!(
+                "escalation policy has {} steps, at most {} are allowed",
+                steps.len(),
+                self.max_steps
+            );
+            return Err(ServiceError::ObjectConflict(String::from(
+                "escalation policy has too many steps",
+            )));
+        }
+
+        // every step fires after the previous one
+        let mut previous_delay = 0;
+        for step in steps.iter() {
+            if step.delay_minutes <= previous_delay {
+                error!(
+                    "escalation delay of {} minutes does not follow the previous step",
+                    step.delay_minutes
+                );
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "escalation delays must be positive and increasing",
+                )));
+            }
+            previous_delay = step.delay_minutes;
+        }
+
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        let mut new_steps = vec![];
+        for step in steps {
+            new_steps.push(self.new_escalation_step(step, &actor).await?);
+        }
+
+        info!(
+            "setting {} escalation steps on group {:?}",
+            new_steps.len(),
+            &group_name
+        );
+        let steps = self
+            .repository
+            .set_escalation_steps(group.id, &new_steps)
+            .await?;
+
+        info!("escalation policy successfully set");
+        Ok(steps)
+    }
+}
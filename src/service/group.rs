@@ -1,10 +1,73 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
 use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
 use tracing::{error, info};
 
-use crate::repository::group::{DynGroupRepositoryTrait, GroupEntity};
+use crate::repository::{
+    group::{DynGroupRepositoryTrait, GroupEntity, GroupFilter, GroupUpdate, GROUP_VISIBILITIES},
+    role::{DynRoleRepositoryTrait, GroupRole, RoleEntity, RoleMember},
+};
+
+const DEFAULT_GROUP_PAGE_SIZE: i64 = 50;
+const MAX_GROUP_PAGE_SIZE: i64 = 500;
+
+/// Empty search and visibility values do not filter the groups.
+fn group_filter(search: String, visibility: String) -> ServiceResult<GroupFilter> {
+    if !visibility.is_empty() && !GROUP_VISIBILITIES.contains(&visibility.as_str()) {
+        error!("group visibility {:?} is not supported", &visibility);
+        return Err(ServiceError::ObjectConflict(String::from(
+            "unknown group visibility",
+        )));
+    }
+
+    Ok(GroupFilter {
+        search: Some(search).filter(|search| !search.is_empty()),
+        visibility: Some(visibility).filter(|visibility| !visibility.is_empty()),
+    })
+}
+
+/// Roles are granted to exactly one of a user id or an email.
+fn validate_member(member: &RoleMember) -> ServiceResult<()> {
+    let has_email = member.email.as_ref().is_some_and(|email| !email.is_empty());
+    if member.user_id.is_some() == has_email {
+        error!("group member must be a user id or an email");
+        return Err(ServiceError::ObjectConflict(String::from(
+            "group member must be a user id or an email",
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the role of the actor in the group when it is at least `required`.
+pub async fn authorize(
+    role_repository: &DynRoleRepositoryTrait,
+    group: &GroupEntity,
+    actor: &RoleMember,
+    required: GroupRole,
+) -> ServiceResult<GroupRole> {
+    let actor_role = role_repository
+        .get_role(group.id, actor)
+        .await?
+        .and_then(|role| role.group_role());
+
+    match actor_role {
+        Some(role) if role >= required => Ok(role),
+        _ => {
+            error!(
+                "actor {:?} {:?} is not {} of group {:?}",
+                actor.user_id,
+                actor.email,
+                required.as_str(),
+                &group.name
+            );
+            Err(ServiceError::ObjectConflict(String::from(
+                "insufficient group role",
+            )))
+        }
+    }
+}
 
 #[async_trait]
 pub trait GroupServiceTrait {
@@ -17,9 +80,49 @@ pub trait GroupServiceTrait {
     async fn remove_group(
         &self,
         name: String,
-        admin_email: String,
+        actor: RoleMember,
     ) -> ServiceResult<Option<GroupEntity>>;
-    async fn list_groups_by_sub(&self, user_id: i64) -> ServiceResult<Vec<GroupEntity>>;
+    async fn restore_group(&self, name: String, actor: RoleMember) -> ServiceResult<GroupEntity>;
+    async fn update_group(
+        &self,
+        name: String,
+        actor: RoleMember,
+        update: GroupUpdate,
+        parent: Option<String>,
+    ) -> ServiceResult<GroupEntity>;
+    async fn grant_role(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        member: RoleMember,
+        role: String,
+    ) -> ServiceResult<RoleEntity>;
+    async fn revoke_role(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        member: RoleMember,
+    ) -> ServiceResult<RoleEntity>;
+    async fn transfer_ownership(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        new_owner: RoleMember,
+    ) -> ServiceResult<RoleEntity>;
+    async fn list_groups_by_sub(
+        &self,
+        user_id: i64,
+        effective: bool,
+    ) -> ServiceResult<Vec<GroupEntity>>;
+    async fn list_groups(
+        &self,
+        search: String,
+        visibility: String,
+        offset: i64,
+        limit: i64,
+    ) -> ServiceResult<Vec<GroupEntity>>;
+    async fn count_groups(&self, search: String, visibility: String) -> ServiceResult<i64>;
+    async fn count_subscribers(&self, groups: &[GroupEntity]) -> ServiceResult<HashMap<i64, i64>>;
     async fn verify_token(&self, name: String, token: String) -> ServiceResult<bool>;
 }
 
@@ -27,11 +130,56 @@ pub type DynGroupServiceTrait = Arc<dyn GroupServiceTrait + Sync + Send>;
 
 pub struct GroupService {
     repository: DynGroupRepositoryTrait,
+    role_repository: DynRoleRepositoryTrait,
 }
 
 impl GroupService {
-    pub fn new(repository: DynGroupRepositoryTrait) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: DynGroupRepositoryTrait,
+        role_repository: DynRoleRepositoryTrait,
+    ) -> Self {
+        Self {
+            repository,
+            role_repository,
+        }
+    }
+
+    async fn existing_group(&self, name: &str) -> ServiceResult<GroupEntity> {
+        match self.repository.get_group(name).await? {
+            Some(group) => Ok(group),
+            None => {
+                error!("group {:?} does not exist", name);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "group does not exist",
+                )))
+            }
+        }
+    }
+
+    /// Resolves the new parent of `group`, the actor has to administer both
+    /// groups and the parent can not be the group itself or one of its
+    /// descendants.
+    async fn parent_group(
+        &self,
+        group: &GroupEntity,
+        actor: &RoleMember,
+        parent_name: &str,
+    ) -> ServiceResult<i64> {
+        let parent = self.existing_group(parent_name).await?;
+        authorize(&self.role_repository, &parent, actor, GroupRole::Admin).await?;
+
+        let descendant_ids = self.repository.list_descendant_ids(group.id).await?;
+        if descendant_ids.contains(&parent.id) {
+            error!(
+                "group {:?} can not be moved under {:?}",
+                &group.name, parent_name
+            );
+            return Err(ServiceError::ObjectConflict(String::from(
+                "group hierarchy can not contain cycles",
+            )));
+        }
+
+        Ok(parent.id)
     }
 }
 
@@ -65,42 +213,292 @@ impl GroupServiceTrait for GroupService {
     async fn remove_group(
         &self,
         name: String,
-        admin_email: String,
+        actor: RoleMember,
     ) -> ServiceResult<Option<GroupEntity>> {
-        let existing_group = self.repository.get_group(&name).await?;
+        let group = self.existing_group(&name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Owner).await?;
 
-        if existing_group.is_none() {
-            error!("group {:?} does not exist", &name);
+        info!("deleting group {:?}", &name);
+        let removed_group = self.repository.remove_group(&name).await?;
+
+        info!("group successfully removed, it can be restored until it is purged");
+
+        Ok(removed_group)
+    }
+
+    async fn restore_group(&self, name: String, actor: RoleMember) -> ServiceResult<GroupEntity> {
+        let deleted_group = match self.repository.get_deleted_group(&name).await? {
+            Some(group) => group,
+            None => {
+                error!("deleted group {:?} does not exist", &name);
+                return Err(ServiceError::NotFound(String::from(
+                    "deleted group not found",
+                )));
+            }
+        };
+        authorize(
+            &self.role_repository,
+            &deleted_group,
+            &actor,
+            GroupRole::Owner,
+        )
+        .await?;
+
+        if self.repository.get_group(&name).await?.is_some() {
+            error!("group {:?} already exists", &name);
             return Err(ServiceError::ObjectConflict(String::from(
-                "group does not exist",
+                "group name is taken",
             )));
         }
 
-        info!("deleting group {:?}", &name);
-        let removed_group = self.repository.remove_group(&name, &admin_email).await?;
+        info!("restoring group {:?}", &name);
+        let restored_group = self.repository.restore_group(deleted_group.id).await?;
+
+        match restored_group {
+            Some(group) => {
+                info!("group successfully restored");
+                Ok(group)
+            }
+            None => Err(ServiceError::NotFound(String::from(
+                "deleted group not found",
+            ))),
+        }
+    }
+
+    async fn update_group(
+        &self,
+        name: String,
+        actor: RoleMember,
+        mut update: GroupUpdate,
+        parent: Option<String>,
+    ) -> ServiceResult<GroupEntity> {
+        let group = self.existing_group(&name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        // an empty parent detaches the group from its current parent
+        if let Some(parent_name) = parent {
+            let parent_id = if parent_name.is_empty() {
+                None
+            } else {
+                Some(self.parent_group(&group, &actor, &parent_name).await?)
+            };
+            update.parent_id = Some(parent_id);
+        }
+
+        if let Some(visibility) = &update.visibility {
+            if !GROUP_VISIBILITIES.contains(&visibility.as_str()) {
+                error!("group visibility {:?} is not supported", visibility);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "unknown group visibility",
+                )));
+            }
+        }
+
+        if let Some(new_name) = update.name.as_ref().filter(|new_name| **new_name != name) {
+            if self.repository.get_group(new_name).await?.is_some() {
+                error!("group {:?} already exists", new_name);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group name is taken",
+                )));
+            }
+        }
+
+        info!("updating group {:?}", &name);
+        let updated_group = self.repository.update_group(&name, &update).await?;
+
+        match updated_group {
+            Some(group) => {
+                info!("group successfully updated");
+                Ok(group)
+            }
+            None => {
+                error!("group {:?} does not exist", &name);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "group does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn grant_role(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        member: RoleMember,
+        role: String,
+    ) -> ServiceResult<RoleEntity> {
+        validate_member(&member)?;
+        let role = match GroupRole::from_name(&role) {
+            Some(GroupRole::Owner) => {
+                error!(
+                    "ownership of group {:?} can only be transferred",
+                    &group_name
+                );
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "ownership can only be transferred",
+                )));
+            }
+            Some(role) => role,
+            None => {
+                error!("group role {:?} is not supported", &role);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "unknown group role",
+                )));
+            }
+        };
 
-        if removed_group.is_none() {
+        let group = self.existing_group(&group_name).await?;
+        let actor_role = authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        let current_role = self
+            .role_repository
+            .get_role(group.id, &member)
+            .await?
+            .and_then(|role| role.group_role());
+
+        // admins manage publishers and viewers, only the owner manages admins
+        if current_role.is_some_and(|current_role| current_role >= actor_role)
+            || (role == GroupRole::Admin && actor_role != GroupRole::Owner)
+        {
             error!(
-                "incorrect admin email ({:?}) used for group {:?}",
-                &admin_email, &name
+                "actor can not grant {} in group {:?}",
+                role.as_str(),
+                &group_name
             );
             return Err(ServiceError::ObjectConflict(String::from(
-                "group does not exist",
+                "insufficient group role",
             )));
         }
-        info!("group successfully removed");
 
-        Ok(removed_group)
+        info!("granting {} in group {:?}", role.as_str(), &group_name);
+        let granted_role = self
+            .role_repository
+            .set_role(group.id, &member, role)
+            .await?;
+
+        info!("role successfully granted");
+        Ok(granted_role)
+    }
+
+    async fn revoke_role(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        member: RoleMember,
+    ) -> ServiceResult<RoleEntity> {
+        validate_member(&member)?;
+
+        let group = self.existing_group(&group_name).await?;
+        let actor_role = authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        let current_role = self.role_repository.get_role(group.id, &member).await?;
+        let current_role = match current_role.as_ref().and_then(|role| role.group_role()) {
+            Some(current_role) => current_role,
+            None => {
+                error!("member has no role in group {:?}", &group_name);
+                return Err(ServiceError::NotFound(String::from(
+                    "member has no role in group",
+                )));
+            }
+        };
+
+        if current_role >= actor_role {
+            error!(
+                "actor can not revoke {} in group {:?}",
+                current_role.as_str(),
+                &group_name
+            );
+            return Err(ServiceError::ObjectConflict(String::from(
+                "insufficient group role",
+            )));
+        }
+
+        info!("revoking role in group {:?}", &group_name);
+        let revoked_role = self.role_repository.remove_role(group.id, &member).await?;
+
+        match revoked_role {
+            Some(role) => {
+                info!("role successfully revoked");
+                Ok(role)
+            }
+            None => Err(ServiceError::NotFound(String::from(
+                "member has no role in group",
+            ))),
+        }
+    }
+
+    async fn transfer_ownership(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        new_owner: RoleMember,
+    ) -> ServiceResult<RoleEntity> {
+        validate_member(&new_owner)?;
+
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Owner).await?;
+
+        info!("transferring ownership of group {:?}", &group_name);
+        let owner_role = self
+            .role_repository
+            .transfer_ownership(group.id, &new_owner)
+            .await?;
+
+        info!("ownership successfully transferred");
+        Ok(owner_role)
     }
 
-    async fn list_groups_by_sub(&self, user_id: i64) -> ServiceResult<Vec<GroupEntity>> {
+    async fn list_groups_by_sub(
+        &self,
+        user_id: i64,
+        effective: bool,
+    ) -> ServiceResult<Vec<GroupEntity>> {
         info!("listing group from subscriber {:?}", user_id);
-        let groups = self.repository.list_groups_by_sub(user_id).await?;
+        let groups = self
+            .repository
+            .list_groups_by_sub(user_id, effective)
+            .await?;
 
         info!("successfully obtained list of groups from subscriber");
         Ok(groups)
     }
 
+    async fn list_groups(
+        &self,
+        search: String,
+        visibility: String,
+        offset: i64,
+        limit: i64,
+    ) -> ServiceResult<Vec<GroupEntity>> {
+        let filter = group_filter(search, visibility)?;
+        let offset = offset.max(0);
+        let limit = if limit <= 0 {
+            DEFAULT_GROUP_PAGE_SIZE
+        } else {
+            limit.min(MAX_GROUP_PAGE_SIZE)
+        };
+
+        info!("listing groups");
+        let groups = self.repository.list_groups(&filter, offset, limit).await?;
+
+        info!("successfully obtained list of groups");
+        Ok(groups)
+    }
+
+    async fn count_groups(&self, search: String, visibility: String) -> ServiceResult<i64> {
+        let filter = group_filter(search, visibility)?;
+        let count = self.repository.count_groups(&filter).await?;
+
+        Ok(count)
+    }
+
+    async fn count_subscribers(&self, groups: &[GroupEntity]) -> ServiceResult<HashMap<i64, i64>> {
+        let group_ids = groups.iter().map(|group| group.id).collect::<Vec<i64>>();
+        let counts = self.repository.count_subscribers(&group_ids).await?;
+
+        Ok(counts)
+    }
+
     async fn verify_token(&self, name: String, token: String) -> ServiceResult<bool> {
         let group_option = self.repository.get_group(&name).await?;
         match group_option {
@@ -1,37 +1,417 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
+use anyhow::Context;
 use async_trait::async_trait;
-use madtofan_microservice_common::errors::ServiceResult;
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    notification::AudienceSelector,
+};
+use sqlx::types::time::OffsetDateTime;
+use tokio::sync::broadcast;
+use tracing::{error, info};
 
-use crate::repository::message::{DynMessageRepositoryTrait, MessageEntity};
+use crate::{
+    repository::{
+        channel::DynChannelRepositoryTrait,
+        channel_subscription::{
+            ChannelPattern, ChannelSubscriptionEntity, DynChannelSubscriptionRepositoryTrait,
+        },
+        delivery::{NewDelivery, IN_APP_DELIVERY_CHANNEL},
+        group::DynGroupRepositoryTrait,
+        message::{
+            DynMessageRepositoryTrait, MessageEditEntity, MessageEntity, MessageFanout,
+            MessageFilter, NewMessage, GENERATED_THREAD_PREFIX, MESSAGE_PRIORITIES,
+        },
+        role::{DynRoleRepositoryTrait, GroupRole, RoleMember},
+        subscriber::{AudienceFilter, DynSubscriberRepositoryTrait, SubscriberEntity, TagFilter},
+    },
+    service::group::authorize,
+};
+
+/// Subscribers of `group` that a message is addressed to.
+pub struct Audience {
+    pub group: String,
+    pub filter: AudienceFilter,
+}
+
+impl TryFrom<AudienceSelector> for Audience {
+    type Error = anyhow::Error;
+
+    fn try_from(selector: AudienceSelector) -> Result<Self, Self::Error> {
+        let joined_after = selector
+            .joined_after
+            .map(OffsetDateTime::from_unix_timestamp)
+            .transpose()
+            .context("invalid joined_after timestamp")?;
+        let joined_before = selector
+            .joined_before
+            .map(OffsetDateTime::from_unix_timestamp)
+            .transpose()
+            .context("invalid joined_before timestamp")?;
+
+        Ok(Self {
+            group: selector.group,
+            filter: AudienceFilter {
+                joined_after,
+                joined_before,
+                include_user_ids: selector.include_user_ids,
+                exclude_user_ids: selector.exclude_user_ids,
+                tags: TagFilter {
+                    tags: selector.tags,
+                    match_all: selector.match_all_tags,
+                },
+            },
+        })
+    }
+}
+
+/// Message posted on `channel`, with the channel's default priority when
+/// `priority` is empty.
+#[derive(Default)]
+pub struct MessagePost {
+    pub channel: String,
+    pub subject: String,
+    pub message: String,
+    pub priority: String,
+    pub audience: Option<Audience>,
+    pub thread: MessageThread,
+}
+
+/// Thread joined by a new message, the one of its parent message or the one
+/// named by `thread_key` on its channel.
+#[derive(Default)]
+pub struct MessageThread {
+    pub parent_id: Option<i64>,
+    pub thread_key: Option<String>,
+}
+
+/// Messages posted on the listed channels or on the channels matching one of
+/// `patterns`, optionally only the ones nobody acknowledged yet. When set,
+/// the patterns `user_id` subscribed to are matched too and the messages it
+/// dismissed are hidden. Threads are collapsed to their latest message with
+/// `collapse_threads`.
+#[derive(Clone, Default)]
+pub struct MessageQuery {
+    pub channels: Vec<String>,
+    pub patterns: Vec<String>,
+    pub unacknowledged_only: bool,
+    pub user_id: Option<i64>,
+    pub collapse_threads: bool,
+}
+
+pub const MESSAGE_POSTED_EVENT: &str = "posted";
+pub const MESSAGE_UPDATED_EVENT: &str = "updated";
+pub const MESSAGE_RETRACTED_EVENT: &str = "retracted";
+pub const MESSAGE_DELETED_EVENT: &str = "deleted";
+pub const MESSAGE_PINNED_EVENT: &str = "pinned";
+pub const MESSAGE_UNPINNED_EVENT: &str = "unpinned";
+
+/// A message that was posted, or changed after being posted.
+#[derive(Clone)]
+pub struct MessageEvent {
+    pub event: &'static str,
+    pub message: MessageEntity,
+}
+
+/// Events of the messages on the channels matching one of `patterns`, from
+/// the time the stream was opened.
+pub struct MessageStream {
+    pub patterns: Vec<ChannelPattern>,
+    pub receiver: broadcast::Receiver<MessageEvent>,
+}
+
+/// Number of message events kept for streams lagging behind.
+const MESSAGE_STREAM_CAPACITY: usize = 1024;
 
 #[async_trait]
 pub trait MessageServiceTrait {
     async fn get_messages(
         &self,
-        channel: Vec<String>,
+        query: MessageQuery,
         offset: i64,
         limit: i64,
     ) -> ServiceResult<Vec<MessageEntity>>;
-    async fn get_messages_count(&self, channel: Vec<String>) -> ServiceResult<i64>;
+    async fn get_messages_count(&self, query: MessageQuery) -> ServiceResult<i64>;
     async fn add_message(
         &self,
-        channel: String,
-        subject: String,
-        message: String,
+        actor: RoleMember,
+        post: MessagePost,
     ) -> ServiceResult<MessageEntity>;
     async fn clear_messages(&self, date: i64) -> ServiceResult<Vec<MessageEntity>>;
+    async fn acknowledge_message(
+        &self,
+        id: i64,
+        user_id: i64,
+        note: Option<String>,
+    ) -> ServiceResult<MessageEntity>;
+    async fn update_message(
+        &self,
+        id: i64,
+        actor: RoleMember,
+        subject: Option<String>,
+        message: Option<String>,
+    ) -> ServiceResult<MessageEntity>;
+    async fn retract_message(&self, id: i64, actor: RoleMember) -> ServiceResult<MessageEntity>;
+    async fn get_message_history(&self, id: i64) -> ServiceResult<Vec<MessageEditEntity>>;
+    async fn get_thread(&self, id: i64) -> ServiceResult<Vec<MessageEntity>>;
+    async fn count_replies(
+        &self,
+        ids: &[i64],
+        whole_thread: bool,
+    ) -> ServiceResult<HashMap<i64, i64>>;
+    async fn delete_message(&self, id: i64, actor: RoleMember) -> ServiceResult<MessageEntity>;
+    async fn dismiss_message(&self, id: i64, user_id: i64) -> ServiceResult<()>;
+    async fn pin_message(
+        &self,
+        id: i64,
+        actor: RoleMember,
+        expires_at: Option<OffsetDateTime>,
+    ) -> ServiceResult<MessageEntity>;
+    async fn unpin_message(&self, id: i64, actor: RoleMember) -> ServiceResult<MessageEntity>;
+    async fn subscribe_channel_pattern(
+        &self,
+        user_id: i64,
+        pattern: String,
+    ) -> ServiceResult<ChannelSubscriptionEntity>;
+    async fn unsubscribe_channel_pattern(
+        &self,
+        user_id: i64,
+        pattern: String,
+    ) -> ServiceResult<ChannelSubscriptionEntity>;
+    async fn list_channel_patterns(
+        &self,
+        user_id: i64,
+    ) -> ServiceResult<Vec<ChannelSubscriptionEntity>>;
+    async fn stream_messages(
+        &self,
+        user_id: Option<i64>,
+        patterns: Vec<String>,
+    ) -> ServiceResult<MessageStream>;
+}
+
+fn parse_pattern(pattern: &str) -> ServiceResult<ChannelPattern> {
+    match ChannelPattern::parse(pattern) {
+        Some(pattern) => Ok(pattern),
+        None => {
+            error!("invalid channel pattern {:?}", pattern);
+            Err(ServiceError::ObjectConflict(String::from(
+                "invalid channel pattern",
+            )))
+        }
+    }
+}
+
+fn parse_patterns(patterns: &[String]) -> ServiceResult<Vec<ChannelPattern>> {
+    patterns
+        .iter()
+        .map(|pattern| parse_pattern(pattern))
+        .collect()
+}
+
+/// Subscribers that opted out of `channel` do not receive the message at all,
+/// muted subscribers still receive it in-app but get no outbound delivery.
+/// Digest subscribers get their outbound delivery later on through the digest.
+fn fanout_message(channel: &str, subscribers: Vec<SubscriberEntity>) -> MessageFanout {
+    let mut fanout = MessageFanout::default();
+
+    for subscriber in subscribers
+        .into_iter()
+        .filter(|sub| !sub.is_opted_out(channel))
+    {
+        if !subscriber.is_muted() && subscriber.digest_period().is_none() {
+            fanout.deliveries.extend(
+                subscriber
+                    .delivery_channels
+                    .iter()
+                    .filter(|delivery_channel| *delivery_channel != IN_APP_DELIVERY_CHANNEL)
+                    .map(|delivery_channel| NewDelivery {
+                        user_id: subscriber.user_id,
+                        delivery_channel: delivery_channel.clone(),
+                    }),
+            );
+        }
+        fanout.recipients.push(subscriber.user_id);
+    }
+
+    fanout
 }
 
 pub type DynMessageServiceTrait = Arc<dyn MessageServiceTrait + Sync + Send>;
 
 pub struct MessageService {
     repository: DynMessageRepositoryTrait,
+    subscriber_repository: DynSubscriberRepositoryTrait,
+    group_repository: DynGroupRepositoryTrait,
+    channel_repository: DynChannelRepositoryTrait,
+    channel_subscription_repository: DynChannelSubscriptionRepositoryTrait,
+    role_repository: DynRoleRepositoryTrait,
+    message_sender: broadcast::Sender<MessageEvent>,
 }
 
 impl MessageService {
-    pub fn new(repository: DynMessageRepositoryTrait) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: DynMessageRepositoryTrait,
+        subscriber_repository: DynSubscriberRepositoryTrait,
+        group_repository: DynGroupRepositoryTrait,
+        channel_repository: DynChannelRepositoryTrait,
+        channel_subscription_repository: DynChannelSubscriptionRepositoryTrait,
+        role_repository: DynRoleRepositoryTrait,
+    ) -> Self {
+        let (message_sender, _) = broadcast::channel(MESSAGE_STREAM_CAPACITY);
+
+        Self {
+            repository,
+            subscriber_repository,
+            group_repository,
+            channel_repository,
+            channel_subscription_repository,
+            role_repository,
+            message_sender,
+        }
+    }
+
+    fn publish(&self, event: &'static str, message: &MessageEntity) {
+        // sending only fails when no stream is open
+        let _ = self.message_sender.send(MessageEvent {
+            event,
+            message: message.clone(),
+        });
+    }
+
+    /// Patterns given along with the ones `user_id` saved a subscription to.
+    async fn channel_patterns(
+        &self,
+        patterns: &[String],
+        user_id: Option<i64>,
+    ) -> ServiceResult<Vec<ChannelPattern>> {
+        let mut patterns = parse_patterns(patterns)?;
+
+        if let Some(user_id) = user_id {
+            let subscriptions = self
+                .channel_subscription_repository
+                .list_channel_subscriptions(user_id)
+                .await?;
+            patterns.extend(
+                subscriptions
+                    .iter()
+                    .filter_map(ChannelSubscriptionEntity::channel_pattern),
+            );
+        }
+
+        Ok(patterns)
+    }
+
+    async fn message_filter(&self, query: MessageQuery) -> ServiceResult<MessageFilter> {
+        Ok(MessageFilter {
+            patterns: self
+                .channel_patterns(&query.patterns, query.user_id)
+                .await?,
+            channels: query.channels,
+            unacknowledged_only: query.unacknowledged_only,
+            dismissed_by: query.user_id,
+            collapse_threads: query.collapse_threads,
+        })
+    }
+
+    /// Replies join the thread of their parent, which has to be posted on the
+    /// same channel.
+    async fn resolve_thread(
+        &self,
+        channel: &str,
+        thread: MessageThread,
+    ) -> ServiceResult<(Option<i64>, Option<String>)> {
+        match (thread.parent_id, thread.thread_key) {
+            (Some(_), Some(_)) => {
+                error!("message has both a parent and a thread key");
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message can not have both a parent and a thread key",
+                )))
+            }
+            (Some(parent_id), None) => {
+                let parent = match self.repository.get_message(parent_id).await? {
+                    Some(parent) => parent,
+                    None => {
+                        error!("parent message {} does not exist", parent_id);
+                        return Err(ServiceError::ObjectConflict(String::from(
+                            "parent message does not exist",
+                        )));
+                    }
+                };
+
+                if parent.channel != channel {
+                    error!(
+                        "parent message {} is not posted on channel {:?}",
+                        parent_id, channel
+                    );
+                    return Err(ServiceError::ObjectConflict(String::from(
+                        "parent message is posted on another channel",
+                    )));
+                }
+
+                Ok((Some(parent.id), Some(parent.thread)))
+            }
+            (None, Some(thread_key)) => {
+                let thread_key = thread_key.trim();
+                if thread_key.is_empty() {
+                    error!("thread key is empty");
+                    return Err(ServiceError::ObjectConflict(String::from(
+                        "thread key can not be empty",
+                    )));
+                }
+
+                // keys of threads started without one are reserved to their replies
+                if thread_key.starts_with(GENERATED_THREAD_PREFIX) {
+                    error!("thread key {:?} is reserved", thread_key);
+                    return Err(ServiceError::ObjectConflict(String::from(
+                        "thread key is reserved",
+                    )));
+                }
+
+                Ok((None, Some(thread_key.to_string())))
+            }
+            (None, None) => Ok((None, None)),
+        }
+    }
+
+    /// Messages are managed by the admins of the group owning their channel.
+    async fn authorize_message(&self, id: i64, actor: &RoleMember) -> ServiceResult<MessageEntity> {
+        let message = match self.repository.get_message(id).await? {
+            Some(message) => message,
+            None => {
+                error!("message {} does not exist", id);
+                return Err(ServiceError::NotFound(String::from(
+                    "message does not exist",
+                )));
+            }
+        };
+
+        let channel = self
+            .channel_repository
+            .get_channel(&message.channel)
+            .await?;
+        let group = match channel {
+            Some(channel) => {
+                self.group_repository
+                    .get_group_by_id(channel.group_id)
+                    .await?
+            }
+            None => None,
+        };
+        match group {
+            Some(group) => {
+                authorize(&self.role_repository, &group, actor, GroupRole::Admin).await?;
+                Ok(message)
+            }
+            None => {
+                error!(
+                    "channel {:?} of message {} has no group",
+                    &message.channel, id
+                );
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message can not be managed",
+                )))
+            }
+        }
     }
 }
 
@@ -39,32 +419,145 @@ impl MessageService {
 impl MessageServiceTrait for MessageService {
     async fn get_messages(
         &self,
-        channel: Vec<String>,
+        query: MessageQuery,
         offset: i64,
         limit: i64,
     ) -> ServiceResult<Vec<MessageEntity>> {
-        let result = self.repository.get_messages(channel, offset, limit).await?;
+        let filter = self.message_filter(query).await?;
+        let result = self.repository.get_messages(&filter, offset, limit).await?;
 
         Ok(result)
     }
 
-    async fn get_messages_count(&self, channel: Vec<String>) -> ServiceResult<i64> {
-        let result = self.repository.get_messages_count(channel).await?;
+    async fn get_messages_count(&self, query: MessageQuery) -> ServiceResult<i64> {
+        let filter = self.message_filter(query).await?;
+        let result = self.repository.get_messages_count(&filter).await?;
 
         Ok(result)
     }
 
+    /// Messages are posted by the publishers of the group owning their
+    /// channel.
     async fn add_message(
         &self,
-        channel: String,
-        subject: String,
-        message: String,
+        actor: RoleMember,
+        post: MessagePost,
     ) -> ServiceResult<MessageEntity> {
+        let MessagePost {
+            channel,
+            subject,
+            message,
+            priority,
+            audience,
+            thread,
+        } = post;
+
+        let channel_entity = match self.channel_repository.get_channel(&channel).await? {
+            Some(channel_entity) => channel_entity,
+            None => {
+                error!("channel {:?} does not exist", &channel);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "channel does not exist",
+                )));
+            }
+        };
+
+        let channel_group = match self
+            .group_repository
+            .get_group_by_id(channel_entity.group_id)
+            .await?
+        {
+            Some(group) => group,
+            None => {
+                error!("group of channel {:?} does not exist", &channel);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group does not exist",
+                )));
+            }
+        };
+        authorize(
+            &self.role_repository,
+            &channel_group,
+            &actor,
+            GroupRole::Publisher,
+        )
+        .await?;
+
+        let priority = if priority.is_empty() {
+            channel_entity.default_priority
+        } else {
+            priority
+        };
+
+        if !MESSAGE_PRIORITIES.contains(&priority.as_str()) {
+            error!("unknown message priority {:?}", &priority);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "unknown message priority",
+            )));
+        }
+
+        let (parent_id, thread_key) = self.resolve_thread(&channel, thread).await?;
+
+        let (group_id, fanout) = match audience {
+            Some(audience) => {
+                let existing_group = self.group_repository.get_group(&audience.group).await?;
+
+                let group = match existing_group {
+                    Some(group) => group,
+                    None => {
+                        error!("group {:?} does not exists", &audience.group);
+                        return Err(ServiceError::ObjectConflict(String::from(
+                            "group name does not exist",
+                        )));
+                    }
+                };
+
+                // only the group owning a channel can address its messages
+                if group.id != channel_entity.group_id {
+                    error!(
+                        "channel {:?} does not belong to group {:?}",
+                        &channel, &audience.group
+                    );
+                    return Err(ServiceError::ObjectConflict(String::from(
+                        "channel does not belong to the group",
+                    )));
+                }
+
+                info!("resolving message audience in group {:?}", &audience.group);
+                let subscribers = self
+                    .subscriber_repository
+                    .resolve_audience(&group, &audience.filter)
+                    .await?;
+                let fanout = fanout_message(&channel, subscribers);
+
+                info!(
+                    "message audience resolved to {} recipients and {} deliveries",
+                    fanout.recipients.len(),
+                    fanout.deliveries.len()
+                );
+                (Some(group.id), fanout)
+            }
+            None => (None, MessageFanout::default()),
+        };
+
         let result = self
             .repository
-            .add_message(&channel, &subject, &message)
+            .add_message(
+                &NewMessage {
+                    channel: &channel,
+                    subject: &subject,
+                    message: &message,
+                    priority: &priority,
+                    group_id,
+                    parent_id,
+                    thread_key: thread_key.as_deref(),
+                },
+                &fanout,
+            )
             .await?;
 
+        self.publish(MESSAGE_POSTED_EVENT, &result);
+
         Ok(result)
     }
     async fn clear_messages(&self, date: i64) -> ServiceResult<Vec<MessageEntity>> {
@@ -72,4 +565,356 @@ impl MessageServiceTrait for MessageService {
 
         Ok(result)
     }
+
+    async fn acknowledge_message(
+        &self,
+        id: i64,
+        user_id: i64,
+        note: Option<String>,
+    ) -> ServiceResult<MessageEntity> {
+        let message = match self.repository.get_message(id).await? {
+            Some(message) => message,
+            None => {
+                error!("message {} does not exist", id);
+                return Err(ServiceError::NotFound(String::from(
+                    "message does not exist",
+                )));
+            }
+        };
+
+        // messages addressed to an audience are acknowledged by its recipients,
+        // even when it turned out empty
+        if message.group_id.is_some() && !self.repository.is_recipient(id, user_id).await? {
+            error!("user {} is not a recipient of message {}", user_id, id);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "user is not a recipient of the message",
+            )));
+        }
+
+        let note = note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+
+        info!("acknowledging message {} by user {}", id, user_id);
+        match self
+            .repository
+            .acknowledge_message(id, user_id, note.as_deref())
+            .await?
+        {
+            Some(message) => {
+                info!("message successfully acknowledged");
+                Ok(message)
+            }
+            None => {
+                error!("message {} is already acknowledged", id);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message is already acknowledged",
+                )))
+            }
+        }
+    }
+
+    async fn update_message(
+        &self,
+        id: i64,
+        actor: RoleMember,
+        subject: Option<String>,
+        message: Option<String>,
+    ) -> ServiceResult<MessageEntity> {
+        if subject.is_none() && message.is_none() {
+            error!("no change given for message {}", id);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "nothing to update on the message",
+            )));
+        }
+
+        self.authorize_message(id, &actor).await?;
+
+        info!("updating message {}", id);
+        match self
+            .repository
+            .update_message(id, subject.as_deref(), message.as_deref())
+            .await?
+        {
+            Some(updated_message) => {
+                info!("message successfully updated");
+                self.publish(MESSAGE_UPDATED_EVENT, &updated_message);
+                Ok(updated_message)
+            }
+            None => {
+                error!("message {} is retracted", id);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message is retracted",
+                )))
+            }
+        }
+    }
+
+    async fn retract_message(&self, id: i64, actor: RoleMember) -> ServiceResult<MessageEntity> {
+        self.authorize_message(id, &actor).await?;
+
+        info!("retracting message {}", id);
+        match self.repository.retract_message(id).await? {
+            Some(retracted_message) => {
+                info!("message successfully retracted");
+                self.publish(MESSAGE_RETRACTED_EVENT, &retracted_message);
+                Ok(retracted_message)
+            }
+            None => {
+                error!("message {} is already retracted", id);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message is already retracted",
+                )))
+            }
+        }
+    }
+
+    async fn get_message_history(&self, id: i64) -> ServiceResult<Vec<MessageEditEntity>> {
+        if self.repository.get_message(id).await?.is_none() {
+            error!("message {} does not exist", id);
+            return Err(ServiceError::NotFound(String::from(
+                "message does not exist",
+            )));
+        }
+
+        let edits = self.repository.list_message_edits(id).await?;
+
+        Ok(edits)
+    }
+
+    async fn get_thread(&self, id: i64) -> ServiceResult<Vec<MessageEntity>> {
+        let message = match self.repository.get_message(id).await? {
+            Some(message) => message,
+            None => {
+                error!("message {} does not exist", id);
+                return Err(ServiceError::NotFound(String::from(
+                    "message does not exist",
+                )));
+            }
+        };
+
+        let messages = self
+            .repository
+            .list_thread_messages(&message.channel, &message.thread)
+            .await?;
+
+        Ok(messages)
+    }
+
+    async fn count_replies(
+        &self,
+        ids: &[i64],
+        whole_thread: bool,
+    ) -> ServiceResult<HashMap<i64, i64>> {
+        let reply_counts = self
+            .repository
+            .count_replies(ids, whole_thread)
+            .await?
+            .into_iter()
+            .map(|reply_count| (reply_count.message_id, reply_count.reply_count))
+            .collect();
+
+        Ok(reply_counts)
+    }
+
+    async fn delete_message(&self, id: i64, actor: RoleMember) -> ServiceResult<MessageEntity> {
+        self.authorize_message(id, &actor).await?;
+
+        info!("deleting message {}", id);
+        match self.repository.delete_message(id).await? {
+            Some(deleted_message) => {
+                info!("message successfully deleted");
+                self.publish(MESSAGE_DELETED_EVENT, &deleted_message);
+                Ok(deleted_message)
+            }
+            None => {
+                error!("message {} does not exist", id);
+                Err(ServiceError::NotFound(String::from(
+                    "message does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn dismiss_message(&self, id: i64, user_id: i64) -> ServiceResult<()> {
+        let message = match self.repository.get_message(id).await? {
+            Some(message) => message,
+            None => {
+                error!("message {} does not exist", id);
+                return Err(ServiceError::NotFound(String::from(
+                    "message does not exist",
+                )));
+            }
+        };
+
+        // messages addressed to an audience are only dismissed by its recipients,
+        // even when it turned out empty
+        if message.group_id.is_some() && !self.repository.is_recipient(id, user_id).await? {
+            error!("user {} is not a recipient of message {}", user_id, id);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "user is not a recipient of the message",
+            )));
+        }
+
+        info!("dismissing message {} for user {}", id, user_id);
+        if !self.repository.dismiss_message(id, user_id).await? {
+            error!("message {} is already dismissed by user {}", id, user_id);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "message is already dismissed",
+            )));
+        }
+
+        info!("message successfully dismissed");
+        Ok(())
+    }
+
+    async fn pin_message(
+        &self,
+        id: i64,
+        actor: RoleMember,
+        expires_at: Option<OffsetDateTime>,
+    ) -> ServiceResult<MessageEntity> {
+        if expires_at.is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc()) {
+            error!("pin expiry {:?} is not in the future", expires_at);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "pin expiry must be in the future",
+            )));
+        }
+
+        self.authorize_message(id, &actor).await?;
+
+        info!("pinning message {}", id);
+        match self.repository.pin_message(id, expires_at).await? {
+            Some(pinned_message) => {
+                info!("message successfully pinned");
+                self.publish(MESSAGE_PINNED_EVENT, &pinned_message);
+                Ok(pinned_message)
+            }
+            None => {
+                error!("message {} is retracted", id);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message is retracted",
+                )))
+            }
+        }
+    }
+
+    async fn unpin_message(&self, id: i64, actor: RoleMember) -> ServiceResult<MessageEntity> {
+        self.authorize_message(id, &actor).await?;
+
+        info!("unpinning message {}", id);
+        match self.repository.unpin_message(id).await? {
+            Some(unpinned_message) => {
+                info!("message successfully unpinned");
+                self.publish(MESSAGE_UNPINNED_EVENT, &unpinned_message);
+                Ok(unpinned_message)
+            }
+            None => {
+                error!("message {} is not pinned", id);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "message is not pinned",
+                )))
+            }
+        }
+    }
+
+    async fn subscribe_channel_pattern(
+        &self,
+        user_id: i64,
+        pattern: String,
+    ) -> ServiceResult<ChannelSubscriptionEntity> {
+        let pattern = parse_pattern(&pattern)?;
+
+        info!(
+            "subscribing user {} to channel pattern {}",
+            user_id, &pattern
+        );
+        match self
+            .channel_subscription_repository
+            .add_channel_subscription(user_id, &pattern)
+            .await?
+        {
+            Some(subscription) => {
+                info!("channel pattern successfully subscribed");
+                Ok(subscription)
+            }
+            None => {
+                error!(
+                    "user {} is already subscribed to channel pattern {}",
+                    user_id, &pattern
+                );
+                Err(ServiceError::ObjectConflict(String::from(
+                    "channel pattern is already subscribed",
+                )))
+            }
+        }
+    }
+
+    async fn unsubscribe_channel_pattern(
+        &self,
+        user_id: i64,
+        pattern: String,
+    ) -> ServiceResult<ChannelSubscriptionEntity> {
+        let pattern = parse_pattern(&pattern)?;
+
+        info!(
+            "unsubscribing user {} from channel pattern {}",
+            user_id, &pattern
+        );
+        match self
+            .channel_subscription_repository
+            .remove_channel_subscription(user_id, &pattern)
+            .await?
+        {
+            Some(subscription) => {
+                info!("channel pattern successfully unsubscribed");
+                Ok(subscription)
+            }
+            None => {
+                error!(
+                    "user {} is not subscribed to channel pattern {}",
+                    user_id, &pattern
+                );
+                Err(ServiceError::NotFound(String::from(
+                    "channel pattern is not subscribed",
+                )))
+            }
+        }
+    }
+
+    async fn list_channel_patterns(
+        &self,
+        user_id: i64,
+    ) -> ServiceResult<Vec<ChannelSubscriptionEntity>> {
+        let subscriptions = self
+            .channel_subscription_repository
+            .list_channel_subscriptions(user_id)
+            .await?;
+
+        Ok(subscriptions)
+    }
+
+    async fn stream_messages(
+        &self,
+        user_id: Option<i64>,
+        patterns: Vec<String>,
+    ) -> ServiceResult<MessageStream> {
+        let patterns = self.channel_patterns(&patterns, user_id).await?;
+
+        if patterns.is_empty() {
+            error!("no channel pattern to stream messages from");
+            return Err(ServiceError::ObjectConflict(String::from(
+                "no channel pattern to stream",
+            )));
+        }
+
+        info!(
+            "streaming messages matching {} channel patterns",
+            patterns.len()
+        );
+        Ok(MessageStream {
+            patterns,
+            receiver: self.message_sender.subscribe(),
+        })
+    }
 }
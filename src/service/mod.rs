@@ -1,3 +1,5 @@
+pub mod channel;
+pub mod escalation;
 pub mod group;
 pub mod message;
 pub mod subscriber;
@@ -6,29 +8,57 @@ pub mod subscriber;
 pub mod test {
     use std::{sync::Arc, thread, time};
 
-    use sqlx::PgPool;
+    use madtofan_microservice_common::notification::EscalationStep;
+    use sqlx::{
+        types::time::{Duration, OffsetDateTime},
+        PgPool,
+    };
 
     use crate::{
         repository::{
-            group::{DynGroupRepositoryTrait, GroupRepository},
-            message::{DynMessageRepositoryTrait, MessageRepository},
-            subscriber::{DynSubscriberRepositoryTrait, SubscriberRepository},
+            channel::{ChannelRepository, ChannelUpdate, DynChannelRepositoryTrait},
+            channel_subscription::{
+                ChannelSubscriptionRepository, DynChannelSubscriptionRepositoryTrait,
+            },
+            delivery::{DeliveryRepository, DynDeliveryRepositoryTrait},
+            escalation::{DynEscalationRepositoryTrait, EscalationRepository},
+            group::{DynGroupRepositoryTrait, GroupRepository, GroupUpdate},
+            invitation::{DynInvitationRepositoryTrait, InvitationRepository},
+            join_request::{DynJoinRequestRepositoryTrait, JoinRequestRepository},
+            message::{
+                DynMessageRepositoryTrait, MessageFanout, MessageFilter, MessageRepository,
+                NewMessage,
+            },
+            quiet_hours::{DynQuietHoursRepositoryTrait, QuietHoursRepository},
+            role::{DynRoleRepositoryTrait, GroupRole, RoleMember, RoleRepository},
+            subscriber::{
+                AudienceFilter, DynSubscriberRepositoryTrait, SubscriberPreferences,
+                SubscriberRepository, TagFilter,
+            },
         },
         service::{
+            channel::{ChannelService, DynChannelServiceTrait},
+            escalation::{DynEscalationServiceTrait, EscalationService},
             group::{DynGroupServiceTrait, GroupService},
-            subscriber::{DynSubscriberServiceTrait, SubscriberService},
+            subscriber::{DynSubscriberServiceTrait, SubscriberService, SubscriptionOutcome},
         },
     };
 
-    use super::message::{DynMessageServiceTrait, MessageService};
+    use super::message::{
+        Audience, DynMessageServiceTrait, MessagePost, MessageQuery, MessageService, MessageThread,
+    };
 
     struct AllTraits {
         subscriber_repository: DynSubscriberRepositoryTrait,
         group_repository: DynGroupRepositoryTrait,
         message_repository: DynMessageRepositoryTrait,
+        delivery_repository: DynDeliveryRepositoryTrait,
         subscriber_service: DynSubscriberServiceTrait,
         group_service: DynGroupServiceTrait,
         message_service: DynMessageServiceTrait,
+        channel_repository: DynChannelRepositoryTrait,
+        channel_service: DynChannelServiceTrait,
+        escalation_service: DynEscalationServiceTrait,
     }
 
     fn initialize_handler(pool: PgPool) -> AllTraits {
@@ -38,14 +68,53 @@ pub mod test {
             Arc::new(GroupRepository::new(pool.clone())) as DynGroupRepositoryTrait;
         let message_repository =
             Arc::new(MessageRepository::new(pool.clone())) as DynMessageRepositoryTrait;
+        let quiet_hours_repository =
+            Arc::new(QuietHoursRepository::new(pool.clone())) as DynQuietHoursRepositoryTrait;
+        let delivery_repository =
+            Arc::new(DeliveryRepository::new(pool.clone())) as DynDeliveryRepositoryTrait;
+        let role_repository = Arc::new(RoleRepository::new(pool.clone())) as DynRoleRepositoryTrait;
+        let invitation_repository =
+            Arc::new(InvitationRepository::new(pool.clone())) as DynInvitationRepositoryTrait;
+        let join_request_repository =
+            Arc::new(JoinRequestRepository::new(pool.clone())) as DynJoinRequestRepositoryTrait;
+        let channel_repository =
+            Arc::new(ChannelRepository::new(pool.clone())) as DynChannelRepositoryTrait;
+        let channel_subscription_repository =
+            Arc::new(ChannelSubscriptionRepository::new(pool.clone()))
+                as DynChannelSubscriptionRepositoryTrait;
+        let escalation_repository =
+            Arc::new(EscalationRepository::new(pool.clone())) as DynEscalationRepositoryTrait;
         let subscriber_service = Arc::new(SubscriberService::new(
             subscriber_repository.clone(),
             group_repository.clone(),
+            quiet_hours_repository.clone(),
+            role_repository.clone(),
+            invitation_repository,
+            join_request_repository,
         )) as DynSubscriberServiceTrait;
-        let group_service =
-            Arc::new(GroupService::new(group_repository.clone())) as DynGroupServiceTrait;
-        let message_service =
-            Arc::new(MessageService::new(message_repository.clone())) as DynMessageServiceTrait;
+        let group_service = Arc::new(GroupService::new(
+            group_repository.clone(),
+            role_repository.clone(),
+        )) as DynGroupServiceTrait;
+        let message_service = Arc::new(MessageService::new(
+            message_repository.clone(),
+            subscriber_repository.clone(),
+            group_repository.clone(),
+            channel_repository.clone(),
+            channel_subscription_repository,
+            role_repository.clone(),
+        )) as DynMessageServiceTrait;
+        let channel_service = Arc::new(ChannelService::new(
+            channel_repository.clone(),
+            group_repository.clone(),
+            role_repository.clone(),
+        )) as DynChannelServiceTrait;
+        let escalation_service = Arc::new(EscalationService::new(
+            escalation_repository,
+            group_repository.clone(),
+            role_repository.clone(),
+            2,
+        )) as DynEscalationServiceTrait;
 
         AllTraits {
             subscriber_repository,
@@ -54,6 +123,10 @@ pub mod test {
             group_service,
             message_repository,
             message_service,
+            delivery_repository,
+            channel_repository,
+            channel_service,
+            escalation_service,
         }
     }
 
@@ -101,7 +174,10 @@ pub mod test {
             .group_service
             .remove_group(
                 group_to_remove_name.to_string(),
-                group_to_remove_admin_email.to_string(),
+                RoleMember {
+                    user_id: None,
+                    email: Some(group_to_remove_admin_email.to_string()),
+                },
             )
             .await?;
 
@@ -119,51 +195,334 @@ pub mod test {
     }
 
     #[sqlx::test]
-    async fn list_subs_by_group_test(pool: PgPool) -> anyhow::Result<()> {
+    async fn restore_group_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
 
-        let group1_name = "group1_name";
-        let group1_admin_email = "admin_email";
-        let group1_token = "token";
-        let group1 = traits
-            .group_repository
-            .add_group(group1_name, group1_admin_email, group1_token)
-            .await?;
-        let group2 = traits
+        let group_name = "group_name";
+        let owner = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let group = traits
             .group_repository
-            .add_group("group2_name", "admin_email", "token")
+            .add_group(group_name, "admin_email", "token")
             .await?;
-
-        let sub1_id = 0;
         traits
             .subscriber_repository
-            .add_subscriber(sub1_id, &group1)
+            .add_subscriber(0, &group)
             .await?;
-        let sub2_id = 1;
+
         traits
-            .subscriber_repository
-            .add_subscriber(sub2_id, &group2)
+            .group_service
+            .remove_group(group_name.to_string(), owner.clone())
+            .await?;
+
+        let obtained_group = traits.group_repository.get_group(group_name).await?;
+
+        assert!(obtained_group.is_none());
+
+        let restored_group = traits
+            .group_service
+            .restore_group(group_name.to_string(), owner.clone())
             .await?;
 
+        assert_eq!(restored_group.id, group.id);
+        assert!(restored_group.deleted_at.is_none());
+
         let subs_list = traits
             .subscriber_service
-            .list_subs_by_group(group1_name.to_string())
+            .list_subs_by_group(group_name.to_string())
             .await?;
 
         assert_eq!(subs_list.len(), 1);
-        assert_eq!(subs_list.first().unwrap().user_id, sub1_id);
+
+        // a new group took the name of the deleted one
+        traits
+            .group_service
+            .remove_group(group_name.to_string(), owner.clone())
+            .await?;
+        traits
+            .group_repository
+            .add_group(group_name, "other_email", "token")
+            .await?;
+
+        let restore_result = traits
+            .group_service
+            .restore_group(group_name.to_string(), owner)
+            .await;
+
+        assert!(restore_result.is_err());
 
         Ok(())
     }
 
     #[sqlx::test]
-    async fn list_groups_by_sub(pool: PgPool) -> anyhow::Result<()> {
+    async fn update_group_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let admin_email = "admin_email";
+        let group = traits
+            .group_repository
+            .add_group(group_name, admin_email, "token")
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some(admin_email.to_string()),
+        };
+
+        let update = GroupUpdate {
+            visibility: Some("hidden".to_string()),
+            ..Default::default()
+        };
+        let invalid_visibility_result = traits
+            .group_service
+            .update_group(group_name.to_string(), admin.clone(), update, None)
+            .await;
+
+        assert!(invalid_visibility_result.is_err());
+
+        let update = GroupUpdate {
+            description: Some("description".to_string()),
+            ..Default::default()
+        };
+        let wrong_admin_result = traits
+            .group_service
+            .update_group(
+                group_name.to_string(),
+                RoleMember {
+                    user_id: None,
+                    email: Some("other_email".to_string()),
+                },
+                update,
+                None,
+            )
+            .await;
+
+        assert!(wrong_admin_result.is_err());
+
+        let new_group_name = "new_group_name";
+        let update = GroupUpdate {
+            name: Some(new_group_name.to_string()),
+            description: Some("description".to_string()),
+            visibility: Some("invite_only".to_string()),
+            ..Default::default()
+        };
+        let updated_group = traits
+            .group_service
+            .update_group(group_name.to_string(), admin.clone(), update, None)
+            .await?;
+
+        assert_eq!(updated_group.id, group.id);
+        assert_eq!(updated_group.name, new_group_name);
+        assert_eq!(updated_group.description, "description");
+        assert_eq!(updated_group.visibility, "invite_only");
+        assert_eq!(updated_group.admin_email, admin_email);
+        assert!(updated_group.updated_at > group.updated_at);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn group_hierarchy_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let company = traits
+            .group_repository
+            .add_group("company", "admin_email", "token")
+            .await?;
+        traits
+            .group_repository
+            .add_group("team", "admin_email", "token")
+            .await?;
+        traits
+            .group_repository
+            .add_group("other_team", "other_email", "token")
+            .await?;
+
+        let team = traits
+            .group_service
+            .update_group(
+                "team".to_string(),
+                admin.clone(),
+                GroupUpdate::default(),
+                Some("company".to_string()),
+            )
+            .await?;
+
+        assert_eq!(team.parent_id, Some(company.id));
+
+        let self_parent_result = traits
+            .group_service
+            .update_group(
+                "team".to_string(),
+                admin.clone(),
+                GroupUpdate::default(),
+                Some("team".to_string()),
+            )
+            .await;
+
+        assert!(self_parent_result.is_err());
+
+        let cycle_result = traits
+            .group_service
+            .update_group(
+                "company".to_string(),
+                admin.clone(),
+                GroupUpdate::default(),
+                Some("team".to_string()),
+            )
+            .await;
+
+        assert!(cycle_result.is_err());
+
+        let foreign_parent_result = traits
+            .group_service
+            .update_group(
+                "team".to_string(),
+                admin.clone(),
+                GroupUpdate::default(),
+                Some("other_team".to_string()),
+            )
+            .await;
+
+        assert!(foreign_parent_result.is_err());
+
+        let team = traits
+            .group_service
+            .update_group(
+                "team".to_string(),
+                admin,
+                GroupUpdate::default(),
+                Some(String::new()),
+            )
+            .await?;
+
+        assert!(team.parent_id.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn group_roles_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        traits
+            .group_repository
+            .add_group(group_name, "owner_email", "token")
+            .await?;
+        let owner = RoleMember {
+            user_id: None,
+            email: Some("owner_email".to_string()),
+        };
+        let admin = RoleMember {
+            user_id: Some(1),
+            email: None,
+        };
+        let publisher = RoleMember {
+            user_id: Some(2),
+            email: None,
+        };
+
+        traits
+            .group_service
+            .grant_role(
+                group_name.to_string(),
+                owner.clone(),
+                admin.clone(),
+                "admin".to_string(),
+            )
+            .await?;
+
+        // admins can not promote other members to admin
+        let promote_result = traits
+            .group_service
+            .grant_role(
+                group_name.to_string(),
+                admin.clone(),
+                publisher.clone(),
+                "admin".to_string(),
+            )
+            .await;
+
+        assert!(promote_result.is_err());
+
+        let granted_role = traits
+            .group_service
+            .grant_role(
+                group_name.to_string(),
+                admin.clone(),
+                publisher.clone(),
+                "publisher".to_string(),
+            )
+            .await?;
+
+        assert_eq!(granted_role.group_role(), Some(GroupRole::Publisher));
+
+        let remove_result = traits
+            .group_service
+            .remove_group(group_name.to_string(), admin.clone())
+            .await;
+
+        assert!(remove_result.is_err());
+
+        traits
+            .group_service
+            .revoke_role(group_name.to_string(), admin.clone(), publisher.clone())
+            .await?;
+
+        let revoke_result = traits
+            .group_service
+            .revoke_role(group_name.to_string(), admin.clone(), publisher)
+            .await;
+
+        assert!(revoke_result.is_err());
+
+        let owner_role = traits
+            .group_service
+            .transfer_ownership(group_name.to_string(), owner.clone(), admin.clone())
+            .await?;
+
+        assert_eq!(owner_role.user_id, Some(1));
+        let group = traits
+            .group_repository
+            .get_group(group_name)
+            .await?
+            .unwrap();
+        assert_eq!(group.admin_email, "");
+
+        let transfer_result = traits
+            .group_service
+            .transfer_ownership(group_name.to_string(), owner, admin.clone())
+            .await;
+
+        assert!(transfer_result.is_err());
+
+        let removed_group = traits
+            .group_service
+            .remove_group(group_name.to_string(), admin)
+            .await?;
+
+        assert!(removed_group.is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_subs_by_group_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
 
         let group1_name = "group1_name";
+        let group1_admin_email = "admin_email";
+        let group1_token = "token";
         let group1 = traits
             .group_repository
-            .add_group(group1_name, "admin_email", "token")
+            .add_group(group1_name, group1_admin_email, group1_token)
             .await?;
         let group2 = traits
             .group_repository
@@ -181,16 +540,19 @@ pub mod test {
             .add_subscriber(sub2_id, &group2)
             .await?;
 
-        let groups_list = traits.group_service.list_groups_by_sub(sub1_id).await?;
+        let subs_list = traits
+            .subscriber_service
+            .list_subs_by_group(group1_name.to_string())
+            .await?;
 
-        assert_eq!(groups_list.len(), 1);
-        assert_eq!(groups_list.first().unwrap().name, group1_name);
+        assert_eq!(subs_list.len(), 1);
+        assert_eq!(subs_list.first().unwrap().user_id, sub1_id);
 
         Ok(())
     }
 
     #[sqlx::test]
-    async fn remove_subcriber_from_group_test(pool: PgPool) -> anyhow::Result<()> {
+    async fn add_subscriber_twice_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
 
         let group_name = "group_name";
@@ -199,99 +561,1870 @@ pub mod test {
             .add_group(group_name, "admin_email", "token")
             .await?;
 
-        let sub1_id = 0;
-        traits
-            .subscriber_repository
-            .add_subscriber(sub1_id, &group)
-            .await?;
-        let sub2_id = 1;
-        traits
-            .subscriber_repository
-            .add_subscriber(sub2_id, &group)
+        let sub_id = 0;
+        let first_outcome = traits
+            .subscriber_service
+            .add_subscriber(sub_id, group_name.to_string(), None)
             .await?;
-
-        traits
+        let second_outcome = traits
             .subscriber_service
-            .remove_subscriber(sub1_id, group_name.to_string())
+            .add_subscriber(sub_id, group_name.to_string(), None)
             .await?;
+
+        assert_eq!(first_outcome, SubscriptionOutcome::Subscribed);
+        assert_eq!(second_outcome, SubscriptionOutcome::AlreadySubscribed);
+
         let subs_list = traits
             .subscriber_repository
             .list_subs_by_group(&group)
             .await?;
 
         assert_eq!(subs_list.len(), 1);
-        assert_eq!(subs_list.first().unwrap().user_id, sub2_id);
 
         Ok(())
     }
 
     #[sqlx::test]
-    async fn verify_token_test(pool: PgPool) -> anyhow::Result<()> {
-        let all_traits = initialize_handler(pool);
+    async fn invite_only_group_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
 
         let group_name = "group_name";
-        let token = "token";
-        all_traits
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let group = traits
             .group_repository
-            .add_group(group_name, "admin_email", token)
+            .add_group(group_name, "admin_email", "token")
             .await?;
-
-        let verify_valid_token = all_traits
+        traits
             .group_service
-            .verify_token(group_name.to_string(), token.to_string())
+            .update_group(
+                group_name.to_string(),
+                admin.clone(),
+                GroupUpdate {
+                    visibility: Some("invite_only".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )
             .await?;
 
-        assert!(verify_valid_token);
+        let uninvited_result = traits
+            .subscriber_service
+            .add_subscriber(0, group_name.to_string(), None)
+            .await;
 
-        let verify_invalid_token = all_traits
-            .group_service
-            .verify_token(group_name.to_string(), "invalid_token".to_string())
+        assert!(uninvited_result.is_err());
+
+        let invitation = traits
+            .subscriber_service
+            .create_invitation(group_name.to_string(), admin.clone(), None, Some(1))
             .await?;
 
-        assert!(!verify_invalid_token);
+        let outcome = traits
+            .subscriber_service
+            .add_subscriber(0, group_name.to_string(), Some(invitation.code.clone()))
+            .await?;
 
-        Ok(())
-    }
+        assert_eq!(outcome, SubscriptionOutcome::Subscribed);
 
-    #[sqlx::test]
-    async fn add_message_test(pool: PgPool) -> anyhow::Result<()> {
-        let all_traits = initialize_handler(pool);
+        let used_up_result = traits
+            .subscriber_service
+            .add_subscriber(1, group_name.to_string(), Some(invitation.code))
+            .await;
 
-        let message = "test_message";
-        let added_message = all_traits
-            .message_service
-            .add_message(
-                "channel".to_string(),
-                "subject".to_string(),
-                message.to_string(),
+        assert!(used_up_result.is_err());
+
+        let expired_invitation = traits
+            .subscriber_service
+            .create_invitation(
+                group_name.to_string(),
+                admin.clone(),
+                Some(OffsetDateTime::now_utc() - Duration::minutes(1)),
+                None,
             )
             .await?;
+        let expired_result = traits
+            .subscriber_service
+            .add_subscriber(1, group_name.to_string(), Some(expired_invitation.code))
+            .await;
 
-        assert_eq!(added_message.message, message);
-
-        Ok(())
-    }
+        assert!(expired_result.is_err());
 
-    #[sqlx::test]
-    async fn get_messages_test(pool: PgPool) -> anyhow::Result<()> {
-        let all_traits = initialize_handler(pool);
+        let join_request = traits
+            .subscriber_service
+            .submit_join_request(1, group_name.to_string())
+            .await?;
+        let pending_requests = traits
+            .subscriber_service
+            .list_join_requests(group_name.to_string(), admin.clone())
+            .await?;
 
-        let channel = "channel1";
-        let channels = vec![channel.to_string()];
-        let message = "test_message";
+        assert_eq!(pending_requests.len(), 1);
+        assert_eq!(pending_requests.first().unwrap().user_id, 1);
 
-        all_traits
-            .message_repository
-            .add_message(channel, "subject", "message")
-            .await?;
-        all_traits
-            .message_repository
-            .add_message(channel, "subject", message)
+        traits
+            .subscriber_service
+            .decide_join_request(group_name.to_string(), admin.clone(), join_request.id, true)
             .await?;
 
-        let obtained_messages = all_traits
-            .message_service
-            .get_messages(channels, 0, 50)
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 2);
+
+        traits
+            .group_service
+            .update_group(
+                group_name.to_string(),
+                admin,
+                GroupUpdate {
+                    visibility: Some("closed".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+
+        let closed_join_result = traits
+            .subscriber_service
+            .submit_join_request(2, group_name.to_string())
+            .await;
+
+        assert!(closed_join_result.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bulk_subscribers_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let missing_group_name = "missing_group_name";
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+
+        let results = traits
+            .subscriber_service
+            .add_subscribers(
+                admin.clone(),
+                vec![0, 1],
+                vec![group_name.to_string(), missing_group_name.to_string()],
+            )
+            .await?;
+
+        let outcome_of = |user_id: i64, group: &str| {
+            results
+                .iter()
+                .find(|result| result.user_id == user_id && result.group == group)
+                .map(|result| result.outcome)
+        };
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            outcome_of(0, group_name),
+            Some(SubscriptionOutcome::AlreadySubscribed)
+        );
+        assert_eq!(
+            outcome_of(1, group_name),
+            Some(SubscriptionOutcome::Subscribed)
+        );
+        assert_eq!(
+            outcome_of(1, missing_group_name),
+            Some(SubscriptionOutcome::GroupNotFound)
+        );
+
+        let results = traits
+            .subscriber_service
+            .remove_subscribers(admin, vec![1, 2], vec![group_name.to_string()])
+            .await?;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].outcome, SubscriptionOutcome::Unsubscribed);
+        assert_eq!(results[1].outcome, SubscriptionOutcome::NotSubscribed);
+
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 1);
+        assert_eq!(subs_list.first().unwrap().user_id, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn import_subscribers_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(1, &group)
+            .await?;
+
+        let diff = traits
+            .subscriber_service
+            .import_subscribers(
+                group_name.to_string(),
+                admin.clone(),
+                vec![1, 2, 2],
+                true,
+                true,
+            )
+            .await?;
+
+        assert_eq!(diff.added, vec![2]);
+        assert_eq!(diff.removed, vec![0]);
+        assert_eq!(diff.unchanged, 1);
+
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 2);
+
+        traits
+            .subscriber_service
+            .import_subscribers(group_name.to_string(), admin, vec![1, 2], true, false)
+            .await?;
+
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(
+            subs_list
+                .iter()
+                .map(|sub| sub.user_id)
+                .collect::<Vec<i64>>(),
+            vec![1, 2]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn closed_group_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        traits
+            .group_service
+            .update_group(
+                group_name.to_string(),
+                admin.clone(),
+                GroupUpdate {
+                    visibility: Some("invite_only".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        let invitation = traits
+            .subscriber_service
+            .create_invitation(group_name.to_string(), admin.clone(), None, None)
+            .await?;
+        traits
+            .group_service
+            .update_group(
+                group_name.to_string(),
+                admin,
+                GroupUpdate {
+                    visibility: Some("closed".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+
+        assert!(traits
+            .subscriber_service
+            .add_subscriber(0, group_name.to_string(), None)
+            .await
+            .is_err());
+        assert!(traits
+            .subscriber_service
+            .add_subscriber(0, group_name.to_string(), Some(invitation.code))
+            .await
+            .is_err());
+
+        assert!(traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bulk_subscribers_authorization_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let outsider = RoleMember {
+            user_id: Some(5),
+            email: None,
+        };
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        traits
+            .group_service
+            .update_group(
+                group_name.to_string(),
+                admin,
+                GroupUpdate {
+                    visibility: Some("closed".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+
+        assert!(traits
+            .subscriber_service
+            .add_subscribers(outsider.clone(), vec![5], vec![group_name.to_string()])
+            .await
+            .is_err());
+        assert!(traits
+            .subscriber_service
+            .import_subscribers(
+                group_name.to_string(),
+                outsider.clone(),
+                vec![5],
+                false,
+                false
+            )
+            .await
+            .is_err());
+        assert!(traits
+            .subscriber_service
+            .remove_subscribers(outsider, vec![5], vec![group_name.to_string()])
+            .await
+            .is_err());
+
+        assert!(traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_subscription_expiry_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let expires_at = OffsetDateTime::now_utc() + Duration::days(1);
+
+        let subscriber_result = traits
+            .subscriber_service
+            .set_subscription_expiry(
+                group_name.to_string(),
+                RoleMember {
+                    user_id: Some(0),
+                    email: None,
+                },
+                0,
+                Some(expires_at),
+            )
+            .await;
+
+        assert!(subscriber_result.is_err());
+
+        let missing_subscriber_result = traits
+            .subscriber_service
+            .set_subscription_expiry(group_name.to_string(), admin.clone(), 1, Some(expires_at))
+            .await;
+
+        assert!(missing_subscriber_result.is_err());
+
+        let subscriber = traits
+            .subscriber_service
+            .set_subscription_expiry(group_name.to_string(), admin, 0, Some(expires_at))
+            .await?;
+
+        assert_eq!(
+            subscriber.expires_at.map(|date| date.unix_timestamp()),
+            Some(expires_at.unix_timestamp())
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn subscriber_tags_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(0, &group)
+            .await?;
+        traits
+            .subscriber_repository
+            .add_subscriber(1, &group)
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let subscriber = traits
+            .subscriber_service
+            .set_subscriber_tags(
+                group_name.to_string(),
+                admin.clone(),
+                0,
+                vec![
+                    " oncall ".to_string(),
+                    "oncall".to_string(),
+                    String::new(),
+                    "sre".to_string(),
+                ],
+            )
+            .await?;
+
+        assert_eq!(subscriber.tags, vec!["oncall", "sre"]);
+
+        let page = traits
+            .subscriber_service
+            .get_subscribers(
+                group_name.to_string(),
+                String::new(),
+                0,
+                String::new(),
+                false,
+                TagFilter {
+                    tags: vec!["oncall".to_string()],
+                    match_all: false,
+                },
+            )
+            .await?;
+
+        assert_eq!(page.subscribers.len(), 1);
+        assert_eq!(page.subscribers.first().unwrap().user_id, 0);
+
+        let subscriber = traits
+            .subscriber_service
+            .clear_subscriber_tags(group_name.to_string(), admin, 0)
+            .await?;
+
+        assert!(subscriber.tags.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_groups_by_sub(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group1_name = "group1_name";
+        let group1 = traits
+            .group_repository
+            .add_group(group1_name, "admin_email", "token")
+            .await?;
+        let group2 = traits
+            .group_repository
+            .add_group("group2_name", "admin_email", "token")
+            .await?;
+
+        let sub1_id = 0;
+        traits
+            .subscriber_repository
+            .add_subscriber(sub1_id, &group1)
+            .await?;
+        let sub2_id = 1;
+        traits
+            .subscriber_repository
+            .add_subscriber(sub2_id, &group2)
+            .await?;
+
+        let groups_list = traits
+            .group_service
+            .list_groups_by_sub(sub1_id, false)
+            .await?;
+
+        assert_eq!(groups_list.len(), 1);
+        assert_eq!(groups_list.first().unwrap().name, group1_name);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn remove_subcriber_from_group_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let sub1_id = 0;
+        traits
+            .subscriber_repository
+            .add_subscriber(sub1_id, &group)
+            .await?;
+        let sub2_id = 1;
+        traits
+            .subscriber_repository
+            .add_subscriber(sub2_id, &group)
+            .await?;
+
+        traits
+            .subscriber_service
+            .remove_subscriber(sub1_id, group_name.to_string())
+            .await?;
+        let subs_list = traits
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?;
+
+        assert_eq!(subs_list.len(), 1);
+        assert_eq!(subs_list.first().unwrap().user_id, sub2_id);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn verify_token_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let token = "token";
+        all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", token)
+            .await?;
+
+        let verify_valid_token = all_traits
+            .group_service
+            .verify_token(group_name.to_string(), token.to_string())
+            .await?;
+
+        assert!(verify_valid_token);
+
+        let verify_invalid_token = all_traits
+            .group_service
+            .verify_token(group_name.to_string(), "invalid_token".to_string())
+            .await?;
+
+        assert!(!verify_invalid_token);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "channel", "", "high", None)
+            .await?;
+
+        let unknown_channel_result = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "unknown_channel".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(unknown_channel_result.is_err());
+
+        let message = "test_message";
+        let added_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "channel".to_string(),
+                    subject: "subject".to_string(),
+                    message: message.to_string(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(added_message.message, message);
+        assert_eq!(added_message.priority, "high");
+
+        // only publishers of the channel's group can post on it
+        let viewer = RoleMember {
+            user_id: Some(1),
+            email: None,
+        };
+        let publisher = RoleMember {
+            user_id: Some(2),
+            email: None,
+        };
+        for (member, role) in [(&viewer, "viewer"), (&publisher, "publisher")] {
+            all_traits
+                .group_service
+                .grant_role(
+                    "group_name".to_string(),
+                    admin.clone(),
+                    member.clone(),
+                    role.to_string(),
+                )
+                .await?;
+        }
+
+        for (actor, allowed) in [
+            (viewer, false),
+            (publisher, true),
+            (RoleMember::default(), false),
+        ] {
+            let post_result = all_traits
+                .message_service
+                .add_message(
+                    actor,
+                    MessagePost {
+                        channel: "channel".to_string(),
+                        subject: "subject".to_string(),
+                        message: message.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await;
+
+            assert_eq!(post_result.is_ok(), allowed);
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn acknowledge_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = all_traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "urgent", None)
+            .await?;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(1, &group)
+            .await?;
+
+        let message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: "group_name".to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(all_traits
+            .message_service
+            .acknowledge_message(message.id + 1, 1, None)
+            .await
+            .is_err());
+        assert!(all_traits
+            .message_service
+            .acknowledge_message(message.id, 2, None)
+            .await
+            .is_err());
+
+        let unacknowledged_query = MessageQuery {
+            channels: vec!["alerts".to_string()],
+            unacknowledged_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            all_traits
+                .message_service
+                .get_messages_count(unacknowledged_query.clone())
+                .await?,
+            1
+        );
+
+        let acknowledged_message = all_traits
+            .message_service
+            .acknowledge_message(message.id, 1, Some(" on it ".to_string()))
+            .await?;
+
+        assert_eq!(acknowledged_message.acknowledged_by, Some(1));
+        assert!(acknowledged_message.acknowledged_at.is_some());
+        assert_eq!(
+            acknowledged_message.acknowledgement_note,
+            Some("on it".to_string())
+        );
+        assert!(all_traits
+            .message_service
+            .acknowledge_message(message.id, 1, None)
+            .await
+            .is_err());
+        assert_eq!(
+            all_traits
+                .message_service
+                .get_messages_count(unacknowledged_query)
+                .await?,
+            0
+        );
+
+        // an audience that reached nobody still restricts acknowledgements
+        let unreached_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: "group_name".to_string(),
+                        filter: AudienceFilter {
+                            include_user_ids: vec![2],
+                            ..Default::default()
+                        },
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(unreached_message.recipient_count, 0);
+        assert!(all_traits
+            .message_service
+            .acknowledge_message(unreached_message.id, 1, None)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn escalation_policy_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        traits
+            .group_repository
+            .add_group("other_group", "other_email", "token")
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let step =
+            |delay_minutes: i32, delivery_channel: Option<&str>, target_group: Option<&str>| {
+                EscalationStep {
+                    delay_minutes,
+                    delivery_channel: delivery_channel.map(String::from),
+                    target_group: target_group.map(String::from),
+                }
+            };
+
+        let invalid_policies = vec![
+            vec![step(10, None, None)],
+            vec![step(10, Some("in_app"), None)],
+            vec![step(10, Some("pigeon"), None)],
+            vec![step(10, Some("sms"), None), step(10, Some("email"), None)],
+            vec![step(0, Some("sms"), None)],
+            vec![step(10, None, Some("other_group"))],
+            vec![
+                step(10, Some("sms"), None),
+                step(20, Some("email"), None),
+                step(30, Some("push"), None),
+            ],
+        ];
+        for steps in invalid_policies {
+            assert!(traits
+                .escalation_service
+                .set_escalation_policy("group_name".to_string(), admin.clone(), steps)
+                .await
+                .is_err());
+        }
+
+        assert!(traits
+            .escalation_service
+            .set_escalation_policy(
+                "group_name".to_string(),
+                RoleMember::default(),
+                vec![step(10, Some("sms"), None)],
+            )
+            .await
+            .is_err());
+
+        let steps = traits
+            .escalation_service
+            .set_escalation_policy(
+                "group_name".to_string(),
+                admin.clone(),
+                vec![step(10, Some("sms"), None), step(20, Some("email"), None)],
+            )
+            .await?;
+
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps.last().unwrap().step, 2);
+
+        traits
+            .escalation_service
+            .set_escalation_policy("group_name".to_string(), admin, vec![])
+            .await?;
+
+        assert!(traits
+            .escalation_service
+            .get_escalation_policy("group_name".to_string())
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn edit_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "normal", None)
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let message = traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(traits
+            .message_service
+            .update_message(
+                message.id,
+                RoleMember::default(),
+                Some("subject".to_string()),
+                None,
+            )
+            .await
+            .is_err());
+        assert!(traits
+            .message_service
+            .update_message(message.id, admin.clone(), None, None)
+            .await
+            .is_err());
+        assert!(traits
+            .message_service
+            .update_message(
+                message.id + 1,
+                admin.clone(),
+                None,
+                Some("body".to_string())
+            )
+            .await
+            .is_err());
+
+        for body in ["first edit", "second edit"] {
+            traits
+                .message_service
+                .update_message(message.id, admin.clone(), None, Some(body.to_string()))
+                .await?;
+        }
+
+        let edits = traits
+            .message_service
+            .get_message_history(message.id)
+            .await?;
+        assert_eq!(
+            edits
+                .iter()
+                .map(|edit| edit.message.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["message", "first edit"]
+        );
+
+        let retracted_message = traits
+            .message_service
+            .retract_message(message.id, admin.clone())
+            .await?;
+        assert!(retracted_message.retracted_at.is_some());
+        assert_eq!(retracted_message.message, "second edit");
+
+        assert!(traits
+            .message_service
+            .retract_message(message.id, admin.clone())
+            .await
+            .is_err());
+        assert!(traits
+            .message_service
+            .update_message(message.id, admin, None, Some("third edit".to_string()))
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn thread_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        for channel in ["incidents", "alerts"] {
+            traits
+                .channel_repository
+                .add_channel(group.id, channel, "", "normal", None)
+                .await?;
+        }
+        let opened = traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "incidents".to_string(),
+                    subject: "incident".to_string(),
+                    message: "opened".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let invalid_threads = [
+            ("incidents", Some(opened.id), Some("incident-1")),
+            ("incidents", Some(opened.id + 1), None),
+            ("alerts", Some(opened.id), None),
+            ("incidents", None, Some(" ")),
+            ("incidents", None, Some(opened.thread.as_str())),
+        ];
+        for (channel, parent_id, thread_key) in invalid_threads {
+            assert!(traits
+                .message_service
+                .add_message(
+                    admin.clone(),
+                    MessagePost {
+                        channel: channel.to_string(),
+                        subject: "incident".to_string(),
+                        message: "reply".to_string(),
+                        thread: MessageThread {
+                            parent_id,
+                            thread_key: thread_key.map(String::from),
+                        },
+                        ..Default::default()
+                    },
+                )
+                .await
+                .is_err());
+        }
+
+        let resolved = traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "incidents".to_string(),
+                    subject: "incident".to_string(),
+                    message: "resolved".to_string(),
+                    thread: MessageThread {
+                        parent_id: Some(opened.id),
+                        thread_key: None,
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(resolved.parent_id, Some(opened.id));
+        assert_eq!(resolved.thread, opened.thread);
+
+        let thread = traits.message_service.get_thread(resolved.id).await?;
+        assert_eq!(
+            thread
+                .iter()
+                .map(|message| message.message.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["opened", "resolved"]
+        );
+
+        let reply_counts = traits
+            .message_service
+            .count_replies(&[opened.id, resolved.id], false)
+            .await?;
+        assert_eq!(reply_counts.get(&opened.id), Some(&1));
+        assert_eq!(reply_counts.get(&resolved.id), None);
+
+        let reply_counts = traits
+            .message_service
+            .count_replies(&[resolved.id], true)
+            .await?;
+        assert_eq!(reply_counts.get(&resolved.id), Some(&1));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn pin_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "normal", None)
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let mut message_ids = vec![];
+        for body in ["announcement", "update"] {
+            let message = traits
+                .message_service
+                .add_message(
+                    admin.clone(),
+                    MessagePost {
+                        channel: "alerts".to_string(),
+                        subject: "subject".to_string(),
+                        message: body.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            message_ids.push(message.id);
+        }
+
+        assert!(traits
+            .message_service
+            .pin_message(message_ids[0], RoleMember::default(), None)
+            .await
+            .is_err());
+        assert!(traits
+            .message_service
+            .pin_message(
+                message_ids[0],
+                admin.clone(),
+                Some(OffsetDateTime::now_utc() - Duration::hours(1)),
+            )
+            .await
+            .is_err());
+
+        let pinned_message = traits
+            .message_service
+            .pin_message(
+                message_ids[0],
+                admin.clone(),
+                Some(OffsetDateTime::now_utc() + Duration::hours(1)),
+            )
+            .await?;
+        assert!(pinned_message.is_pinned());
+
+        let query = MessageQuery {
+            channels: vec!["alerts".to_string()],
+            ..Default::default()
+        };
+        let messages = traits
+            .message_service
+            .get_messages(query.clone(), 0, 10)
+            .await?;
+        assert_eq!(messages.first().unwrap().message, "announcement");
+
+        traits
+            .message_service
+            .unpin_message(message_ids[0], admin.clone())
+            .await?;
+        assert!(traits
+            .message_service
+            .unpin_message(message_ids[0], admin)
+            .await
+            .is_err());
+
+        let messages = traits.message_service.get_messages(query, 0, 10).await?;
+        assert_eq!(messages.first().unwrap().message, "update");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_and_dismiss_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group = traits
+            .group_repository
+            .add_group("group_name", "admin_email", "token")
+            .await?;
+        traits
+            .channel_repository
+            .add_channel(group.id, "alerts", "", "normal", None)
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+        let mut message_ids = vec![];
+        for body in ["first", "second"] {
+            let message = traits
+                .message_service
+                .add_message(
+                    admin.clone(),
+                    MessagePost {
+                        channel: "alerts".to_string(),
+                        subject: "subject".to_string(),
+                        message: body.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            message_ids.push(message.id);
+        }
+
+        traits
+            .message_service
+            .dismiss_message(message_ids[0], 1)
+            .await?;
+        assert!(traits
+            .message_service
+            .dismiss_message(message_ids[0], 1)
+            .await
+            .is_err());
+
+        let query = MessageQuery {
+            channels: vec!["alerts".to_string()],
+            user_id: Some(1),
+            ..Default::default()
+        };
+        let messages = traits
+            .message_service
+            .get_messages(query.clone(), 0, 10)
+            .await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages.first().unwrap().message, "second");
+        assert_eq!(
+            traits
+                .message_service
+                .get_messages_count(MessageQuery {
+                    user_id: Some(2),
+                    ..query.clone()
+                })
+                .await?,
+            2
+        );
+
+        assert!(traits
+            .message_service
+            .delete_message(message_ids[1], RoleMember::default())
+            .await
+            .is_err());
+        traits
+            .message_service
+            .delete_message(message_ids[1], admin.clone())
+            .await?;
+        assert!(traits
+            .message_service
+            .delete_message(message_ids[1], admin.clone())
+            .await
+            .is_err());
+        assert_eq!(traits.message_service.get_messages_count(query).await?, 0);
+
+        // an audience that reached nobody still restricts dismissals
+        let unreached_message = traits
+            .message_service
+            .add_message(
+                admin,
+                MessagePost {
+                    channel: "alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: "group_name".to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(unreached_message.recipient_count, 0);
+        assert!(traits
+            .message_service
+            .dismiss_message(unreached_message.id, 1)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn channels_test(pool: PgPool) -> anyhow::Result<()> {
+        let traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        let other_group = traits
+            .group_repository
+            .add_group("other_group", "other_email", "token")
+            .await?;
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let invalid_priority_result = traits
+            .channel_service
+            .add_channel(
+                group_name.to_string(),
+                admin.clone(),
+                "alerts".to_string(),
+                String::new(),
+                "critical".to_string(),
+                None,
+            )
+            .await;
+
+        assert!(invalid_priority_result.is_err());
+
+        let channel = traits
+            .channel_service
+            .add_channel(
+                group_name.to_string(),
+                admin.clone(),
+                "alerts".to_string(),
+                String::new(),
+                String::new(),
+                Some(30),
+            )
+            .await?;
+
+        assert_eq!(channel.default_priority, "normal");
+        assert_eq!(channel.retention_days, Some(30));
+
+        let taken_name_result = traits
+            .channel_service
+            .add_channel(
+                "other_group".to_string(),
+                RoleMember {
+                    user_id: None,
+                    email: Some("other_email".to_string()),
+                },
+                "alerts".to_string(),
+                String::new(),
+                String::new(),
+                None,
+            )
+            .await;
+
+        assert!(taken_name_result.is_err());
+
+        let foreign_update_result = traits
+            .channel_service
+            .update_channel(
+                "alerts".to_string(),
+                RoleMember {
+                    user_id: None,
+                    email: Some("other_email".to_string()),
+                },
+                ChannelUpdate::default(),
+            )
+            .await;
+
+        assert!(foreign_update_result.is_err());
+
+        let channel = traits
+            .channel_service
+            .update_channel(
+                "alerts".to_string(),
+                admin.clone(),
+                ChannelUpdate {
+                    default_priority: Some("urgent".to_string()),
+                    retention_days: Some(None),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(channel.default_priority, "urgent");
+        assert!(channel.retention_days.is_none());
+
+        traits
+            .channel_repository
+            .add_channel(other_group.id, "other_alerts", "", "normal", None)
+            .await?;
+        let foreign_channel_result = traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "other_alerts".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    audience: Some(Audience {
+                        group: group_name.to_string(),
+                        filter: AudienceFilter::default(),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(foreign_channel_result.is_err());
+
+        traits
+            .channel_service
+            .remove_channel("alerts".to_string(), admin)
+            .await?;
+        let channels = traits
+            .channel_service
+            .list_channels(group_name.to_string())
+            .await?;
+
+        assert!(channels.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_message_with_audience_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "channel", "", "normal", None)
+            .await?;
+
+        for user_id in 0..3 {
+            all_traits
+                .subscriber_repository
+                .add_subscriber(user_id, &group)
+                .await?;
+        }
+
+        let audience = Audience {
+            group: group_name.to_string(),
+            filter: AudienceFilter {
+                exclude_user_ids: vec![1],
+                ..Default::default()
+            },
+        };
+        let added_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: "channel".to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    priority: "normal".to_string(),
+                    audience: Some(audience),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(added_message.group_id, Some(group.id));
+        assert_eq!(added_message.recipient_count, 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_message_fanout_preferences_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group_name = "group_name";
+        let channel = "channel";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, channel, "", "normal", None)
+            .await?;
+
+        let opted_out_id = 0;
+        let muted_id = 1;
+        let email_id = 2;
+        for user_id in [opted_out_id, muted_id, email_id] {
+            all_traits
+                .subscriber_repository
+                .add_subscriber(user_id, &group)
+                .await?;
+        }
+
+        all_traits
+            .subscriber_service
+            .update_preferences(
+                opted_out_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: None,
+                    opted_out_channels: vec![channel.to_string()],
+                    delivery_channels: vec!["email".to_string()],
+                    digest_mode: "off".to_string(),
+                },
+            )
+            .await?;
+        all_traits
+            .subscriber_service
+            .update_preferences(
+                muted_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: Some(OffsetDateTime::now_utc() + Duration::hours(1)),
+                    opted_out_channels: vec![],
+                    delivery_channels: vec!["email".to_string()],
+                    digest_mode: "off".to_string(),
+                },
+            )
+            .await?;
+        all_traits
+            .subscriber_service
+            .update_preferences(
+                email_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: None,
+                    opted_out_channels: vec![],
+                    delivery_channels: vec!["in_app".to_string(), "email".to_string()],
+                    digest_mode: "off".to_string(),
+                },
+            )
+            .await?;
+
+        let audience = Audience {
+            group: group_name.to_string(),
+            filter: AudienceFilter::default(),
+        };
+        let added_message = all_traits
+            .message_service
+            .add_message(
+                admin.clone(),
+                MessagePost {
+                    channel: channel.to_string(),
+                    subject: "subject".to_string(),
+                    message: "message".to_string(),
+                    priority: "normal".to_string(),
+                    audience: Some(audience),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(added_message.recipient_count, 2);
+
+        let deliveries = all_traits
+            .delivery_repository
+            .list_deliveries_by_message(added_message.id)
+            .await?;
+
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries.first().unwrap().user_id, email_id);
+        assert_eq!(deliveries.first().unwrap().delivery_channel, "email");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_preferences_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+
+        let sub_id = 0;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+
+        let invalid_preferences = all_traits
+            .subscriber_service
+            .update_preferences(
+                sub_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: None,
+                    opted_out_channels: vec![],
+                    delivery_channels: vec!["pigeon".to_string()],
+                    digest_mode: "off".to_string(),
+                },
+            )
+            .await;
+
+        assert!(invalid_preferences.is_err());
+
+        all_traits
+            .subscriber_service
+            .update_preferences(
+                sub_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: None,
+                    opted_out_channels: vec!["channel".to_string()],
+                    delivery_channels: vec!["push".to_string()],
+                    digest_mode: "off".to_string(),
+                },
+            )
+            .await?;
+
+        let subscriber = all_traits
+            .subscriber_service
+            .get_preferences(sub_id, group_name.to_string())
+            .await?;
+
+        assert_eq!(subscriber.opted_out_channels, vec!["channel".to_string()]);
+        assert_eq!(subscriber.delivery_channels, vec!["push".to_string()]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn quiet_hours_deferred_delivery_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+        let admin = RoleMember {
+            user_id: None,
+            email: Some("admin_email".to_string()),
+        };
+
+        let group_name = "group_name";
+        let group = all_traits
+            .group_repository
+            .add_group(group_name, "admin_email", "token")
+            .await?;
+        all_traits
+            .channel_repository
+            .add_channel(group.id, "channel", "", "normal", None)
+            .await?;
+
+        let sub_id = 0;
+        all_traits
+            .subscriber_repository
+            .add_subscriber(sub_id, &group)
+            .await?;
+        all_traits
+            .subscriber_service
+            .update_preferences(
+                sub_id,
+                group_name.to_string(),
+                SubscriberPreferences {
+                    muted_until: None,
+                    opted_out_channels: vec![],
+                    delivery_channels: vec!["push".to_string()],
+                    digest_mode: "off".to_string(),
+                },
+            )
+            .await?;
+
+        let now = OffsetDateTime::now_utc();
+        let start_minute = i32::from(now.hour()) * 60 + i32::from(now.minute());
+        let end_minute = (start_minute + 120) % (24 * 60);
+        all_traits
+            .subscriber_service
+            .set_quiet_hours(sub_id, start_minute, end_minute, "UTC".to_string())
+            .await?;
+
+        let mut deliver_after = Vec::new();
+        for priority in ["normal", "urgent"] {
+            let audience = Audience {
+                group: group_name.to_string(),
+                filter: AudienceFilter::default(),
+            };
+            let added_message = all_traits
+                .message_service
+                .add_message(
+                    admin.clone(),
+                    MessagePost {
+                        channel: "channel".to_string(),
+                        subject: "subject".to_string(),
+                        message: "message".to_string(),
+                        priority: priority.to_string(),
+                        audience: Some(audience),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+
+            assert_eq!(added_message.recipient_count, 1);
+
+            let deliveries = all_traits
+                .delivery_repository
+                .list_deliveries_by_message(added_message.id)
+                .await?;
+            deliver_after.push(deliveries.first().unwrap().deliver_after);
+        }
+
+        assert!(deliver_after[0] > now + Duration::minutes(60));
+        assert!(deliver_after[1] < now + Duration::minutes(1));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_quiet_hours_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let sub_id = 0;
+        let invalid_timezone = all_traits
+            .subscriber_service
+            .set_quiet_hours(sub_id, 22 * 60, 7 * 60, "Mars/Olympus_Mons".to_string())
+            .await;
+
+        assert!(invalid_timezone.is_err());
+
+        all_traits
+            .subscriber_service
+            .set_quiet_hours(sub_id, 22 * 60, 7 * 60, "Asia/Kuala_Lumpur".to_string())
+            .await?;
+
+        let quiet_hours = all_traits
+            .subscriber_service
+            .get_quiet_hours(sub_id)
+            .await?
+            .into_quiet_hours_response();
+
+        assert_eq!(quiet_hours.start_minute, 22 * 60);
+        assert_eq!(quiet_hours.end_minute, 7 * 60);
+        assert_eq!(quiet_hours.timezone, "Asia/Kuala_Lumpur");
+
+        all_traits
+            .subscriber_service
+            .clear_quiet_hours(sub_id)
+            .await?;
+
+        assert!(all_traits
+            .subscriber_service
+            .get_quiet_hours(sub_id)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn channel_patterns_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        assert!(all_traits
+            .message_service
+            .get_messages(
+                MessageQuery {
+                    patterns: vec!["billing..*".to_string()],
+                    ..Default::default()
+                },
+                0,
+                50,
+            )
+            .await
+            .is_err());
+        assert!(all_traits
+            .message_service
+            .stream_messages(Some(1), vec![])
+            .await
+            .is_err());
+
+        let subscription = all_traits
+            .message_service
+            .subscribe_channel_pattern(1, "alerts.#.#".to_string())
+            .await?;
+        assert_eq!(subscription.pattern, "alerts.#");
+
+        assert!(all_traits
+            .message_service
+            .subscribe_channel_pattern(1, "alerts.#".to_string())
+            .await
+            .is_err());
+
+        let message_stream = all_traits
+            .message_service
+            .stream_messages(Some(1), vec!["billing.*".to_string()])
+            .await?;
+        assert_eq!(message_stream.patterns.len(), 2);
+
+        all_traits
+            .message_service
+            .unsubscribe_channel_pattern(1, "alerts.#".to_string())
+            .await?;
+        assert!(all_traits
+            .message_service
+            .unsubscribe_channel_pattern(1, "alerts.#".to_string())
+            .await
+            .is_err());
+        assert!(all_traits
+            .message_service
+            .list_channel_patterns(1)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_messages_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let channel = "channel1";
+        let channels = vec![channel.to_string()];
+        let message = "test_message";
+
+        all_traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+        all_traits
+            .message_repository
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
+            .await?;
+
+        let obtained_messages = all_traits
+            .message_service
+            .get_messages(
+                MessageQuery {
+                    channels,
+                    ..Default::default()
+                },
+                0,
+                50,
+            )
             .await?;
 
         assert_eq!(obtained_messages.len(), 2);
@@ -299,6 +2432,65 @@ pub mod test {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn get_messages_subscribed_patterns_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        for channel in ["alerts.db.down", "billing.invoice"] {
+            all_traits
+                .message_repository
+                .add_message(
+                    &NewMessage {
+                        channel,
+                        subject: "subject",
+                        message: "message",
+                        priority: "normal",
+                        ..Default::default()
+                    },
+                    &MessageFanout::default(),
+                )
+                .await?;
+        }
+        all_traits
+            .message_service
+            .subscribe_channel_pattern(1, "alerts.#".to_string())
+            .await?;
+
+        // saved patterns are matched along with the requested channels
+        let query = MessageQuery {
+            channels: vec!["billing.invoice".to_string()],
+            user_id: Some(1),
+            ..Default::default()
+        };
+        let obtained_messages = all_traits
+            .message_service
+            .get_messages(query.clone(), 0, 50)
+            .await?;
+
+        assert_eq!(obtained_messages.len(), 2);
+        assert_eq!(
+            all_traits.message_service.get_messages_count(query).await?,
+            2
+        );
+
+        let query = MessageQuery {
+            user_id: Some(2),
+            ..Default::default()
+        };
+        assert!(all_traits
+            .message_service
+            .get_messages(query.clone(), 0, 50)
+            .await?
+            .is_empty());
+        assert_eq!(
+            all_traits.message_service.get_messages_count(query).await?,
+            0
+        );
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn clear_messages_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
@@ -308,7 +2500,16 @@ pub mod test {
         let message = "test_message";
         let first_message = all_traits
             .message_repository
-            .add_message(channel, "subject", "message")
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message: "message",
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         let first_message_time = first_message.created_at;
@@ -317,7 +2518,16 @@ pub mod test {
 
         all_traits
             .message_repository
-            .add_message(channel, "subject", message)
+            .add_message(
+                &NewMessage {
+                    channel,
+                    subject: "subject",
+                    message,
+                    priority: "normal",
+                    ..Default::default()
+                },
+                &MessageFanout::default(),
+            )
             .await?;
 
         all_traits
@@ -327,7 +2537,14 @@ pub mod test {
 
         let left_messages = all_traits
             .message_repository
-            .get_messages(channels, 0, 50)
+            .get_messages(
+                &MessageFilter {
+                    channels,
+                    ..Default::default()
+                },
+                0,
+                50,
+            )
             .await?;
 
         assert_eq!(left_messages.len(), 1);
@@ -1,19 +1,261 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use async_trait::async_trait;
-use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    notification::SubscriptionResultResponse,
+};
+use sqlx::types::time::{OffsetDateTime, Time};
 use tracing::log::{error, info};
 
-use crate::repository::{
-    group::DynGroupRepositoryTrait,
-    subscriber::{DynSubscriberRepositoryTrait, SubscriberEntity},
+use crate::{
+    repository::{
+        delivery::DELIVERY_CHANNELS,
+        group::{
+            DynGroupRepositoryTrait, GroupEntity, GROUP_VISIBILITY_INVITE_ONLY,
+            GROUP_VISIBILITY_PUBLIC,
+        },
+        invitation::{DynInvitationRepositoryTrait, InvitationEntity},
+        join_request::{
+            DynJoinRequestRepositoryTrait, JoinRequestEntity, JOIN_REQUEST_APPROVED,
+            JOIN_REQUEST_PENDING, JOIN_REQUEST_REJECTED,
+        },
+        quiet_hours::{DynQuietHoursRepositoryTrait, QuietHoursEntity},
+        role::{DynRoleRepositoryTrait, GroupRole, RoleMember},
+        subscriber::{
+            DynSubscriberRepositoryTrait, SubscriberCursor, SubscriberEntity, SubscriberPage,
+            SubscriberPreferences, TagFilter, DIGEST_MODES, DIGEST_MODE_OFF,
+            SUBSCRIBER_SORT_CREATED_AT, SUBSCRIBER_SORT_FIELDS,
+        },
+    },
+    service::group::authorize,
 };
 
+const MINUTES_IN_DAY: i32 = 24 * 60;
+
+fn minute_to_time(minute: i32) -> ServiceResult<Time> {
+    if !(0..MINUTES_IN_DAY).contains(&minute) {
+        error!("quiet hours minute {:?} is out of range", minute);
+        return Err(ServiceError::ObjectConflict(String::from(
+            "quiet hours must be within a day",
+        )));
+    }
+
+    let time =
+        Time::from_hms((minute / 60) as u8, (minute % 60) as u8, 0).map_err(anyhow::Error::from)?;
+    Ok(time)
+}
+
+/// Upper bound of user and group pairs handled by a single bulk request.
+const MAX_BULK_SUBSCRIPTIONS: usize = 1000;
+
+/// Trims the tags and drops the empty and repeated ones, keeping their order.
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut seen_tags = HashSet::new();
+    tags.into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty() && seen_tags.insert(tag.clone()))
+        .collect()
+}
+
+const DEFAULT_SUBSCRIBER_PAGE_SIZE: i64 = 50;
+const MAX_SUBSCRIBER_PAGE_SIZE: i64 = 500;
+
+/// A page of subscribers, `next_cursor` is only set when more subscribers
+/// follow the page.
+pub struct SubscribersPage {
+    pub subscribers: Vec<SubscriberEntity>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubscriptionOutcome {
+    Subscribed,
+    AlreadySubscribed,
+    Unsubscribed,
+    NotSubscribed,
+    GroupNotFound,
+}
+
+impl SubscriptionOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionOutcome::Subscribed => "subscribed",
+            SubscriptionOutcome::AlreadySubscribed => "already_subscribed",
+            SubscriptionOutcome::Unsubscribed => "unsubscribed",
+            SubscriptionOutcome::NotSubscribed => "not_subscribed",
+            SubscriptionOutcome::GroupNotFound => "group_not_found",
+        }
+    }
+}
+
+pub struct SubscriptionResult {
+    pub user_id: i64,
+    pub group: String,
+    pub outcome: SubscriptionOutcome,
+}
+
+impl SubscriptionResult {
+    pub fn into_subscription_result_response(self) -> SubscriptionResultResponse {
+        SubscriptionResultResponse {
+            user_id: self.user_id,
+            group: self.group,
+            outcome: self.outcome.as_str().to_string(),
+        }
+    }
+}
+
+/// Changes an import made, or would make on a dry run, to the subscribers of
+/// a group.
+pub struct ImportDiff {
+    pub added: Vec<i64>,
+    pub removed: Vec<i64>,
+    pub unchanged: i64,
+}
+
+/// Pairs every user with every group, `changed` holds the subscriptions that
+/// were actually inserted or deleted.
+fn bulk_results(
+    user_ids: &[i64],
+    group_names: &[String],
+    groups: &[GroupEntity],
+    changed: &[SubscriberEntity],
+    changed_outcome: SubscriptionOutcome,
+    unchanged_outcome: SubscriptionOutcome,
+) -> Vec<SubscriptionResult> {
+    let mut results = Vec::with_capacity(user_ids.len() * group_names.len());
+
+    for group_name in group_names {
+        let group = groups.iter().find(|group| &group.name == group_name);
+        for user_id in user_ids {
+            let outcome = match group {
+                Some(group) => {
+                    let is_changed = changed
+                        .iter()
+                        .any(|sub| sub.user_id == *user_id && sub.group_id == group.id);
+                    if is_changed {
+                        changed_outcome
+                    } else {
+                        unchanged_outcome
+                    }
+                }
+                None => SubscriptionOutcome::GroupNotFound,
+            };
+            results.push(SubscriptionResult {
+                user_id: *user_id,
+                group: group_name.clone(),
+                outcome,
+            });
+        }
+    }
+
+    results
+}
+
 #[async_trait]
 pub trait SubscriberServiceTrait {
     async fn list_subs_by_group(&self, group_name: String) -> ServiceResult<Vec<SubscriberEntity>>;
-    async fn add_subscriber(&self, user_id: i64, group_name: String) -> ServiceResult<()>;
+    async fn get_subscribers(
+        &self,
+        group_name: String,
+        cursor: String,
+        limit: i64,
+        sort_by: String,
+        descending: bool,
+        tags: TagFilter,
+    ) -> ServiceResult<SubscribersPage>;
+    async fn count_subscribers(&self, group_name: String, tags: TagFilter) -> ServiceResult<i64>;
+    async fn add_subscriber(
+        &self,
+        user_id: i64,
+        group_name: String,
+        invitation_code: Option<String>,
+    ) -> ServiceResult<SubscriptionOutcome>;
+    async fn create_invitation(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        expires_at: Option<OffsetDateTime>,
+        max_uses: Option<i32>,
+    ) -> ServiceResult<InvitationEntity>;
+    async fn set_subscription_expiry(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_id: i64,
+        expires_at: Option<OffsetDateTime>,
+    ) -> ServiceResult<SubscriberEntity>;
+    async fn set_subscriber_tags(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_id: i64,
+        tags: Vec<String>,
+    ) -> ServiceResult<SubscriberEntity>;
+    async fn clear_subscriber_tags(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_id: i64,
+    ) -> ServiceResult<SubscriberEntity>;
+    async fn submit_join_request(
+        &self,
+        user_id: i64,
+        group_name: String,
+    ) -> ServiceResult<JoinRequestEntity>;
+    async fn list_join_requests(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+    ) -> ServiceResult<Vec<JoinRequestEntity>>;
+    async fn decide_join_request(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        join_request_id: i64,
+        approve: bool,
+    ) -> ServiceResult<JoinRequestEntity>;
     async fn remove_subscriber(&self, user_id: i64, group_name: String) -> ServiceResult<()>;
+    async fn get_preferences(
+        &self,
+        user_id: i64,
+        group_name: String,
+    ) -> ServiceResult<SubscriberEntity>;
+    async fn update_preferences(
+        &self,
+        user_id: i64,
+        group_name: String,
+        preferences: SubscriberPreferences,
+    ) -> ServiceResult<SubscriberEntity>;
+    async fn get_quiet_hours(&self, user_id: i64) -> ServiceResult<QuietHoursEntity>;
+    async fn set_quiet_hours(
+        &self,
+        user_id: i64,
+        start_minute: i32,
+        end_minute: i32,
+        timezone: String,
+    ) -> ServiceResult<QuietHoursEntity>;
+    async fn clear_quiet_hours(&self, user_id: i64) -> ServiceResult<()>;
+    async fn add_subscribers(
+        &self,
+        actor: RoleMember,
+        user_ids: Vec<i64>,
+        group_names: Vec<String>,
+    ) -> ServiceResult<Vec<SubscriptionResult>>;
+    async fn remove_subscribers(
+        &self,
+        actor: RoleMember,
+        user_ids: Vec<i64>,
+        group_names: Vec<String>,
+    ) -> ServiceResult<Vec<SubscriptionResult>>;
+    async fn import_subscribers(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_ids: Vec<i64>,
+        replace: bool,
+        dry_run: bool,
+    ) -> ServiceResult<ImportDiff>;
 }
 
 pub type DynSubscriberServiceTrait = Arc<dyn SubscriberServiceTrait + Sync + Send>;
@@ -21,18 +263,80 @@ pub type DynSubscriberServiceTrait = Arc<dyn SubscriberServiceTrait + Sync + Sen
 pub struct SubscriberService {
     subscriber_repository: DynSubscriberRepositoryTrait,
     group_repository: DynGroupRepositoryTrait,
+    quiet_hours_repository: DynQuietHoursRepositoryTrait,
+    role_repository: DynRoleRepositoryTrait,
+    invitation_repository: DynInvitationRepositoryTrait,
+    join_request_repository: DynJoinRequestRepositoryTrait,
 }
 
 impl SubscriberService {
     pub fn new(
         subscriber_repository: DynSubscriberRepositoryTrait,
         group_repository: DynGroupRepositoryTrait,
+        quiet_hours_repository: DynQuietHoursRepositoryTrait,
+        role_repository: DynRoleRepositoryTrait,
+        invitation_repository: DynInvitationRepositoryTrait,
+        join_request_repository: DynJoinRequestRepositoryTrait,
     ) -> Self {
         Self {
             subscriber_repository,
             group_repository,
+            quiet_hours_repository,
+            role_repository,
+            invitation_repository,
+            join_request_repository,
+        }
+    }
+
+    async fn existing_group(&self, group_name: &str) -> ServiceResult<GroupEntity> {
+        match self.group_repository.get_group(group_name).await? {
+            Some(group) => Ok(group),
+            None => {
+                error!("group {:?} does not exists", group_name);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "group name does not exist",
+                )))
+            }
         }
     }
+
+    /// Deduplicates the requested users and groups, and resolves the groups
+    /// that exist. Subscribing other users is left to the admins of every
+    /// resolved group.
+    async fn prepare_bulk(
+        &self,
+        actor: &RoleMember,
+        user_ids: &mut Vec<i64>,
+        group_names: &mut Vec<String>,
+    ) -> ServiceResult<Vec<GroupEntity>> {
+        user_ids.sort_unstable();
+        user_ids.dedup();
+        group_names.sort_unstable();
+        group_names.dedup();
+
+        if user_ids.len() * group_names.len() > MAX_BULK_SUBSCRIPTIONS {
+            error!(
+                "bulk request of {} users and {} groups is too large",
+                user_ids.len(),
+                group_names.len()
+            );
+            return Err(ServiceError::ObjectConflict(format!(
+                "a bulk request can not exceed {} subscriptions",
+                MAX_BULK_SUBSCRIPTIONS
+            )));
+        }
+
+        let groups = self
+            .group_repository
+            .get_groups_by_names(group_names)
+            .await?;
+
+        for group in groups.iter() {
+            authorize(&self.role_repository, group, actor, GroupRole::Admin).await?;
+        }
+
+        Ok(groups)
+    }
 }
 
 #[async_trait]
@@ -60,18 +364,98 @@ impl SubscriberServiceTrait for SubscriberService {
         }
     }
 
-    async fn add_subscriber(&self, user_id: i64, group_name: String) -> ServiceResult<()> {
+    async fn get_subscribers(
+        &self,
+        group_name: String,
+        cursor: String,
+        limit: i64,
+        sort_by: String,
+        descending: bool,
+        tags: TagFilter,
+    ) -> ServiceResult<SubscribersPage> {
+        let existing_group = self.group_repository.get_group(&group_name).await?;
+
+        let group = match existing_group {
+            Some(group) => group,
+            None => {
+                error!("group {:?} does not exists", &group_name);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group name does not exist",
+                )));
+            }
+        };
+
+        let sort_by = if sort_by.is_empty() {
+            String::from(SUBSCRIBER_SORT_CREATED_AT)
+        } else {
+            sort_by
+        };
+        if !SUBSCRIBER_SORT_FIELDS.contains(&sort_by.as_str()) {
+            error!("subscribers can not be sorted by {:?}", &sort_by);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "unknown subscriber sort field",
+            )));
+        }
+
+        let cursor = if cursor.is_empty() {
+            None
+        } else {
+            match SubscriberCursor::decode(&cursor) {
+                Some(cursor) => Some(cursor),
+                None => {
+                    error!("subscriber cursor {:?} is malformed", &cursor);
+                    return Err(ServiceError::ObjectConflict(String::from(
+                        "invalid subscriber cursor",
+                    )));
+                }
+            }
+        };
+
+        let limit = if limit <= 0 {
+            DEFAULT_SUBSCRIBER_PAGE_SIZE
+        } else {
+            limit.min(MAX_SUBSCRIBER_PAGE_SIZE)
+        };
+
+        info!("paging subscribers from group {:?}", &group_name);
+        // one extra subscriber is fetched to know whether another page follows
+        let page = SubscriberPage {
+            cursor,
+            limit: limit + 1,
+            sort_by,
+            descending,
+            tags,
+        };
+        let mut subscribers = self
+            .subscriber_repository
+            .list_subs_page(&group, &page)
+            .await?;
+
+        let next_cursor = if subscribers.len() as i64 > limit {
+            subscribers.truncate(limit as usize);
+            subscribers.last().map(|sub| sub.cursor().encode())
+        } else {
+            None
+        };
+
+        info!("successfully obtained page of subscribers from group");
+        Ok(SubscribersPage {
+            subscribers,
+            next_cursor,
+        })
+    }
+
+    async fn count_subscribers(&self, group_name: String, tags: TagFilter) -> ServiceResult<i64> {
         let existing_group = self.group_repository.get_group(&group_name).await?;
 
         match existing_group {
             Some(group) => {
-                info!("add subscriber into group {:?}", &group_name);
-                self.subscriber_repository
-                    .add_subscriber(user_id, &group)
+                let count = self
+                    .subscriber_repository
+                    .count_subs_by_group(&group, &tags)
                     .await?;
 
-                info!("successfully added subscriber into group");
-                Ok(())
+                Ok(count)
             }
             None => {
                 error!("group {:?} does not exists", &group_name);
@@ -82,10 +466,535 @@ impl SubscriberServiceTrait for SubscriberService {
         }
     }
 
+    async fn add_subscriber(
+        &self,
+        user_id: i64,
+        group_name: String,
+        invitation_code: Option<String>,
+    ) -> ServiceResult<SubscriptionOutcome> {
+        let group = self.existing_group(&group_name).await?;
+
+        let existing_subscriber = self
+            .subscriber_repository
+            .get_subscriber(user_id, &group)
+            .await?;
+        if existing_subscriber.is_some() {
+            info!("subscriber is already in group {:?}", &group_name);
+            return Ok(SubscriptionOutcome::AlreadySubscribed);
+        }
+
+        match group.visibility.as_str() {
+            GROUP_VISIBILITY_PUBLIC => {}
+            GROUP_VISIBILITY_INVITE_ONLY => {
+                let code = invitation_code.unwrap_or_default();
+
+                info!("add invited subscriber into group {:?}", &group_name);
+                let redeemed_invitation = self
+                    .invitation_repository
+                    .redeem_invitation(group.id, user_id, &code, OffsetDateTime::now_utc())
+                    .await?;
+
+                if redeemed_invitation.is_none() {
+                    error!("invalid invitation code used for group {:?}", &group_name);
+                    return Err(ServiceError::ObjectConflict(String::from(
+                        "invalid invitation code",
+                    )));
+                }
+
+                info!("successfully added subscriber into group");
+                return Ok(SubscriptionOutcome::Subscribed);
+            }
+            _ => {
+                error!("group {:?} is closed to new subscribers", &group_name);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group is closed",
+                )));
+            }
+        }
+
+        info!("add subscriber into group {:?}", &group_name);
+        let added_subscriber = self
+            .subscriber_repository
+            .add_subscriber(user_id, &group)
+            .await?;
+
+        match added_subscriber {
+            Some(_) => {
+                info!("successfully added subscriber into group");
+                Ok(SubscriptionOutcome::Subscribed)
+            }
+            None => {
+                info!("subscriber is already in group {:?}", &group_name);
+                Ok(SubscriptionOutcome::AlreadySubscribed)
+            }
+        }
+    }
+
+    async fn create_invitation(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        expires_at: Option<OffsetDateTime>,
+        max_uses: Option<i32>,
+    ) -> ServiceResult<InvitationEntity> {
+        if max_uses.is_some_and(|max_uses| max_uses <= 0) {
+            error!("invitation max uses {:?} is not positive", max_uses);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "invitation max uses must be positive",
+            )));
+        }
+
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        info!("creating invitation for group {:?}", &group_name);
+        let invitation = self
+            .invitation_repository
+            .add_invitation(group.id, expires_at, max_uses)
+            .await?;
+
+        info!("invitation successfully created");
+        Ok(invitation)
+    }
+
+    async fn set_subscription_expiry(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_id: i64,
+        expires_at: Option<OffsetDateTime>,
+    ) -> ServiceResult<SubscriberEntity> {
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        info!(
+            "setting subscription expiry of {:?} in group {:?}",
+            user_id, &group_name
+        );
+        match self
+            .subscriber_repository
+            .update_expiry(user_id, &group, expires_at)
+            .await?
+        {
+            Some(subscriber) => Ok(subscriber),
+            None => Err(ServiceError::NotFound(String::from(
+                "user is not subscribed to the group",
+            ))),
+        }
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_id: i64,
+        tags: Vec<String>,
+    ) -> ServiceResult<SubscriberEntity> {
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        info!("tagging {:?} in group {:?}", user_id, &group_name);
+        match self
+            .subscriber_repository
+            .set_tags(user_id, &group, &normalize_tags(tags))
+            .await?
+        {
+            Some(subscriber) => Ok(subscriber),
+            None => Err(ServiceError::NotFound(String::from(
+                "user is not subscribed to the group",
+            ))),
+        }
+    }
+
+    async fn clear_subscriber_tags(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        user_id: i64,
+    ) -> ServiceResult<SubscriberEntity> {
+        self.set_subscriber_tags(group_name, actor, user_id, vec![])
+            .await
+    }
+
+    async fn submit_join_request(
+        &self,
+        user_id: i64,
+        group_name: String,
+    ) -> ServiceResult<JoinRequestEntity> {
+        let group = self.existing_group(&group_name).await?;
+
+        // public groups are joined directly and closed groups not at all
+        if group.visibility != GROUP_VISIBILITY_INVITE_ONLY {
+            error!("group {:?} does not accept join requests", &group_name);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "group does not accept join requests",
+            )));
+        }
+
+        let existing_subscriber = self
+            .subscriber_repository
+            .get_subscriber(user_id, &group)
+            .await?;
+        if existing_subscriber.is_some() {
+            error!("user {:?} is already in group {:?}", user_id, &group_name);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "user is already subscribed",
+            )));
+        }
+
+        info!("submitting join request for group {:?}", &group_name);
+        let join_request = self
+            .join_request_repository
+            .add_join_request(group.id, user_id)
+            .await?;
+
+        match join_request {
+            Some(join_request) => {
+                info!("join request successfully submitted");
+                Ok(join_request)
+            }
+            None => {
+                error!("user {:?} already asked to join {:?}", user_id, &group_name);
+                Err(ServiceError::ObjectConflict(String::from(
+                    "join request is already pending",
+                )))
+            }
+        }
+    }
+
+    async fn list_join_requests(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+    ) -> ServiceResult<Vec<JoinRequestEntity>> {
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        let join_requests = self
+            .join_request_repository
+            .list_join_requests(group.id, JOIN_REQUEST_PENDING)
+            .await?;
+
+        Ok(join_requests)
+    }
+
+    async fn decide_join_request(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        join_request_id: i64,
+        approve: bool,
+    ) -> ServiceResult<JoinRequestEntity> {
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        let status = if approve {
+            JOIN_REQUEST_APPROVED
+        } else {
+            JOIN_REQUEST_REJECTED
+        };
+
+        info!("deciding join request {:?} as {}", join_request_id, status);
+        let join_request = self
+            .join_request_repository
+            .decide_join_request(join_request_id, group.id, status)
+            .await?;
+
+        match join_request {
+            Some(join_request) => {
+                info!("join request successfully decided");
+                Ok(join_request)
+            }
+            None => {
+                error!("pending join request {:?} not found", join_request_id);
+                Err(ServiceError::NotFound(String::from(
+                    "pending join request not found",
+                )))
+            }
+        }
+    }
+
     async fn remove_subscriber(&self, user_id: i64, group_name: String) -> ServiceResult<()> {
         self.subscriber_repository
             .remove_subscriber(user_id, &group_name)
             .await?;
         Ok(())
     }
+
+    async fn get_preferences(
+        &self,
+        user_id: i64,
+        group_name: String,
+    ) -> ServiceResult<SubscriberEntity> {
+        let existing_group = self.group_repository.get_group(&group_name).await?;
+
+        let group = match existing_group {
+            Some(group) => group,
+            None => {
+                error!("group {:?} does not exists", &group_name);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group name does not exist",
+                )));
+            }
+        };
+
+        match self
+            .subscriber_repository
+            .get_subscriber(user_id, &group)
+            .await?
+        {
+            Some(subscriber) => Ok(subscriber),
+            None => Err(ServiceError::NotFound(String::from(
+                "user is not subscribed to the group",
+            ))),
+        }
+    }
+
+    async fn update_preferences(
+        &self,
+        user_id: i64,
+        group_name: String,
+        mut preferences: SubscriberPreferences,
+    ) -> ServiceResult<SubscriberEntity> {
+        if preferences.digest_mode.is_empty() {
+            preferences.digest_mode = String::from(DIGEST_MODE_OFF);
+        }
+
+        if let Some(unknown_channel) = preferences
+            .delivery_channels
+            .iter()
+            .find(|channel| !DELIVERY_CHANNELS.contains(&channel.as_str()))
+        {
+            error!("unknown delivery channel {:?}", unknown_channel);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "unknown delivery channel",
+            )));
+        }
+
+        if !DIGEST_MODES.contains(&preferences.digest_mode.as_str()) {
+            error!("unknown digest mode {:?}", &preferences.digest_mode);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "unknown digest mode",
+            )));
+        }
+
+        let existing_group = self.group_repository.get_group(&group_name).await?;
+
+        let group = match existing_group {
+            Some(group) => group,
+            None => {
+                error!("group {:?} does not exists", &group_name);
+                return Err(ServiceError::ObjectConflict(String::from(
+                    "group name does not exist",
+                )));
+            }
+        };
+
+        info!(
+            "updating preferences of subscriber in group {:?}",
+            &group_name
+        );
+        let updated_subscriber = self
+            .subscriber_repository
+            .update_preferences(user_id, &group, &preferences)
+            .await?;
+
+        match updated_subscriber {
+            Some(subscriber) => {
+                info!("successfully updated subscriber preferences");
+                Ok(subscriber)
+            }
+            None => Err(ServiceError::NotFound(String::from(
+                "user is not subscribed to the group",
+            ))),
+        }
+    }
+
+    async fn get_quiet_hours(&self, user_id: i64) -> ServiceResult<QuietHoursEntity> {
+        match self.quiet_hours_repository.get_quiet_hours(user_id).await? {
+            Some(quiet_hours) => Ok(quiet_hours),
+            None => Err(ServiceError::NotFound(String::from(
+                "quiet hours are not set",
+            ))),
+        }
+    }
+
+    async fn set_quiet_hours(
+        &self,
+        user_id: i64,
+        start_minute: i32,
+        end_minute: i32,
+        timezone: String,
+    ) -> ServiceResult<QuietHoursEntity> {
+        if start_minute == end_minute {
+            error!("quiet hours of user {:?} start and end together", user_id);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "quiet hours must not start and end at the same time",
+            )));
+        }
+
+        let start_time = minute_to_time(start_minute)?;
+        let end_time = minute_to_time(end_minute)?;
+
+        if !self
+            .quiet_hours_repository
+            .is_valid_timezone(&timezone)
+            .await?
+        {
+            error!("unknown timezone {:?}", &timezone);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "unknown timezone",
+            )));
+        }
+
+        info!("setting quiet hours of user {:?}", user_id);
+        let quiet_hours = self
+            .quiet_hours_repository
+            .set_quiet_hours(user_id, start_time, end_time, &timezone)
+            .await?;
+
+        info!("successfully set quiet hours");
+        Ok(quiet_hours)
+    }
+
+    async fn clear_quiet_hours(&self, user_id: i64) -> ServiceResult<()> {
+        self.quiet_hours_repository
+            .remove_quiet_hours(user_id)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_subscribers(
+        &self,
+        actor: RoleMember,
+        mut user_ids: Vec<i64>,
+        mut group_names: Vec<String>,
+    ) -> ServiceResult<Vec<SubscriptionResult>> {
+        let groups = self
+            .prepare_bulk(&actor, &mut user_ids, &mut group_names)
+            .await?;
+
+        info!(
+            "adding {} subscribers into {} groups",
+            user_ids.len(),
+            groups.len()
+        );
+        let added_subscribers = self
+            .subscriber_repository
+            .add_subscribers(&user_ids, &groups)
+            .await?;
+
+        info!(
+            "successfully added {} subscriptions",
+            added_subscribers.len()
+        );
+        Ok(bulk_results(
+            &user_ids,
+            &group_names,
+            &groups,
+            &added_subscribers,
+            SubscriptionOutcome::Subscribed,
+            SubscriptionOutcome::AlreadySubscribed,
+        ))
+    }
+
+    async fn remove_subscribers(
+        &self,
+        actor: RoleMember,
+        mut user_ids: Vec<i64>,
+        mut group_names: Vec<String>,
+    ) -> ServiceResult<Vec<SubscriptionResult>> {
+        let groups = self
+            .prepare_bulk(&actor, &mut user_ids, &mut group_names)
+            .await?;
+
+        info!(
+            "removing {} subscribers from {} groups",
+            user_ids.len(),
+            groups.len()
+        );
+        let removed_subscribers = self
+            .subscriber_repository
+            .remove_subscribers(&user_ids, &groups)
+            .await?;
+
+        info!(
+            "successfully removed {} subscriptions",
+            removed_subscribers.len()
+        );
+        Ok(bulk_results(
+            &user_ids,
+            &group_names,
+            &groups,
+            &removed_subscribers,
+            SubscriptionOutcome::Unsubscribed,
+            SubscriptionOutcome::NotSubscribed,
+        ))
+    }
+
+    async fn import_subscribers(
+        &self,
+        group_name: String,
+        actor: RoleMember,
+        mut user_ids: Vec<i64>,
+        replace: bool,
+        dry_run: bool,
+    ) -> ServiceResult<ImportDiff> {
+        let group = self.existing_group(&group_name).await?;
+        authorize(&self.role_repository, &group, &actor, GroupRole::Admin).await?;
+
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let existing_user_ids = self
+            .subscriber_repository
+            .list_subs_by_group(&group)
+            .await?
+            .into_iter()
+            .map(|sub| sub.user_id)
+            .collect::<HashSet<i64>>();
+
+        let (unchanged, added): (Vec<i64>, Vec<i64>) = user_ids
+            .iter()
+            .partition(|user_id| existing_user_ids.contains(*user_id));
+        let removed = if replace {
+            let imported_user_ids = user_ids.iter().collect::<HashSet<&i64>>();
+            let mut removed = existing_user_ids
+                .into_iter()
+                .filter(|user_id| !imported_user_ids.contains(user_id))
+                .collect::<Vec<i64>>();
+            removed.sort_unstable();
+            removed
+        } else {
+            Vec::new()
+        };
+
+        let diff = ImportDiff {
+            added,
+            removed,
+            unchanged: unchanged.len() as i64,
+        };
+
+        if dry_run {
+            info!(
+                "dry run import into group {:?} would add {} and remove {} subscribers",
+                &group_name,
+                diff.added.len(),
+                diff.removed.len()
+            );
+            return Ok(diff);
+        }
+
+        info!("importing subscribers into group {:?}", &group_name);
+        self.subscriber_repository
+            .import_subscribers(&group, &diff.added, &diff.removed)
+            .await?;
+
+        info!(
+            "successfully imported subscribers, added {} and removed {}",
+            diff.added.len(),
+            diff.removed.len()
+        );
+        Ok(diff)
+    }
 }